@@ -0,0 +1,157 @@
+use crate::ocr::area_pixels;
+use crate::timeline::Coordinates;
+use base64::Engine;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::io::ErrorKind;
+use std::process::Command;
+
+const ZBARIMG: &str = "zbarimg";
+// codes on screen have few pixels per module, ZBar finds them more easily when enlarged
+const SCAN_SCALE: u32 = 2;
+// exit status of zbarimg when the image has no codes
+const NO_CODES: i32 = 4;
+
+/* QR code or barcode found by ZBar, with its bounding box in pixels of the image */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Barcode {
+    // symbology as named by ZBar, e.g. QR-Code or EAN-13
+    pub kind: String,
+    pub payload: String,
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Barcode {
+    /* one line for lists, e.g. "QR-Code: https://example.com" */
+    pub fn describe(&self) -> String {
+        format!("{}: {}", self.kind, self.payload)
+    }
+}
+
+/* symbols of the XML output of zbarimg; the box is the one of the polygon of the symbol,
+or the whole image of width x height for the versions of ZBar that do not give it */
+pub fn parse_zbar_xml(xml: &str, width: u32, height: u32) -> Vec<Barcode> {
+    xml.split("<symbol")
+        .skip(1)
+        .filter_map(|symbol| {
+            let symbol = &symbol[..symbol.find("</symbol>")?];
+            let kind = attribute(symbol, "type")?;
+            let data_start = symbol.find("<data")?;
+            let data = &symbol[data_start..];
+            let content_start = data.find("<![CDATA[")? + "<![CDATA[".len();
+            let content_end = data.find("]]>")?;
+            let content = &data[content_start..content_end];
+            let head = &data[..data.find('>')?];
+            let payload = if attribute(head, "format").as_deref() == Some("base64") {
+                let bytes = base64::engine::general_purpose::STANDARD
+                    .decode(content.trim())
+                    .ok()?;
+                String::from_utf8_lossy(&bytes).to_string()
+            } else {
+                content.to_string()
+            };
+            let (left, top, right, bottom) = attribute(symbol, "points")
+                .and_then(|points| polygon_bounds(&points))
+                .unwrap_or((0, 0, width, height));
+            Some(Barcode {
+                kind,
+                payload,
+                left,
+                top,
+                width: right.saturating_sub(left).max(1),
+                height: bottom.saturating_sub(top).max(1),
+            })
+        })
+        .collect()
+}
+
+/* value of name='...' (or name="...") in the tag */
+fn attribute(tag: &str, name: &str) -> Option<String> {
+    let start = tag.find(&format!(" {}=", name))? + name.len() + 2;
+    let quote = tag[start..].chars().next()?;
+    let value = &tag[start + 1..];
+    Some(value[..value.find(quote)?].to_string())
+}
+
+/* left, top, right and bottom of points like "+12,10 +12,80 +82,80", clamped at 0 */
+fn polygon_bounds(points: &str) -> Option<(u32, u32, u32, u32)> {
+    let mut bounds: Option<(i64, i64, i64, i64)> = None;
+    for point in points.split_whitespace() {
+        let (x, y) = point.split_once(',')?;
+        let x: i64 = x.trim_start_matches('+').parse().ok()?;
+        let y: i64 = y.trim_start_matches('+').parse().ok()?;
+        bounds = Some(match bounds {
+            Some((left, top, right, bottom)) => {
+                (left.min(x), top.min(y), right.max(x), bottom.max(y))
+            }
+            None => (x, y, x, y),
+        });
+    }
+    let (left, top, right, bottom) = bounds?;
+    Some((
+        left.max(0) as u32,
+        top.max(0) as u32,
+        right.max(0) as u32 + 1,
+        bottom.max(0) as u32 + 1,
+    ))
+}
+
+/* runs the zbarimg executable on the image */
+pub fn scan(image: &DynamicImage) -> Result<Vec<Barcode>, String> {
+    let mut path = std::env::temp_dir();
+    path.push(format!("screen-pds-scan-{}.png", std::process::id()));
+    image
+        .resize(
+            image.width() * SCAN_SCALE,
+            image.height() * SCAN_SCALE,
+            FilterType::Nearest,
+        )
+        .save(&path)
+        .map_err(|error| error.to_string())?;
+    let output = Command::new(ZBARIMG)
+        .arg("--xml")
+        .arg("-q")
+        .arg(&path)
+        .output();
+    let _ = std::fs::remove_file(&path);
+    let output = output.map_err(|error| match error.kind() {
+        ErrorKind::NotFound => "ZBar is not installed, see the README".to_string(),
+        _ => error.to_string(),
+    })?;
+    if output.status.code() == Some(NO_CODES) {
+        return Ok(Vec::new());
+    }
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let mut codes = parse_zbar_xml(
+        &String::from_utf8_lossy(&output.stdout),
+        image.width() * SCAN_SCALE,
+        image.height() * SCAN_SCALE,
+    );
+    for code in &mut codes {
+        code.left /= SCAN_SCALE;
+        code.top /= SCAN_SCALE;
+        code.width = (code.width as f64 / SCAN_SCALE as f64).ceil() as u32;
+        code.height = (code.height as f64 / SCAN_SCALE as f64).ceil() as u32;
+    }
+    Ok(codes)
+}
+
+/* scans the part of the image inside the rectangle, or all of it; the boxes of the codes
+are in pixels of the whole image */
+pub fn scan_area(image: &DynamicImage, area: Option<&Coordinates>) -> Result<Vec<Barcode>, String> {
+    let (left, top, width, height) = area_pixels(image, area);
+    if width == 0 || height == 0 {
+        return Err("The area to scan is empty".to_string());
+    }
+    let mut codes = scan(&image.crop_imm(left, top, width, height))?;
+    for code in &mut codes {
+        code.left += left;
+        code.top += top;
+    }
+    Ok(codes)
+}
@@ -0,0 +1,550 @@
+#[cfg(target_os = "linux")]
+use crate::cursor::from_premultiplied_argb;
+use crate::cursor::CursorImage;
+use image::{open, RgbaImage};
+use screenshots::Screen;
+use serde::{Deserialize, Serialize};
+use std::path::Path;
+use std::sync::atomic::{AtomicU32, Ordering};
+use std::time;
+
+/* a physical screen as seen by a capture backend */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScreenInfo {
+    pub id: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+    pub scale_factor: f32,
+    pub is_primary: bool,
+}
+
+/* rectangle in desktop coordinates */
+#[derive(Clone, Copy, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Region {
+    pub x: i32,
+    pub y: i32,
+    pub width: u32,
+    pub height: u32,
+}
+
+impl Region {
+    pub fn contains(&self, x: i32, y: i32) -> bool {
+        x >= self.x
+            && y >= self.y
+            && (x as i64) < self.x as i64 + self.width as i64
+            && (y as i64) < self.y as i64 + self.height as i64
+    }
+}
+
+impl ScreenInfo {
+    pub fn region(&self) -> Region {
+        Region {
+            x: self.x,
+            y: self.y,
+            width: self.width,
+            height: self.height,
+        }
+    }
+}
+
+/* source of screen pixels: the real display or a fake used by tests */
+pub trait CaptureBackend {
+    fn list_screens(&self) -> Result<Vec<ScreenInfo>, String>;
+    fn capture_screen(&self, screen_id: u32) -> Result<RgbaImage, String>;
+    fn capture_region(&self, region: Region) -> Result<RgbaImage, String>;
+    fn capture_window(&self, window_id: u32) -> Result<RgbaImage, String>;
+    /* visible windows in desktop coordinates, top-most first; a window comes right
+    after the windows inside it, so the first one containing a point is the innermost */
+    fn list_windows(&self) -> Result<Vec<(u32, Region)>, String>;
+    /* image and desktop position of the mouse pointer */
+    fn cursor(&self) -> Result<CursorImage, String>;
+    /* turns the mouse wheel down by clicks notches over the centre of the region */
+    fn scroll_down(&self, region: Region, clicks: u32) -> Result<(), String>;
+}
+
+/* real backend: X11 (and Windows/MacOS) through the screenshots crate */
+pub struct SystemBackend;
+
+impl SystemBackend {
+    fn screen_by_id(screen_id: u32) -> Result<Screen, String> {
+        let screens = Screen::all().map_err(|error| error.to_string())?;
+        screens
+            .into_iter()
+            .find(|screen| screen.display_info.id == screen_id)
+            .ok_or(format!("No screen with id {}", screen_id))
+    }
+}
+
+impl CaptureBackend for SystemBackend {
+    fn list_screens(&self) -> Result<Vec<ScreenInfo>, String> {
+        let screens = Screen::all().map_err(|error| error.to_string())?;
+        Ok(screens
+            .iter()
+            .map(|screen| ScreenInfo {
+                id: screen.display_info.id,
+                x: screen.display_info.x,
+                y: screen.display_info.y,
+                width: screen.display_info.width,
+                height: screen.display_info.height,
+                scale_factor: screen.display_info.scale_factor,
+                is_primary: screen.display_info.is_primary,
+            })
+            .collect())
+    }
+
+    fn capture_screen(&self, screen_id: u32) -> Result<RgbaImage, String> {
+        let screen = SystemBackend::screen_by_id(screen_id)?;
+        screen.capture().map_err(|error| error.to_string())
+    }
+
+    fn capture_region(&self, region: Region) -> Result<RgbaImage, String> {
+        let screen = Screen::from_point(region.x, region.y).map_err(|error| error.to_string())?;
+        screen
+            .capture_area(
+                region.x - screen.display_info.x,
+                region.y - screen.display_info.y,
+                region.width,
+                region.height,
+            )
+            .map_err(|error| error.to_string())
+    }
+
+    fn capture_window(&self, window_id: u32) -> Result<RgbaImage, String> {
+        let region = window_region(window_id)?;
+        self.capture_region(region)
+    }
+
+    fn list_windows(&self) -> Result<Vec<(u32, Region)>, String> {
+        visible_windows()
+    }
+
+    fn cursor(&self) -> Result<CursorImage, String> {
+        cursor_image()
+    }
+
+    fn scroll_down(&self, region: Region, clicks: u32) -> Result<(), String> {
+        wheel_down(region, clicks)
+    }
+}
+
+/* position and size of an X11 window in root coordinates */
+#[cfg(target_os = "linux")]
+fn window_region(window_id: u32) -> Result<Region, String> {
+    use x11rb::connection::Connection;
+
+    let (conn, screen_num) = x11rb::connect(None).map_err(|error| error.to_string())?;
+    let root = conn.setup().roots[screen_num].root;
+    region_in_root(&conn, root, window_id)
+}
+
+#[cfg(target_os = "linux")]
+fn region_in_root(
+    conn: &impl x11rb::connection::Connection,
+    root: u32,
+    window_id: u32,
+) -> Result<Region, String> {
+    use x11rb::protocol::xproto::ConnectionExt;
+
+    let geometry = conn
+        .get_geometry(window_id)
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?;
+    let origin = conn
+        .translate_coordinates(window_id, root, 0, 0)
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?;
+    Ok(Region {
+        x: origin.dst_x as i32,
+        y: origin.dst_y as i32,
+        width: geometry.width as u32,
+        height: geometry.height as u32,
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn window_region(_window_id: u32) -> Result<Region, String> {
+    Err("Window capture is only supported on X11".to_string())
+}
+
+/* whole X11 window tree below the root, see CaptureBackend::list_windows */
+#[cfg(target_os = "linux")]
+fn visible_windows() -> Result<Vec<(u32, Region)>, String> {
+    use x11rb::connection::Connection;
+
+    let (conn, screen_num) = x11rb::connect(None).map_err(|error| error.to_string())?;
+    let root = conn.setup().roots[screen_num].root;
+    let mut windows = Vec::new();
+    collect_windows(&conn, root, root, &mut windows)?;
+    Ok(windows)
+}
+
+#[cfg(target_os = "linux")]
+fn collect_windows(
+    conn: &impl x11rb::connection::Connection,
+    root: u32,
+    parent: u32,
+    windows: &mut Vec<(u32, Region)>,
+) -> Result<(), String> {
+    use x11rb::protocol::xproto::{ConnectionExt, MapState};
+
+    let tree = conn
+        .query_tree(parent)
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?;
+    // children come bottom-most first
+    for child in tree.children.iter().rev() {
+        // windows can disappear while the tree is walked, they are just skipped
+        let viewable = match conn.get_window_attributes(*child) {
+            Ok(cookie) => match cookie.reply() {
+                Ok(attributes) => attributes.map_state == MapState::VIEWABLE,
+                Err(_) => false,
+            },
+            Err(_) => false,
+        };
+        if !viewable {
+            continue;
+        }
+        let _ = collect_windows(conn, root, *child, windows);
+        if let Ok(region) = region_in_root(conn, root, *child) {
+            windows.push((*child, region));
+        }
+    }
+    Ok(())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn visible_windows() -> Result<Vec<(u32, Region)>, String> {
+    Err("Window bounds are only available on X11".to_string())
+}
+
+/* current cursor through the XFixes extension */
+#[cfg(target_os = "linux")]
+fn cursor_image() -> Result<CursorImage, String> {
+    use x11rb::protocol::xfixes::ConnectionExt;
+
+    let (conn, _) = x11rb::connect(None).map_err(|error| error.to_string())?;
+    // the extension answers only to clients that told it their version
+    conn.xfixes_query_version(4, 0)
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?;
+    let reply = conn
+        .xfixes_get_cursor_image()
+        .map_err(|error| error.to_string())?
+        .reply()
+        .map_err(|error| error.to_string())?;
+    Ok(CursorImage {
+        x: reply.x as i32,
+        y: reply.y as i32,
+        hot_x: reply.xhot as u32,
+        hot_y: reply.yhot as u32,
+        image: from_premultiplied_argb(
+            reply.width as u32,
+            reply.height as u32,
+            &reply.cursor_image,
+        ),
+    })
+}
+
+#[cfg(not(target_os = "linux"))]
+fn cursor_image() -> Result<CursorImage, String> {
+    Err("Cursor capture is only supported on X11".to_string())
+}
+
+/* moves the pointer to the centre of the region and presses the wheel-down button (5)
+through the XTest extension */
+#[cfg(target_os = "linux")]
+fn wheel_down(region: Region, clicks: u32) -> Result<(), String> {
+    use x11rb::connection::Connection;
+    use x11rb::protocol::xproto::{BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT, MOTION_NOTIFY_EVENT};
+    use x11rb::protocol::xtest::ConnectionExt;
+    use x11rb::CURRENT_TIME;
+
+    const WHEEL_DOWN: u8 = 5;
+    let (conn, screen_num) = x11rb::connect(None).map_err(|error| error.to_string())?;
+    let root = conn.setup().roots[screen_num].root;
+    let x = (region.x as i64 + region.width as i64 / 2) as i16;
+    let y = (region.y as i64 + region.height as i64 / 2) as i16;
+    conn.xtest_fake_input(MOTION_NOTIFY_EVENT, 0, CURRENT_TIME, root, x, y, 0)
+        .map_err(|error| error.to_string())?;
+    for _ in 0..clicks {
+        for event in [BUTTON_PRESS_EVENT, BUTTON_RELEASE_EVENT] {
+            conn.xtest_fake_input(event, WHEEL_DOWN, CURRENT_TIME, root, 0, 0, 0)
+                .map_err(|error| error.to_string())?;
+        }
+    }
+    conn.flush().map_err(|error| error.to_string())
+}
+
+#[cfg(not(target_os = "linux"))]
+fn wheel_down(_region: Region, _clicks: u32) -> Result<(), String> {
+    Err("Automatic scrolling is only supported on X11".to_string())
+}
+
+/* deterministic backend for tests: screens are image files or generated patterns; a screen
+image taller than the screen is a page that scroll_down moves up */
+pub struct FakeBackend {
+    screens: Vec<(ScreenInfo, RgbaImage)>,
+    windows: Vec<(u32, Region)>,
+    cursor: Option<CursorImage>,
+    captures: AtomicU32,
+    // rows moved by a notch of the wheel and rows scrolled so far
+    scroll_step: u32,
+    scrolled: AtomicU32,
+}
+
+impl FakeBackend {
+    /* one screen at (0, 0) showing the given image */
+    pub fn from_image(image: RgbaImage) -> Self {
+        let info = ScreenInfo {
+            id: 0,
+            x: 0,
+            y: 0,
+            width: image.width(),
+            height: image.height(),
+            scale_factor: 1.0,
+            is_primary: true,
+        };
+        FakeBackend {
+            screens: vec![(info, image)],
+            windows: Vec::new(),
+            cursor: None,
+            captures: AtomicU32::new(0),
+            scroll_step: 0,
+            scrolled: AtomicU32::new(0),
+        }
+    }
+
+    /* one screen height pixels tall showing the top of the page, every notch of the wheel
+    scrolls it by scroll_step rows until its end */
+    pub fn from_page(page: RgbaImage, height: u32, scroll_step: u32) -> Self {
+        let mut backend = FakeBackend::from_image(page);
+        backend.screens[0].0.height = height;
+        backend.scroll_step = scroll_step;
+        backend
+    }
+
+    /* one screen showing the content of an image file */
+    pub fn from_file(path: &Path) -> Result<Self, String> {
+        let image = open(path).map_err(|error| error.to_string())?;
+        Ok(FakeBackend::from_image(image.to_rgba8()))
+    }
+
+    /* one screen filled with pattern_pixel, so every pixel tells its own position */
+    pub fn from_pattern(width: u32, height: u32) -> Self {
+        FakeBackend::from_image(RgbaImage::from_fn(width, height, pattern_pixel))
+    }
+
+    /* adds another screen to the right of the existing ones */
+    pub fn add_screen(&mut self, image: RgbaImage) -> u32 {
+        let id = self.screens.len() as u32;
+        let x = self
+            .screens
+            .iter()
+            .map(|(info, _)| info.x + info.width as i32)
+            .max()
+            .unwrap_or(0);
+        let info = ScreenInfo {
+            id,
+            x,
+            y: 0,
+            width: image.width(),
+            height: image.height(),
+            scale_factor: 1.0,
+            is_primary: false,
+        };
+        self.screens.push((info, image));
+        id
+    }
+
+    pub fn add_window(&mut self, window_id: u32, region: Region) {
+        self.windows.push((window_id, region));
+    }
+
+    pub fn set_cursor(&mut self, cursor: CursorImage) {
+        self.cursor = Some(cursor);
+    }
+
+    /* part of the screen image currently on screen */
+    fn visible(&self, info: &ScreenInfo, image: &RgbaImage) -> RgbaImage {
+        let top = self.scrolled.load(Ordering::SeqCst);
+        image::imageops::crop_imm(image, 0, top, info.width, info.height).to_image()
+    }
+
+    /* number of successful captures served so far */
+    pub fn capture_count(&self) -> u32 {
+        self.captures.load(Ordering::SeqCst)
+    }
+}
+
+impl CaptureBackend for FakeBackend {
+    fn list_screens(&self) -> Result<Vec<ScreenInfo>, String> {
+        Ok(self.screens.iter().map(|(info, _)| *info).collect())
+    }
+
+    fn capture_screen(&self, screen_id: u32) -> Result<RgbaImage, String> {
+        let (info, image) = self
+            .screens
+            .iter()
+            .find(|(info, _)| info.id == screen_id)
+            .ok_or(format!("No screen with id {}", screen_id))?;
+        self.captures.fetch_add(1, Ordering::SeqCst);
+        Ok(self.visible(info, image))
+    }
+
+    fn capture_region(&self, region: Region) -> Result<RgbaImage, String> {
+        let (info, image) = self
+            .screens
+            .iter()
+            .find(|(info, _)| info.region().contains(region.x, region.y))
+            .ok_or(format!("No screen at ({}, {})", region.x, region.y))?;
+        let x = (region.x - info.x) as u32;
+        let y = (region.y - info.y) as u32;
+        if x + region.width > info.width || y + region.height > info.height {
+            return Err("Region exceeds the screen bounds".to_string());
+        }
+        self.captures.fetch_add(1, Ordering::SeqCst);
+        let screen = self.visible(info, image);
+        Ok(image::imageops::crop_imm(&screen, x, y, region.width, region.height).to_image())
+    }
+
+    fn capture_window(&self, window_id: u32) -> Result<RgbaImage, String> {
+        let (_, region) = self
+            .windows
+            .iter()
+            .find(|(id, _)| *id == window_id)
+            .ok_or(format!("No window with id {}", window_id))?;
+        self.capture_region(*region)
+    }
+
+    /* windows added later are on top */
+    fn list_windows(&self) -> Result<Vec<(u32, Region)>, String> {
+        Ok(self.windows.iter().rev().copied().collect())
+    }
+
+    fn cursor(&self) -> Result<CursorImage, String> {
+        self.cursor.clone().ok_or("No cursor".to_string())
+    }
+
+    fn scroll_down(&self, _region: Region, clicks: u32) -> Result<(), String> {
+        let (info, image) = &self.screens[0];
+        let end = image.height().saturating_sub(info.height);
+        let scrolled = self.scrolled.load(Ordering::SeqCst) + clicks * self.scroll_step;
+        self.scrolled.store(scrolled.min(end), Ordering::SeqCst);
+        Ok(())
+    }
+}
+
+/* colour of pixel (x, y) in FakeBackend::from_pattern */
+pub fn pattern_pixel(x: u32, y: u32) -> image::Rgba<u8> {
+    image::Rgba([
+        (x % 256) as u8,
+        (y % 256) as u8,
+        ((x / 256) + (y / 256) * 16) as u8,
+        255,
+    ])
+}
+
+/* waits for the delay chosen in the dropdown */
+pub fn wait_delay(delay: u32) {
+    match delay {
+        0 => {}
+        1 => {
+            std::thread::sleep(time::Duration::from_millis(3000));
+        }
+        2 => {
+            std::thread::sleep(time::Duration::from_millis(5000));
+        }
+        3 => {
+            std::thread::sleep(time::Duration::from_millis(10000));
+        }
+        _ => eprintln!("Error"),
+    }
+}
+
+/* the application's own windows, put out of the way while the screen is captured */
+pub trait SelfHiding {
+    fn hide(&mut self);
+    /* true once the windows are reported off the screen */
+    fn is_hidden(&mut self) -> bool;
+    fn restore(&mut self);
+}
+
+// how often the hidden windows are checked, and the time the compositor gets to repaint
+// the screen without them
+pub const HIDDEN_POLL: time::Duration = time::Duration::from_millis(10);
+const COMPOSITOR_FRAME: time::Duration = time::Duration::from_millis(50);
+
+/* windows on their way off the screen, checked every HIDDEN_POLL from the main loop so that
+the capture runs on a later turn of it, once is_ready, and the windows are then restored */
+pub struct HidingWait {
+    start: time::Instant,
+    hidden_at: Option<time::Instant>,
+    settle: time::Duration,
+}
+
+impl HidingWait {
+    pub fn start(windows: &mut dyn SelfHiding, settle: time::Duration) -> HidingWait {
+        windows.hide();
+        HidingWait {
+            start: time::Instant::now(),
+            hidden_at: None,
+            settle,
+        }
+    }
+
+    /* true a frame after the windows are reported gone, or after settle if they never are */
+    pub fn is_ready(&mut self, windows: &mut dyn SelfHiding) -> bool {
+        if self.hidden_at.is_none() && (windows.is_hidden() || self.start.elapsed() >= self.settle)
+        {
+            self.hidden_at = Some(time::Instant::now());
+        }
+        match self.hidden_at {
+            Some(hidden_at) => hidden_at.elapsed() >= COMPOSITOR_FRAME,
+            None => false,
+        }
+    }
+}
+
+/* screen containing the origin (or the first one), the one captured by capture_fullscreen */
+pub fn origin_screen(backend: &dyn CaptureBackend) -> Result<ScreenInfo, String> {
+    let screens = backend.list_screens()?;
+    screens
+        .iter()
+        .find(|screen| screen.region().contains(0, 0))
+        .or(screens.first())
+        .copied()
+        .ok_or("No screen available".to_string())
+}
+
+/* captures fullscreen screenshot of the screen containing the origin, returns that screen */
+pub fn capture_fullscreen(backend: &dyn CaptureBackend, path: &Path) -> Result<ScreenInfo, String> {
+    let screen = origin_screen(backend)?;
+    let image = backend.capture_screen(screen.id)?;
+    image.save(path).map_err(|error| error.to_string())?;
+    Ok(screen)
+}
+
+/* captures a region of the desktop into a file */
+pub fn capture_region_to(
+    backend: &dyn CaptureBackend,
+    region: Region,
+    path: &Path,
+) -> Result<(), String> {
+    let image = backend.capture_region(region)?;
+    image.save(path).map_err(|error| error.to_string())
+}
+
+pub fn capture_screenshot_with_delay(
+    backend: &dyn CaptureBackend,
+    delay: u32,
+    path: &Path,
+) -> Result<(), String> {
+    wait_delay(delay);
+    capture_fullscreen(backend, path).map(|_| ())
+}
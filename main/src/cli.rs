@@ -0,0 +1,265 @@
+use crate::capture::{origin_screen, CaptureBackend, Region};
+use crate::diff::{aligned_pair, heatmap, score, DiffOptions};
+use crate::ocr::{recognize_area, words_to_text};
+use crate::timeline::Coordinates;
+use image::{open, DynamicImage};
+use std::path::PathBuf;
+
+pub const USAGE: &str = "Usage:
+  screen-pds                         opens the application
+  screen-pds ocr [--file PATH] [--region X,Y,W,H] [--lang LANG]
+                                     prints the text of the screen (or of an image file),
+                                     or of a region of it
+  screen-pds diff BASELINE CURRENT [--threshold PERCENT] [--tolerance N]
+                  [--aa-tolerance N] [--ignore-region X,Y,W,H]... [--out PATH] [--update]
+                                     compares an image with its baseline and fails when
+                                     more than PERCENT% of the pixels changed; --update
+                                     replaces the baseline with the current image
+
+Exit status: 0 on success, 1 when diff finds more changes than the threshold, 2 for wrong
+arguments and any other error";
+
+// exit statuses of a failed command, see USAGE
+pub const DIFFERENCES_EXIT: i32 = 1;
+pub const ERROR_EXIT: i32 = 2;
+
+/* why a command failed, each with its own exit status */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Failure {
+    // the images differ more than the threshold
+    Differences(String),
+    // wrong arguments, unreadable files, failed capture or recognition
+    Error(String),
+}
+
+impl Failure {
+    pub fn exit_code(&self) -> i32 {
+        match self {
+            Failure::Differences(_) => DIFFERENCES_EXIT,
+            Failure::Error(_) => ERROR_EXIT,
+        }
+    }
+
+    pub fn message(&self) -> &str {
+        match self {
+            Failure::Differences(message) | Failure::Error(message) => message,
+        }
+    }
+}
+
+impl From<String> for Failure {
+    fn from(message: String) -> Self {
+        Failure::Error(message)
+    }
+}
+
+/* what is asked on the command line, see USAGE */
+#[derive(Clone, Debug, PartialEq)]
+pub enum Command {
+    Ocr {
+        file: Option<PathBuf>,
+        region: Option<Region>,
+        language: String,
+    },
+    Diff {
+        baseline: PathBuf,
+        current: PathBuf,
+        // largest share of changed pixels, in percent, for the images to match
+        threshold: f64,
+        options: DiffOptions,
+        out: Option<PathBuf>,
+        update: bool,
+    },
+}
+
+/* arguments after the program name */
+pub fn parse_args(args: &[String]) -> Result<Command, String> {
+    let (name, options) = match args.split_first() {
+        Some(split) => split,
+        None => return Err(USAGE.to_string()),
+    };
+    match name.as_str() {
+        "ocr" => {
+            let mut file = None;
+            let mut region = None;
+            let mut language = "eng".to_string();
+            let mut options = options.iter();
+            while let Some(option) = options.next() {
+                let value = options
+                    .next()
+                    .ok_or(format!("Missing value for {}", option))?;
+                match option.as_str() {
+                    "--file" => file = Some(PathBuf::from(value)),
+                    "--region" => {
+                        region =
+                            Some(parse_region(value).ok_or(format!("Invalid region {}", value))?)
+                    }
+                    "--lang" => language = value.clone(),
+                    _ => return Err(format!("Unknown option {}\n{}", option, USAGE)),
+                }
+            }
+            Ok(Command::Ocr {
+                file,
+                region,
+                language,
+            })
+        }
+        "diff" => {
+            let mut paths = Vec::new();
+            let mut threshold = 0.0;
+            let mut diff_options = DiffOptions::default();
+            let mut out = None;
+            let mut update = false;
+            let mut options = options.iter();
+            while let Some(option) = options.next() {
+                if option == "--update" {
+                    update = true;
+                    continue;
+                }
+                if !option.starts_with("--") {
+                    paths.push(PathBuf::from(option));
+                    continue;
+                }
+                let value = options
+                    .next()
+                    .ok_or(format!("Missing value for {}", option))?;
+                let invalid = || format!("Invalid value {} for {}", value, option);
+                match option.as_str() {
+                    "--threshold" => {
+                        threshold = value
+                            .trim_end_matches('%')
+                            .parse::<f64>()
+                            .ok()
+                            .filter(|threshold| (0.0..=100.0).contains(threshold))
+                            .ok_or_else(invalid)?
+                    }
+                    "--tolerance" => {
+                        diff_options.tolerance = value.parse().map_err(|_| invalid())?
+                    }
+                    "--aa-tolerance" => {
+                        diff_options.antialiasing = Some(value.parse().map_err(|_| invalid())?)
+                    }
+                    "--ignore-region" => diff_options
+                        .ignore
+                        .push(parse_region(value).ok_or_else(invalid)?),
+                    "--out" => out = Some(PathBuf::from(value)),
+                    _ => return Err(format!("Unknown option {}\n{}", option, USAGE)),
+                }
+            }
+            let (baseline, current) = match <[PathBuf; 2]>::try_from(paths) {
+                Ok([baseline, current]) => (baseline, current),
+                Err(_) => return Err(format!("Expected BASELINE and CURRENT\n{}", USAGE)),
+            };
+            Ok(Command::Diff {
+                baseline,
+                current,
+                threshold,
+                options: diff_options,
+                out,
+                update,
+            })
+        }
+        _ => Err(format!("Unknown command {}\n{}", name, USAGE)),
+    }
+}
+
+/* "x,y,w,h" with a non empty size */
+pub fn parse_region(text: &str) -> Option<Region> {
+    let values: Vec<&str> = text.split(',').map(|value| value.trim()).collect();
+    if values.len() != 4 {
+        return None;
+    }
+    let region = Region {
+        x: values[0].parse().ok()?,
+        y: values[1].parse().ok()?,
+        width: values[2].parse().ok()?,
+        height: values[3].parse().ok()?,
+    };
+    if region.width == 0 || region.height == 0 {
+        return None;
+    }
+    Some(region)
+}
+
+/* runs the command and returns what it prints; a region of the screen is in desktop
+coordinates, a region of a file in its pixels. Images differing more than the threshold
+are a failure of their own, so that the process exits with DIFFERENCES_EXIT */
+pub fn run(command: &Command, backend: &dyn CaptureBackend) -> Result<String, Failure> {
+    match command {
+        Command::Ocr {
+            file,
+            region,
+            language,
+        } => {
+            let (image, area) = match (file, region) {
+                (Some(path), region) => (
+                    open(path).map_err(|error| error.to_string())?,
+                    region.map(|region| Coordinates {
+                        start_x: region.x as f64,
+                        start_y: region.y as f64,
+                        offset_x: region.width as f64,
+                        offset_y: region.height as f64,
+                    }),
+                ),
+                (None, Some(region)) => (
+                    DynamicImage::ImageRgba8(backend.capture_region(*region)?),
+                    None,
+                ),
+                (None, None) => (
+                    DynamicImage::ImageRgba8(backend.capture_screen(origin_screen(backend)?.id)?),
+                    None,
+                ),
+            };
+            let words = recognize_area(&image, area.as_ref(), language)?;
+            Ok(words_to_text(&words))
+        }
+        Command::Diff {
+            baseline,
+            current,
+            threshold,
+            options,
+            out,
+            update,
+        } => {
+            let current_image = open(current).map_err(|error| error.to_string())?;
+            // a missing baseline is created by --update
+            if *update && !baseline.exists() {
+                current_image
+                    .save(baseline)
+                    .map_err(|error| error.to_string())?;
+                return Ok(format!("Baseline {} created", baseline.display()));
+            }
+            let baseline_image = open(baseline).map_err(|error| error.to_string())?;
+            // images of different sizes are compared from their top left corner
+            let (before, after) = aligned_pair(
+                &baseline_image.to_rgba8(),
+                &current_image.to_rgba8(),
+                (0, 0),
+            );
+            let diff = score(&before, &after, options);
+            if let Some(out) = out {
+                heatmap(&before, &after, options)
+                    .save(out)
+                    .map_err(|error| error.to_string())?;
+            }
+            if *update {
+                current_image
+                    .save(baseline)
+                    .map_err(|error| error.to_string())?;
+                return Ok(format!(
+                    "{}\nBaseline {} updated",
+                    diff.describe(),
+                    baseline.display()
+                ));
+            }
+            if diff.percent() > *threshold {
+                return Err(Failure::Differences(format!(
+                    "{}, more than {}%",
+                    diff.describe(),
+                    threshold
+                )));
+            }
+            Ok(diff.describe())
+        }
+    }
+}
@@ -0,0 +1,249 @@
+use arboard::Clipboard;
+use base64::Engine;
+use image::{DynamicImage, ImageFormat, RgbaImage};
+use std::cell::RefCell;
+use std::io::Cursor;
+use std::path::Path;
+
+thread_local! {
+    /* on X11 the clipboard content is served by its owner, so the owner lives until
+    release_clipboard instead of being dropped right after each copy */
+    static CLIPBOARD_OWNER: RefCell<Option<Clipboard>> = const { RefCell::new(None) };
+}
+
+/* when captures are copied to the clipboard without pressing Copy */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CopyPolicy {
+    EveryStep,
+    OnSave,
+    Never,
+}
+
+impl CopyPolicy {
+    pub fn from_json(value: &str) -> CopyPolicy {
+        match value {
+            "EVERY_STEP" => CopyPolicy::EveryStep,
+            "ON_SAVE" => CopyPolicy::OnSave,
+            "NEVER" => CopyPolicy::Never,
+            _ => CopyPolicy::EveryStep,
+        }
+    }
+
+    pub fn to_json(self) -> String {
+        match self {
+            CopyPolicy::EveryStep => "EVERY_STEP".to_string(),
+            CopyPolicy::OnSave => "ON_SAVE".to_string(),
+            CopyPolicy::Never => "NEVER".to_string(),
+        }
+    }
+
+    pub fn from_index(index: u32) -> CopyPolicy {
+        match index {
+            1 => CopyPolicy::OnSave,
+            2 => CopyPolicy::Never,
+            _ => CopyPolicy::EveryStep,
+        }
+    }
+
+    pub fn to_index(self) -> u32 {
+        match self {
+            CopyPolicy::EveryStep => 0,
+            CopyPolicy::OnSave => 1,
+            CopyPolicy::Never => 2,
+        }
+    }
+}
+
+/* flavour chosen in the "copy as" dropdown */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CopyFormat {
+    Png,
+    Html,
+    FileUri,
+    Markdown,
+}
+
+impl CopyFormat {
+    pub fn from_index(index: u32) -> CopyFormat {
+        match index {
+            1 => CopyFormat::Html,
+            2 => CopyFormat::FileUri,
+            3 => CopyFormat::Markdown,
+            _ => CopyFormat::Png,
+        }
+    }
+
+    /* file URI and Markdown point at the saved file, so they need one */
+    pub fn needs_saved_file(self) -> bool {
+        matches!(self, CopyFormat::FileUri | CopyFormat::Markdown)
+    }
+}
+
+// MIME types of the flavours of a copied capture
+pub const PNG_TARGET: &str = "image/png";
+pub const HTML_TARGET: &str = "text/html";
+pub const URI_LIST_TARGET: &str = "text/uri-list";
+pub const MARKDOWN_TARGET: &str = "text/markdown";
+pub const TEXT_TARGET: &str = "text/plain;charset=utf-8";
+
+/* every representation of a capture that is put on the clipboard */
+pub struct ClipboardPayload {
+    pub png: Vec<u8>,
+    pub html: String,
+    pub uri_list: Option<String>,
+    pub markdown: Option<String>,
+}
+
+impl ClipboardPayload {
+    /* saved_path is the file written by Save, if any */
+    pub fn new(image: &DynamicImage, saved_path: Option<&Path>) -> Result<Self, String> {
+        let png = encode_png(image)?;
+        let alt = saved_path
+            .and_then(|path| path.file_stem())
+            .map(|stem| stem.to_string_lossy().to_string())
+            .unwrap_or("capture".to_string());
+        Ok(ClipboardPayload {
+            html: html_image(&png, image.width(), image.height(), &alt),
+            png,
+            uri_list: saved_path.map(|path| file_uri(path) + "\r\n"),
+            markdown: saved_path.map(|path| markdown_image(path, &alt)),
+        })
+    }
+
+    /* text alternative for targets that only accept plain text */
+    pub fn plain_text(&self, format: CopyFormat) -> Option<String> {
+        match format {
+            CopyFormat::Png => None,
+            CopyFormat::Html => Some(self.html.clone()),
+            CopyFormat::FileUri => self
+                .uri_list
+                .as_ref()
+                .map(|list| list.trim_end().to_string()),
+            CopyFormat::Markdown => self.markdown.clone(),
+        }
+    }
+
+    /* (MIME type, content) of all the flavours, the one chosen in format first so that
+    applications accepting several of them prefer it; plain text carries the chosen one */
+    pub fn into_targets(self, format: CopyFormat) -> Vec<(&'static str, Vec<u8>)> {
+        let text = self.plain_text(format);
+        let mut targets = vec![
+            (PNG_TARGET, self.png),
+            (HTML_TARGET, self.html.into_bytes()),
+        ];
+        if let Some(uri_list) = self.uri_list {
+            targets.push((URI_LIST_TARGET, uri_list.into_bytes()));
+        }
+        if let Some(markdown) = self.markdown {
+            targets.push((MARKDOWN_TARGET, markdown.into_bytes()));
+        }
+        let chosen = match format {
+            CopyFormat::Png => PNG_TARGET,
+            CopyFormat::Html => HTML_TARGET,
+            CopyFormat::FileUri => URI_LIST_TARGET,
+            CopyFormat::Markdown => MARKDOWN_TARGET,
+        };
+        targets.sort_by_key(|(target, _)| *target != chosen);
+        if let Some(text) = text {
+            targets.push((TEXT_TARGET, text.into_bytes()));
+        }
+        targets
+    }
+}
+
+/* plain text, e.g. a picked colour */
+pub fn set_clipboard_text(text: &str) -> Result<(), String> {
+    with_clipboard(|ctx| ctx.set_text(text).map_err(|error| error.to_string()))
+}
+
+/* image currently on the clipboard, e.g. copied from a browser */
+pub fn clipboard_image() -> Result<DynamicImage, String> {
+    with_clipboard(|ctx| {
+        let data = ctx.get_image().map_err(|error| error.to_string())?;
+        RgbaImage::from_raw(
+            data.width as u32,
+            data.height as u32,
+            data.bytes.into_owned(),
+        )
+        .map(DynamicImage::ImageRgba8)
+        .ok_or("The clipboard image has an invalid size".to_string())
+    })
+}
+
+/* runs f on the long-lived owner, creating it on first use */
+fn with_clipboard<R, F>(f: F) -> Result<R, String>
+where
+    F: FnOnce(&mut Clipboard) -> Result<R, String>,
+{
+    CLIPBOARD_OWNER.with(|owner| {
+        let mut owner = owner.borrow_mut();
+        if owner.is_none() {
+            *owner = Some(Clipboard::new().map_err(|error| error.to_string())?);
+        }
+        f(owner.as_mut().unwrap())
+    })
+}
+
+/* drops the owner: arboard asks the clipboard manager to take the content
+(SAVE_TARGETS), so the last copy is still pasteable after the application quits */
+pub fn release_clipboard() {
+    CLIPBOARD_OWNER.with(|owner| {
+        owner.borrow_mut().take();
+    });
+}
+
+pub fn encode_png(image: &DynamicImage) -> Result<Vec<u8>, String> {
+    let mut bytes = Cursor::new(Vec::new());
+    image
+        .write_to(&mut bytes, ImageFormat::Png)
+        .map_err(|error| error.to_string())?;
+    Ok(bytes.into_inner())
+}
+
+/* <img> tag embedding the png as a data URI */
+pub fn html_image(png: &[u8], width: u32, height: u32, alt: &str) -> String {
+    format!(
+        "<img src=\"data:image/png;base64,{}\" width=\"{}\" height=\"{}\" alt=\"{}\">",
+        base64::engine::general_purpose::STANDARD.encode(png),
+        width,
+        height,
+        escape_html(alt)
+    )
+}
+
+/* file:// URI of an absolute path, percent-encoded */
+pub fn file_uri(path: &Path) -> String {
+    let mut text = path.to_string_lossy().replace('\\', "/");
+    if !text.starts_with('/') {
+        // windows drive letter, e.g. C:/Users
+        text.insert(0, '/');
+    }
+    let mut uri = "file://".to_string();
+    for byte in text.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'.' | b'_' | b'~' | b'/' | b':' => {
+                uri.push(byte as char)
+            }
+            _ => uri.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    uri
+}
+
+/* Markdown image link to the saved file */
+pub fn markdown_image(path: &Path, alt: &str) -> String {
+    let target = path.to_string_lossy().replace('\\', "/");
+    let alt = alt.replace('[', "\\[").replace(']', "\\]");
+    if target.contains(|c: char| c.is_whitespace() || c == '(' || c == ')') {
+        format!("![{}](<{}>)", alt, target)
+    } else {
+        format!("![{}]({})", alt, target)
+    }
+}
+
+pub fn escape_html(text: &str) -> String {
+    text.replace('&', "&amp;")
+        .replace('"', "&quot;")
+        .replace('<', "&lt;")
+        .replace('>', "&gt;")
+}
@@ -0,0 +1,111 @@
+use image::{Rgba, RgbaImage};
+
+/* how the images of a collage are arranged */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CollageLayout {
+    Horizontal,
+    Vertical,
+    // rows of the given number of columns, filled from the left
+    Grid(u32),
+}
+
+impl CollageLayout {
+    /* index in the layout dropdown, the grid getting the columns */
+    pub fn from_index(index: u32, columns: u32) -> CollageLayout {
+        match index {
+            1 => CollageLayout::Vertical,
+            2 => CollageLayout::Grid(columns.max(1)),
+            _ => CollageLayout::Horizontal,
+        }
+    }
+
+    fn columns(&self, count: u32) -> u32 {
+        match self {
+            CollageLayout::Horizontal => count.max(1),
+            CollageLayout::Vertical => 1,
+            CollageLayout::Grid(columns) => (*columns).max(1),
+        }
+    }
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct CollageOptions {
+    pub layout: CollageLayout,
+    // pixels between the images and around them
+    pub spacing: u32,
+    pub background: Rgba<u8>,
+    // height of the band left below every image for its label, 0 for no labels
+    pub label_height: u32,
+}
+
+/* the composed image and, for every image, the band (left, top, width, height) where its
+label goes */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Collage {
+    pub image: RgbaImage,
+    pub labels: Vec<(u32, u32, u32, u32)>,
+}
+
+/* places the images in cells as wide as the widest image of their column and as tall as the
+tallest of their row, each image centred in its cell above its label band */
+pub fn collage(images: &[RgbaImage], options: &CollageOptions) -> Collage {
+    let count = images.len() as u32;
+    let columns = options.layout.columns(count).min(count.max(1));
+    let rows = images.chunks(columns as usize).count() as u32;
+    let mut widths = vec![0; columns as usize];
+    let mut heights = vec![0; rows.max(1) as usize];
+    for (index, image) in images.iter().enumerate() {
+        let column = index % columns as usize;
+        let row = index / columns as usize;
+        widths[column] = widths[column].max(image.width());
+        heights[row] = heights[row].max(image.height() + options.label_height);
+    }
+    let offsets = |sizes: &[u32]| -> Vec<u32> {
+        sizes
+            .iter()
+            .scan(options.spacing, |next, size| {
+                let offset = *next;
+                *next += size + options.spacing;
+                Some(offset)
+            })
+            .collect()
+    };
+    let lefts = offsets(&widths);
+    let tops = offsets(&heights);
+    let width = options.spacing
+        + widths
+            .iter()
+            .map(|width| width + options.spacing)
+            .sum::<u32>();
+    let height = options.spacing
+        + heights
+            .iter()
+            .map(|height| height + options.spacing)
+            .sum::<u32>();
+
+    let mut image = RgbaImage::from_pixel(width, height, options.background);
+    let mut labels = Vec::new();
+    for (index, picture) in images.iter().enumerate() {
+        let column = index % columns as usize;
+        let row = index / columns as usize;
+        let left = lefts[column] + (widths[column] - picture.width()) / 2;
+        let top = tops[row] + (heights[row] - picture.height() - options.label_height) / 2;
+        image::imageops::overlay(&mut image, picture, left as i64, top as i64);
+        labels.push((
+            lefts[column],
+            top + picture.height(),
+            widths[column],
+            options.label_height,
+        ));
+    }
+    Collage { image, labels }
+}
+
+/* columns of a grid as square as possible for count images */
+pub fn square_columns(count: u32) -> u32 {
+    let mut columns = 1;
+    while columns * columns < count {
+        columns += 1;
+    }
+    columns
+}
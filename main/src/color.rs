@@ -0,0 +1,96 @@
+use image::Rgba;
+
+/* notation used when a picked colour is copied */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum ColorFormat {
+    Hex,
+    Rgb,
+    Hsl,
+}
+
+impl ColorFormat {
+    pub fn from_json(value: &str) -> ColorFormat {
+        match value {
+            "HEX" => ColorFormat::Hex,
+            "RGB" => ColorFormat::Rgb,
+            "HSL" => ColorFormat::Hsl,
+            _ => ColorFormat::Hex,
+        }
+    }
+
+    pub fn to_json(self) -> String {
+        match self {
+            ColorFormat::Hex => "HEX".to_string(),
+            ColorFormat::Rgb => "RGB".to_string(),
+            ColorFormat::Hsl => "HSL".to_string(),
+        }
+    }
+
+    pub fn format(self, pixel: Rgba<u8>) -> String {
+        match self {
+            ColorFormat::Hex => hex(pixel),
+            ColorFormat::Rgb => rgb(pixel),
+            ColorFormat::Hsl => hsl(pixel),
+        }
+    }
+}
+
+/* #RRGGBB */
+pub fn hex(pixel: Rgba<u8>) -> String {
+    format!("#{:02X}{:02X}{:02X}", pixel[0], pixel[1], pixel[2])
+}
+
+/* rgb(r, g, b) */
+pub fn rgb(pixel: Rgba<u8>) -> String {
+    format!("rgb({}, {}, {})", pixel[0], pixel[1], pixel[2])
+}
+
+/* hsl(h, s%, l%) with the hue in degrees */
+pub fn hsl(pixel: Rgba<u8>) -> String {
+    let r = pixel[0] as f64 / 255.0;
+    let g = pixel[1] as f64 / 255.0;
+    let b = pixel[2] as f64 / 255.0;
+    let max = r.max(g).max(b);
+    let min = r.min(g).min(b);
+    let lightness = (max + min) / 2.0;
+    let delta = max - min;
+    let (hue, saturation) = if delta == 0.0 {
+        // grey
+        (0.0, 0.0)
+    } else {
+        let saturation = delta / (1.0 - (2.0 * lightness - 1.0).abs());
+        let hue = if max == r {
+            60.0 * (((g - b) / delta).rem_euclid(6.0))
+        } else if max == g {
+            60.0 * ((b - r) / delta + 2.0)
+        } else {
+            60.0 * ((r - g) / delta + 4.0)
+        };
+        (hue, saturation)
+    };
+    format!(
+        "hsl({}, {}%, {}%)",
+        hue.round() as u32 % 360,
+        (saturation * 100.0).round() as u32,
+        (lightness * 100.0).round() as u32
+    )
+}
+
+/* opaque colour from #RRGGBB, as stored in the history */
+pub fn parse_hex(text: &str) -> Option<Rgba<u8>> {
+    let digits = text.strip_prefix('#')?;
+    if digits.len() != 6 {
+        return None;
+    }
+    let channel = |index: usize| u8::from_str_radix(digits.get(index..index + 2)?, 16).ok();
+    Some(Rgba([channel(0)?, channel(2)?, channel(4)?, 255]))
+}
+
+/* puts the colour first in the history of recent colours, without duplicates and
+keeping at most limit entries */
+pub fn push_recent(history: &mut Vec<String>, pixel: Rgba<u8>, limit: usize) {
+    let entry = hex(pixel);
+    history.retain(|color| *color != entry);
+    history.insert(0, entry);
+    history.truncate(limit);
+}
@@ -0,0 +1,89 @@
+use crate::timeline::Coordinates;
+
+/* limits on the crop rectangle chosen in the crop panel; stored in the settings as
+"4:3" for a ratio and "1280x720" for a fixed size */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum CropConstraint {
+    Free,
+    Ratio(f64, f64),
+    Size(u32, u32),
+}
+
+impl CropConstraint {
+    pub fn parse(text: &str) -> Option<CropConstraint> {
+        let text = text.trim();
+        if let Some((width, height)) = text.split_once(':') {
+            let width: f64 = width.trim().parse().ok()?;
+            let height: f64 = height.trim().parse().ok()?;
+            if width > 0.0 && height > 0.0 {
+                return Some(CropConstraint::Ratio(width, height));
+            }
+        } else if let Some((width, height)) = text.split_once(['x', '×']) {
+            let width: u32 = width.trim().parse().ok()?;
+            let height: u32 = height.trim().parse().ok()?;
+            if width > 0 && height > 0 {
+                return Some(CropConstraint::Size(width, height));
+            }
+        }
+        None
+    }
+
+    pub fn to_text(self) -> String {
+        match self {
+            CropConstraint::Free => "Free".to_string(),
+            CropConstraint::Ratio(width, height) => format!("{}:{}", width, height),
+            CropConstraint::Size(width, height) => format!("{}x{}", width, height),
+        }
+    }
+
+    /* rectangle in image pixels, from the same start point and towards the same direction,
+    that satisfies the constraint: a ratio keeps the largest rectangle inside the dragged one,
+    a fixed size is moved back inside the image if it would go over its border */
+    pub fn apply(&self, coor: &Coordinates, image_width: u32, image_height: u32) -> Coordinates {
+        match *self {
+            CropConstraint::Free => coor.clone(),
+            CropConstraint::Ratio(ratio_width, ratio_height) => {
+                let ratio = ratio_width / ratio_height;
+                let mut width = coor.offset_x.abs();
+                let mut height = coor.offset_y.abs();
+                if height == 0.0 || width / height > ratio {
+                    width = (height * ratio).round();
+                } else {
+                    height = (width / ratio).round();
+                }
+                Coordinates {
+                    start_x: coor.start_x,
+                    start_y: coor.start_y,
+                    offset_x: width.copysign(coor.offset_x),
+                    offset_y: height.copysign(coor.offset_y),
+                }
+            }
+            CropConstraint::Size(width, height) => {
+                let width = width.min(image_width) as f64;
+                let height = height.min(image_height) as f64;
+                let mut start_x = coor.start_x.round();
+                let mut start_y = coor.start_y.round();
+                if coor.offset_x.is_sign_negative() {
+                    start_x -= width;
+                }
+                if coor.offset_y.is_sign_negative() {
+                    start_y -= height;
+                }
+                Coordinates {
+                    start_x: start_x.clamp(0.0, image_width as f64 - width),
+                    start_y: start_y.clamp(0.0, image_height as f64 - height),
+                    offset_x: width,
+                    offset_y: height,
+                }
+            }
+        }
+    }
+}
+
+/* presets offered in the crop panel on a fresh settings file */
+pub fn default_crop_presets() -> Vec<String> {
+    ["1:1", "4:3", "16:9", "1280x720", "1920x1080"]
+        .iter()
+        .map(|preset| preset.to_string())
+        .collect()
+}
@@ -0,0 +1,150 @@
+use crate::capture::ScreenInfo;
+use image::{Rgba, RgbaImage};
+
+// click-highlight ring drawn around the hotspot, in pixels of the capture
+pub const RING_RADIUS: f64 = 18.0;
+pub const RING_WIDTH: f64 = 4.0;
+pub const RING_COLOR: Rgba<u8> = Rgba([255, 200, 0, 170]);
+
+/* pointer image as shown on the screen, with straight (not premultiplied) alpha */
+#[derive(Clone, Debug, PartialEq)]
+pub struct CursorImage {
+    // pointer position on the desktop
+    pub x: i32,
+    pub y: i32,
+    // pixel of the image that is at the pointer position
+    pub hot_x: u32,
+    pub hot_y: u32,
+    pub image: RgbaImage,
+}
+
+/* cursor kept above the capture so that it can still be moved or removed; x, y is the
+hotspot in pixels of the first step of the timeline */
+#[derive(Clone, Debug, PartialEq)]
+pub struct CursorLayer {
+    pub cursor: CursorImage,
+    pub x: f64,
+    pub y: f64,
+    pub ring: bool,
+}
+
+impl CursorLayer {
+    /* layer over a capture of the given screen, None when the pointer is on another one */
+    pub fn on_screen(cursor: CursorImage, screen: &ScreenInfo, ring: bool) -> Option<CursorLayer> {
+        if !screen.region().contains(cursor.x, cursor.y) {
+            return None;
+        }
+        let scale_factor = screen.scale_factor as f64;
+        Some(CursorLayer {
+            x: (cursor.x - screen.x) as f64 * scale_factor,
+            y: (cursor.y - screen.y) as f64 * scale_factor,
+            cursor,
+            ring,
+        })
+    }
+
+    /* hotspot in pixels of a step whose top left corner is at origin in the first step */
+    pub fn position(&self, origin: (f64, f64)) -> (f64, f64) {
+        (self.x - origin.0, self.y - origin.1)
+    }
+
+    /* top left corner of the cursor image in pixels of the step, see position */
+    pub fn image_corner(&self, origin: (f64, f64)) -> (f64, f64) {
+        let (x, y) = self.position(origin);
+        (
+            x.round() - self.cursor.hot_x as f64,
+            y.round() - self.cursor.hot_y as f64,
+        )
+    }
+
+    /* true when the point of the step is on the cursor image or on its ring */
+    pub fn contains(&self, origin: (f64, f64), x: f64, y: f64) -> bool {
+        let (hot_x, hot_y) = self.position(origin);
+        let (left, top) = self.image_corner(origin);
+        let on_image = x >= left
+            && y >= top
+            && x < left + self.cursor.image.width() as f64
+            && y < top + self.cursor.image.height() as f64;
+        let on_ring = self.ring && (x - hot_x).hypot(y - hot_y) <= RING_RADIUS + RING_WIDTH;
+        on_image || on_ring
+    }
+
+    /* the step with the ring, if any, and the cursor drawn over it */
+    pub fn compose(&self, image: &RgbaImage, origin: (f64, f64)) -> RgbaImage {
+        let mut composed = image.clone();
+        let (x, y) = self.position(origin);
+        if self.ring {
+            draw_ring(&mut composed, x.round() + 0.5, y.round() + 0.5);
+        }
+        let (left, top) = self.image_corner(origin);
+        for (cursor_x, cursor_y, pixel) in self.cursor.image.enumerate_pixels() {
+            let image_x = left as i64 + cursor_x as i64;
+            let image_y = top as i64 + cursor_y as i64;
+            if image_x >= 0
+                && image_y >= 0
+                && image_x < composed.width() as i64
+                && image_y < composed.height() as i64
+            {
+                blend(
+                    composed.get_pixel_mut(image_x as u32, image_y as u32),
+                    pixel,
+                );
+            }
+        }
+        composed
+    }
+}
+
+/* cursor image from the 32 bit premultiplied ARGB pixels given by XFixes */
+pub fn from_premultiplied_argb(width: u32, height: u32, pixels: &[u32]) -> RgbaImage {
+    RgbaImage::from_fn(width, height, |x, y| {
+        let argb = pixels
+            .get((y * width + x) as usize)
+            .copied()
+            .unwrap_or_default();
+        let alpha = (argb >> 24) as u8;
+        let channel = |shift: u32| {
+            let value = (argb >> shift) & 0xff;
+            if alpha == 0 {
+                0
+            } else {
+                (value * 255 / alpha as u32).min(255) as u8
+            }
+        };
+        Rgba([channel(16), channel(8), channel(0), alpha])
+    })
+}
+
+/* ring of RING_COLOR centred at (center_x, center_y) */
+fn draw_ring(image: &mut RgbaImage, center_x: f64, center_y: f64) {
+    let reach = RING_RADIUS + RING_WIDTH;
+    let left = (center_x - reach).floor().max(0.0) as u32;
+    let top = (center_y - reach).floor().max(0.0) as u32;
+    let right = ((center_x + reach).ceil().max(0.0) as u32).min(image.width());
+    let bottom = ((center_y + reach).ceil().max(0.0) as u32).min(image.height());
+    for y in top..bottom {
+        for x in left..right {
+            let distance = (x as f64 + 0.5 - center_x).hypot(y as f64 + 0.5 - center_y);
+            if (distance - RING_RADIUS).abs() <= RING_WIDTH / 2.0 {
+                blend(image.get_pixel_mut(x, y), &RING_COLOR);
+            }
+        }
+    }
+}
+
+/* source pixel over the destination one */
+fn blend(destination: &mut Rgba<u8>, source: &Rgba<u8>) {
+    let source_alpha = source[3] as f64 / 255.0;
+    let destination_alpha = destination[3] as f64 / 255.0;
+    let alpha = source_alpha + destination_alpha * (1.0 - source_alpha);
+    if alpha <= 0.0 {
+        return;
+    }
+    for channel in 0..3 {
+        let value = (source[channel] as f64 * source_alpha
+            + destination[channel] as f64 * destination_alpha * (1.0 - source_alpha))
+            / alpha;
+        destination[channel] = value.round() as u8;
+    }
+    destination[3] = (alpha * 255.0).round() as u8;
+}
@@ -0,0 +1,269 @@
+use crate::capture::Region;
+use image::{Rgba, RgbaImage};
+
+// pixels compared for each shift tried by align, at most
+const ALIGN_SAMPLES: u64 = 20_000;
+// grey band between the two images shown side by side
+const SIDE_BY_SIDE_GAP: u32 = 8;
+const GAP_COLOR: Rgba<u8> = Rgba([128, 128, 128, 255]);
+const IGNORED_COLOR: Rgba<u8> = Rgba([64, 64, 64, 255]);
+
+/* when a pixel counts as changed: one of its channels, alpha included, differs by more than
+tolerance, it is not in an ignored region (in pixels of the images) and, with antialiasing set,
+it is not a pixel of an edge drawn a bit differently, see is_antialiasing */
+#[derive(Clone, Debug, Default, PartialEq)]
+pub struct DiffOptions {
+    pub tolerance: u8,
+    pub antialiasing: Option<u8>,
+    pub ignore: Vec<Region>,
+}
+
+/* how much two aligned images differ, ignored regions left out */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct DiffScore {
+    pub changed_pixels: u64,
+    pub total_pixels: u64,
+    // average of the largest channel difference of every pixel, 0 to 255
+    pub mean_difference: f64,
+}
+
+impl DiffScore {
+    /* share of changed pixels, 0 to 100 */
+    pub fn percent(&self) -> f64 {
+        if self.total_pixels == 0 {
+            return 0.0;
+        }
+        self.changed_pixels as f64 * 100.0 / self.total_pixels as f64
+    }
+
+    pub fn describe(&self) -> String {
+        format!(
+            "{:.2}% changed ({} of {} px), mean difference {:.1}",
+            self.percent(),
+            self.changed_pixels,
+            self.total_pixels,
+            self.mean_difference
+        )
+    }
+}
+
+/* largest difference between the channels of two pixels */
+pub fn pixel_difference(a: &Rgba<u8>, b: &Rgba<u8>) -> u8 {
+    a.0.iter()
+        .zip(b.0.iter())
+        .map(|(a, b)| a.abs_diff(*b))
+        .max()
+        .unwrap_or(0)
+}
+
+/* shift (dx, dy) of after over before, each at most max_shift pixels, for which the two
+images match best: the pixel (x, y) of after is then the pixel (x + dx, y + dy) of before.
+Shifts leaving less than half of the smaller image overlapping are not tried, and with equal
+matches the smaller shift wins */
+pub fn align(before: &RgbaImage, after: &RgbaImage, max_shift: u32) -> (i64, i64) {
+    let max_shift = max_shift as i64;
+    let smaller_area = (before.width() as u64 * before.height() as u64)
+        .min(after.width() as u64 * after.height() as u64);
+    let mut shifts: Vec<(i64, i64)> = (-max_shift..=max_shift)
+        .flat_map(|dy| (-max_shift..=max_shift).map(move |dx| (dx, dy)))
+        .collect();
+    shifts.sort_by_key(|(dx, dy)| dx.abs() + dy.abs());
+
+    let mut best = (0, 0);
+    let mut best_difference = f64::INFINITY;
+    for (dx, dy) in shifts {
+        let left = dx.max(0);
+        let top = dy.max(0);
+        let right = (before.width() as i64).min(after.width() as i64 + dx);
+        let bottom = (before.height() as i64).min(after.height() as i64 + dy);
+        if right <= left || bottom <= top {
+            continue;
+        }
+        let area = ((right - left) * (bottom - top)) as u64;
+        if area * 2 < smaller_area {
+            continue;
+        }
+        // on large images only a grid of pixels of the overlap is compared
+        let step = ((area as f64 / ALIGN_SAMPLES as f64).sqrt().ceil() as i64).max(1);
+        let mut total = 0u64;
+        let mut count = 0u64;
+        let mut y = top;
+        while y < bottom {
+            let mut x = left;
+            while x < right {
+                total += pixel_difference(
+                    before.get_pixel(x as u32, y as u32),
+                    after.get_pixel((x - dx) as u32, (y - dy) as u32),
+                ) as u64;
+                count += 1;
+                x += step;
+            }
+            y += step;
+        }
+        let difference = total as f64 / count as f64;
+        if difference < best_difference {
+            best_difference = difference;
+            best = (dx, dy);
+        }
+    }
+    best
+}
+
+/* before and after on two canvases of the same size, after moved by the shift found by
+align; where an image does not reach the canvas is transparent */
+pub fn aligned_pair(
+    before: &RgbaImage,
+    after: &RgbaImage,
+    shift: (i64, i64),
+) -> (RgbaImage, RgbaImage) {
+    let (dx, dy) = shift;
+    let left = dx.min(0);
+    let top = dy.min(0);
+    let right = (before.width() as i64).max(after.width() as i64 + dx);
+    let bottom = (before.height() as i64).max(after.height() as i64 + dy);
+    let width = (right - left) as u32;
+    let height = (bottom - top) as u32;
+    let mut before_canvas = RgbaImage::new(width, height);
+    let mut after_canvas = RgbaImage::new(width, height);
+    image::imageops::replace(&mut before_canvas, before, -left, -top);
+    image::imageops::replace(&mut after_canvas, after, dx - left, dy - top);
+    (before_canvas, after_canvas)
+}
+
+/* whether the colour of the pixel in each image lies between the colours around it in the
+other one, widened by tolerance on every channel: a shifted edge or a different anti-aliasing
+of text gives such pixels, a new drawing does not */
+pub fn is_antialiasing(
+    before: &RgbaImage,
+    after: &RgbaImage,
+    x: u32,
+    y: u32,
+    tolerance: u8,
+) -> bool {
+    between_neighbours(before, after.get_pixel(x, y), x, y, tolerance)
+        && between_neighbours(after, before.get_pixel(x, y), x, y, tolerance)
+}
+
+fn between_neighbours(image: &RgbaImage, pixel: &Rgba<u8>, x: u32, y: u32, tolerance: u8) -> bool {
+    let mut low = [u8::MAX; 4];
+    let mut high = [u8::MIN; 4];
+    for neighbour_y in y.saturating_sub(1)..=(y + 1).min(image.height() - 1) {
+        for neighbour_x in x.saturating_sub(1)..=(x + 1).min(image.width() - 1) {
+            if (neighbour_x, neighbour_y) == (x, y) {
+                continue;
+            }
+            let neighbour = image.get_pixel(neighbour_x, neighbour_y);
+            for channel in 0..4 {
+                low[channel] = low[channel].min(neighbour[channel]);
+                high[channel] = high[channel].max(neighbour[channel]);
+            }
+        }
+    }
+    (0..4).all(|channel| {
+        pixel[channel] >= low[channel].saturating_sub(tolerance)
+            && pixel[channel] <= high[channel].saturating_add(tolerance)
+    })
+}
+
+/* difference of the pixel, None if it is in an ignored region; Some(0) when the difference
+does not count as a change */
+fn counted_difference(
+    before: &RgbaImage,
+    after: &RgbaImage,
+    x: u32,
+    y: u32,
+    options: &DiffOptions,
+) -> Option<u8> {
+    if options
+        .ignore
+        .iter()
+        .any(|region| region.contains(x as i32, y as i32))
+    {
+        return None;
+    }
+    let difference = pixel_difference(before.get_pixel(x, y), after.get_pixel(x, y));
+    if difference <= options.tolerance {
+        return Some(0);
+    }
+    match options.antialiasing {
+        Some(tolerance) if is_antialiasing(before, after, x, y, tolerance) => Some(0),
+        _ => Some(difference),
+    }
+}
+
+/* compares two images of the same size */
+pub fn score(before: &RgbaImage, after: &RgbaImage, options: &DiffOptions) -> DiffScore {
+    let mut changed_pixels = 0;
+    let mut total_difference = 0u64;
+    let mut total_pixels = 0;
+    for (x, y, _) in before.enumerate_pixels() {
+        if let Some(difference) = counted_difference(before, after, x, y, options) {
+            if difference > 0 {
+                changed_pixels += 1;
+            }
+            total_difference += difference as u64;
+            total_pixels += 1;
+        }
+    }
+    DiffScore {
+        changed_pixels,
+        total_pixels,
+        mean_difference: if total_pixels == 0 {
+            0.0
+        } else {
+            total_difference as f64 / total_pixels as f64
+        },
+    }
+}
+
+/* after in light grey where nothing changed, changed pixels from yellow (small difference)
+to red (largest difference), ignored regions in dark grey; the images have the same size */
+pub fn heatmap(before: &RgbaImage, after: &RgbaImage, options: &DiffOptions) -> RgbaImage {
+    let mut map = RgbaImage::new(before.width(), before.height());
+    for (x, y, pixel) in map.enumerate_pixels_mut() {
+        *pixel = match counted_difference(before, after, x, y, options) {
+            None => IGNORED_COLOR,
+            Some(difference) if difference > 0 => Rgba([255, 255 - difference, 0, 255]),
+            Some(_) => {
+                let b = after.get_pixel(x, y);
+                let luminance = (b[0] as u32 * 299 + b[1] as u32 * 587 + b[2] as u32 * 114) / 1000
+                    * b[3] as u32
+                    / 255;
+                let grey = (170 + luminance / 3) as u8;
+                Rgba([grey, grey, grey, 255])
+            }
+        };
+    }
+    map
+}
+
+/* after laid over before with the given opacity, 0 shows only before and 1 only after */
+pub fn onion_skin(before: &RgbaImage, after: &RgbaImage, opacity: f64) -> RgbaImage {
+    let opacity = opacity.clamp(0.0, 1.0);
+    let mut blend = RgbaImage::new(before.width(), before.height());
+    for ((a, b), pixel) in before.pixels().zip(after.pixels()).zip(blend.pixels_mut()) {
+        for channel in 0..4 {
+            pixel[channel] =
+                (a[channel] as f64 * (1.0 - opacity) + b[channel] as f64 * opacity).round() as u8;
+        }
+    }
+    blend
+}
+
+/* before on the left and after on the right, separated by a grey band */
+pub fn side_by_side(before: &RgbaImage, after: &RgbaImage) -> RgbaImage {
+    let height = before.height().max(after.height());
+    let mut pair = RgbaImage::from_pixel(
+        before.width() + SIDE_BY_SIDE_GAP + after.width(),
+        height,
+        GAP_COLOR,
+    );
+    image::imageops::replace(&mut pair, before, 0, 0);
+    image::imageops::replace(
+        &mut pair,
+        after,
+        (before.width() + SIDE_BY_SIDE_GAP) as i64,
+        0,
+    );
+    pair
+}
@@ -0,0 +1,190 @@
+use crate::color::parse_hex;
+use image::imageops;
+use image::{Rgba, RgbaImage};
+use serde::{Deserialize, Serialize};
+
+// value of background for no background at all
+pub const TRANSPARENT: &str = "transparent";
+// matte of the formats without transparency when there is no background
+pub const WHITE: Rgba<u8> = Rgba([255, 255, 255, 255]);
+
+/* decorations added around the image when it is saved or copied, kept in the settings;
+colours are #RRGGBB like the recent colours */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Effects {
+    pub enabled: bool,
+    // space between the image and the edge of the output
+    pub padding: u32,
+    // colour or TRANSPARENT; with gradient_end set, the top colour of a vertical gradient
+    pub background: String,
+    pub gradient_end: String,
+    pub border_width: u32,
+    pub border_color: String,
+    pub corner_radius: u32,
+    pub shadow_blur: u32,
+    pub shadow_offset_x: i32,
+    pub shadow_offset_y: i32,
+    // 0 for no shadow, 100 for a black one
+    pub shadow_opacity: u32,
+}
+
+impl Default for Effects {
+    fn default() -> Self {
+        Effects {
+            enabled: false,
+            padding: 32,
+            background: TRANSPARENT.to_string(),
+            gradient_end: String::new(),
+            border_width: 0,
+            border_color: "#000000".to_string(),
+            corner_radius: 8,
+            shadow_blur: 16,
+            shadow_offset_x: 0,
+            shadow_offset_y: 8,
+            shadow_opacity: 40,
+        }
+    }
+}
+
+impl Effects {
+    /* same effects for an image scaled by factor, as the preview */
+    pub fn scaled(&self, factor: f64) -> Effects {
+        let scale = |value: u32| (value as f64 * factor).round() as u32;
+        Effects {
+            padding: scale(self.padding),
+            border_width: scale(self.border_width),
+            corner_radius: scale(self.corner_radius),
+            shadow_blur: scale(self.shadow_blur),
+            shadow_offset_x: (self.shadow_offset_x as f64 * factor).round() as i32,
+            shadow_offset_y: (self.shadow_offset_y as f64 * factor).round() as i32,
+            ..self.clone()
+        }
+    }
+
+    /* the image with its border and rounded corners, its shadow below it, on the background
+    grown by the padding; the sides are grown more when the shadow would not fit */
+    pub fn apply(&self, image: &RgbaImage) -> Result<RgbaImage, String> {
+        let background = if self.background == TRANSPARENT {
+            None
+        } else {
+            let top = color(&self.background)?;
+            let bottom = if self.gradient_end.trim().is_empty() {
+                top
+            } else {
+                color(&self.gradient_end)?
+            };
+            Some((top, bottom))
+        };
+
+        let border = self.border_width;
+        let mut content = RgbaImage::from_pixel(
+            image.width() + 2 * border,
+            image.height() + 2 * border,
+            color(&self.border_color)?,
+        );
+        imageops::replace(&mut content, image, border as i64, border as i64);
+        round_corners(&mut content, self.corner_radius);
+
+        let opacity = self.shadow_opacity.min(100);
+        let blur = if opacity > 0 {
+            self.shadow_blur as i64
+        } else {
+            0
+        };
+        let (dx, dy) = if opacity > 0 {
+            (self.shadow_offset_x as i64, self.shadow_offset_y as i64)
+        } else {
+            (0, 0)
+        };
+        let padding = self.padding as i64;
+        let left = padding.max(blur - dx) as u32;
+        let right = padding.max(blur + dx) as u32;
+        let top = padding.max(blur - dy) as u32;
+        let bottom = padding.max(blur + dy) as u32;
+        let width = left + content.width() + right;
+        let height = top + content.height() + bottom;
+
+        let mut output = RgbaImage::new(width, height);
+        if let Some((top_color, bottom_color)) = background {
+            for (_, y, pixel) in output.enumerate_pixels_mut() {
+                let share = if height > 1 {
+                    y as f64 / (height - 1) as f64
+                } else {
+                    0.0
+                };
+                *pixel = mix(top_color, bottom_color, share);
+            }
+        }
+        if opacity > 0 {
+            let mut shadow = RgbaImage::new(width, height);
+            for (x, y, pixel) in content.enumerate_pixels() {
+                let alpha = pixel[3] as u32 * opacity * 255 / (255 * 100);
+                shadow.put_pixel(
+                    (left as i64 + x as i64 + dx) as u32,
+                    (top as i64 + y as i64 + dy) as u32,
+                    Rgba([0, 0, 0, alpha as u8]),
+                );
+            }
+            if blur > 0 {
+                // three sigmas reach about as far as the blur
+                shadow = imageops::blur(&shadow, blur as f32 / 3.0);
+            }
+            imageops::overlay(&mut output, &shadow, 0, 0);
+        }
+        imageops::overlay(&mut output, &content, left as i64, top as i64);
+        Ok(output)
+    }
+
+    /* colour the transparent pixels get in a JPG or GIF: the background of the enabled
+    effects (its top colour with a gradient), otherwise white */
+    pub fn matte(&self) -> Rgba<u8> {
+        if !self.enabled || self.background == TRANSPARENT {
+            return WHITE;
+        }
+        color(&self.background).unwrap_or(WHITE)
+    }
+}
+
+fn color(text: &str) -> Result<Rgba<u8>, String> {
+    parse_hex(text.trim()).ok_or(format!("{} is not a colour like #FFFFFF", text))
+}
+
+fn mix(a: Rgba<u8>, b: Rgba<u8>, share: f64) -> Rgba<u8> {
+    let mut mixed = a;
+    for channel in 0..4 {
+        mixed[channel] =
+            (a[channel] as f64 * (1.0 - share) + b[channel] as f64 * share).round() as u8;
+    }
+    mixed
+}
+
+/* makes the corners outside the circles of the given radius transparent, smoothing the
+edge over one pixel */
+pub fn round_corners(image: &mut RgbaImage, radius: u32) {
+    let radius = radius.min(image.width() / 2).min(image.height() / 2);
+    if radius == 0 {
+        return;
+    }
+    let (width, height) = image.dimensions();
+    for y in 0..radius {
+        for x in 0..radius {
+            let distance = ((radius as f64 - x as f64 - 0.5).powi(2)
+                + (radius as f64 - y as f64 - 0.5).powi(2))
+            .sqrt();
+            let coverage = (radius as f64 - distance + 0.5).clamp(0.0, 1.0);
+            if coverage >= 1.0 {
+                continue;
+            }
+            for (corner_x, corner_y) in [
+                (x, y),
+                (width - 1 - x, y),
+                (x, height - 1 - y),
+                (width - 1 - x, height - 1 - y),
+            ] {
+                let pixel = image.get_pixel_mut(corner_x, corner_y);
+                pixel[3] = (pixel[3] as f64 * coverage).round() as u8;
+            }
+        }
+    }
+}
@@ -0,0 +1,174 @@
+use image::imageops::{self, FilterType};
+use image::{Rgba, RgbaImage};
+
+/* filters offered for resizing, in the order of the dropdown: Nearest keeps pixel art sharp,
+Lanczos gives the smoothest photos */
+pub const RESIZE_FILTERS: [(&str, FilterType); 4] = [
+    ("Nearest", FilterType::Nearest),
+    ("Bilinear", FilterType::Triangle),
+    ("Bicubic", FilterType::CatmullRom),
+    ("Lanczos", FilterType::Lanczos3),
+];
+
+// longest side of a resized or rotated image, a larger one would take gigabytes of memory
+pub const MAX_DIMENSION: u32 = 16384;
+
+/* change of the whole image recorded as a step of the timeline */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum GeometryEdit {
+    Resize {
+        width: u32,
+        height: u32,
+        filter: FilterType,
+    },
+    // quarter turns clockwise
+    RotateQuarter(u32),
+    // any angle in degrees, clockwise; the corners left uncovered get the background
+    Rotate {
+        degrees: f64,
+        background: Rgba<u8>,
+    },
+    FlipHorizontal,
+    FlipVertical,
+}
+
+impl GeometryEdit {
+    /* the edited image, an error when it would be larger than MAX_DIMENSION */
+    pub fn apply(&self, image: &RgbaImage) -> Result<RgbaImage, String> {
+        let size = match *self {
+            GeometryEdit::Resize { width, height, .. } => (width, height),
+            GeometryEdit::Rotate { degrees, .. } => rotated_size(image.dimensions(), degrees),
+            _ => (0, 0),
+        };
+        check_size(size)?;
+        Ok(match *self {
+            GeometryEdit::Resize {
+                width,
+                height,
+                filter,
+            } => imageops::resize(image, width.max(1), height.max(1), filter),
+            GeometryEdit::RotateQuarter(turns) => rotate_quarter(image, turns),
+            GeometryEdit::Rotate {
+                degrees,
+                background,
+            } => rotate(image, degrees, background),
+            GeometryEdit::FlipHorizontal => imageops::flip_horizontal(image),
+            GeometryEdit::FlipVertical => imageops::flip_vertical(image),
+        })
+    }
+}
+
+fn check_size((width, height): (u32, u32)) -> Result<(), String> {
+    if width > MAX_DIMENSION || height > MAX_DIMENSION {
+        return Err(format!(
+            "{}x{} is too large, the sides can be {} pixels at most",
+            width, height, MAX_DIMENSION
+        ));
+    }
+    Ok(())
+}
+
+/* size to resize an image of size to: width and height are pixels, or percentages of the
+current size when percent is set; with keep_ratio the height follows the width. Sides
+larger than MAX_DIMENSION are an error */
+pub fn target_size(
+    size: (u32, u32),
+    width: f64,
+    height: f64,
+    percent: bool,
+    keep_ratio: bool,
+) -> Result<(u32, u32), String> {
+    let (current_width, current_height) = (size.0 as f64, size.1 as f64);
+    let new_width = if percent {
+        current_width * width / 100.0
+    } else {
+        width
+    };
+    let new_height = if keep_ratio {
+        current_height * new_width / current_width
+    } else if percent {
+        current_height * height / 100.0
+    } else {
+        height
+    };
+    // the casts saturate, so huge values stay huge
+    let target = (
+        (new_width.round() as u32).max(1),
+        (new_height.round() as u32).max(1),
+    );
+    check_size(target)?;
+    Ok(target)
+}
+
+pub fn rotate_quarter(image: &RgbaImage, turns: u32) -> RgbaImage {
+    match turns % 4 {
+        1 => imageops::rotate90(image),
+        2 => imageops::rotate180(image),
+        3 => imageops::rotate270(image),
+        _ => image.clone(),
+    }
+}
+
+/* rotates clockwise on a canvas large enough for the whole image, sampling bilinearly; right
+angles are exact */
+pub fn rotate(image: &RgbaImage, degrees: f64, background: Rgba<u8>) -> RgbaImage {
+    let degrees = degrees.rem_euclid(360.0);
+    if (degrees / 90.0).fract() == 0.0 {
+        return rotate_quarter(image, (degrees / 90.0) as u32);
+    }
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (width, height) = (image.width() as f64, image.height() as f64);
+    let (new_width, new_height) = rotated_size(image.dimensions(), degrees);
+    let mut rotated = RgbaImage::new(new_width, new_height);
+    let (new_width, new_height) = (new_width as f64, new_height as f64);
+    for (x, y, pixel) in rotated.enumerate_pixels_mut() {
+        // centre of the pixel turned back into the source image
+        let dx = x as f64 + 0.5 - new_width / 2.0;
+        let dy = y as f64 + 0.5 - new_height / 2.0;
+        let source_x = dx * cos + dy * sin + width / 2.0 - 0.5;
+        let source_y = -dx * sin + dy * cos + height / 2.0 - 0.5;
+        *pixel = sample(image, source_x, source_y, background);
+    }
+    rotated
+}
+
+/* canvas holding the whole image of size rotated by degrees */
+fn rotated_size(size: (u32, u32), degrees: f64) -> (u32, u32) {
+    let (sin, cos) = degrees.to_radians().sin_cos();
+    let (width, height) = (size.0 as f64, size.1 as f64);
+    // the small margin keeps rounding errors from adding a row or column
+    let new_width = (width * cos.abs() + height * sin.abs() - 1e-6)
+        .ceil()
+        .max(1.0);
+    let new_height = (width * sin.abs() + height * cos.abs() - 1e-6)
+        .ceil()
+        .max(1.0);
+    (new_width as u32, new_height as u32)
+}
+
+/* bilinear blend of the four pixels around (x, y), those outside the image being background */
+fn sample(image: &RgbaImage, x: f64, y: f64, background: Rgba<u8>) -> Rgba<u8> {
+    let left = x.floor();
+    let top = y.floor();
+    let (fx, fy) = (x - left, y - top);
+    let pixel_at = |px: f64, py: f64| -> Rgba<u8> {
+        if px < 0.0 || py < 0.0 || px >= image.width() as f64 || py >= image.height() as f64 {
+            background
+        } else {
+            *image.get_pixel(px as u32, py as u32)
+        }
+    };
+    let corners = [
+        (pixel_at(left, top), (1.0 - fx) * (1.0 - fy)),
+        (pixel_at(left + 1.0, top), fx * (1.0 - fy)),
+        (pixel_at(left, top + 1.0), (1.0 - fx) * fy),
+        (pixel_at(left + 1.0, top + 1.0), fx * fy),
+    ];
+    let mut blended = [0.0; 4];
+    for (corner, weight) in corners {
+        for channel in 0..4 {
+            blended[channel] += corner[channel] as f64 * weight;
+        }
+    }
+    Rgba(blended.map(|value| value.round().clamp(0.0, 255.0) as u8))
+}
@@ -0,0 +1,23 @@
+/* core of Screen-PDS that does not depend on GTK, so it can be driven by tests */
+pub mod barcode;
+pub mod capture;
+pub mod cli;
+pub mod clipboard;
+pub mod collage;
+pub mod color;
+pub mod constraint;
+pub mod cursor;
+pub mod diff;
+pub mod effects;
+pub mod geometry;
+pub mod loupe;
+pub mod measure;
+pub mod ocr;
+pub mod redact;
+pub mod region;
+pub mod save;
+pub mod snap;
+pub mod stitch;
+pub mod timeline;
+pub mod trim;
+pub mod view;
@@ -0,0 +1,52 @@
+use image::{Rgba, RgbaImage};
+
+/* square of pixels around the pointer, None where it falls outside of the image */
+pub struct Loupe {
+    pub x: u32,
+    pub y: u32,
+    pub radius: u32,
+    cells: Vec<Option<Rgba<u8>>>,
+}
+
+impl Loupe {
+    /* pixels from (x - radius, y - radius) to (x + radius, y + radius) */
+    pub fn sample(image: &RgbaImage, x: u32, y: u32, radius: u32) -> Loupe {
+        let side = 2 * radius + 1;
+        let mut cells = Vec::with_capacity((side * side) as usize);
+        for row in 0..side {
+            for column in 0..side {
+                let image_x = x as i64 + column as i64 - radius as i64;
+                let image_y = y as i64 + row as i64 - radius as i64;
+                if image_x >= 0
+                    && image_y >= 0
+                    && image_x < image.width() as i64
+                    && image_y < image.height() as i64
+                {
+                    cells.push(Some(*image.get_pixel(image_x as u32, image_y as u32)));
+                } else {
+                    cells.push(None);
+                }
+            }
+        }
+        Loupe {
+            x,
+            y,
+            radius,
+            cells,
+        }
+    }
+
+    /* number of cells on each side */
+    pub fn side(&self) -> u32 {
+        2 * self.radius + 1
+    }
+
+    pub fn cell(&self, column: u32, row: u32) -> Option<Rgba<u8>> {
+        self.cells[(row * self.side() + column) as usize]
+    }
+
+    /* the hovered pixel */
+    pub fn center(&self) -> Option<Rgba<u8>> {
+        self.cell(self.radius, self.radius)
+    }
+}
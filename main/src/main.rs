@@ -1,43 +1,63 @@
-use arboard::{Clipboard, ImageData};
 use chrono::{Datelike, Local, Timelike};
-use gif;
 use gtk::prelude::*;
 use gtk::{
-    glib, Align, Application, ApplicationWindow, Box, Button, ContentFit, DropDown, GestureDrag,
-    Grid, Label, Orientation, Picture, Window,
+    cairo, gdk, glib, Align, Application, ApplicationWindow, Box, Button, CheckButton, ContentFit,
+    DrawingArea, DropDown, DropTarget, Entry, EventControllerFocus, EventControllerKey,
+    EventControllerMotion, EventControllerScroll, EventControllerScrollFlags, EventSequenceState,
+    GestureClick, GestureDrag, GestureZoom, Grid, Label, Orientation, Overlay, Picture, PolicyType,
+    PropagationPhase, Scale, ScrolledWindow, SpinButton, StringList, TextView, Window, WrapMode,
 };
-use image::{open, ImageFormat};
+use image::{open, DynamicImage, Rgba, RgbaImage};
 use livesplit_hotkey::{Hook, Hotkey, KeyCode, Modifiers};
 use native_dialog::FileDialog;
-use screenshots::Screen;
+use screen_pds::barcode::{scan_area, Barcode};
+use screen_pds::capture::{
+    capture_fullscreen, capture_region_to, capture_screenshot_with_delay, origin_screen,
+    CaptureBackend, HidingWait, Region, ScreenInfo, SelfHiding, SystemBackend, HIDDEN_POLL,
+};
+use screen_pds::cli::{parse_args, run, Failure};
+use screen_pds::clipboard::{
+    clipboard_image, release_clipboard, set_clipboard_text, ClipboardPayload, CopyFormat,
+    CopyPolicy,
+};
+use screen_pds::collage::{collage, square_columns, CollageLayout, CollageOptions};
+use screen_pds::color::{self, ColorFormat};
+use screen_pds::constraint::{default_crop_presets, CropConstraint};
+use screen_pds::cursor::{
+    from_premultiplied_argb, CursorLayer, RING_COLOR, RING_RADIUS, RING_WIDTH,
+};
+use screen_pds::diff::{
+    align, aligned_pair, heatmap, onion_skin, score, side_by_side, DiffOptions,
+};
+use screen_pds::effects::{Effects, TRANSPARENT};
+use screen_pds::geometry::{target_size, GeometryEdit, MAX_DIMENSION, RESIZE_FILTERS};
+use screen_pds::loupe::Loupe;
+use screen_pds::measure::{snap_to_edges, Measurement};
+use screen_pds::ocr::{recognize_area, words_to_text};
+use screen_pds::redact::{
+    default_redact_patterns, find_sensitive, redact, Detection, RedactPattern, RedactStyle,
+};
+use screen_pds::region::{cut_region, NamedRegion};
+use screen_pds::save::{extension_for, free_path, write_image};
+use screen_pds::snap::{snap_rectangle, window_at, windows_in_capture};
+use screen_pds::stitch::{scrolling_capture, ScrollCapture, ScrollOptions};
+use screen_pds::timeline::{
+    session_path, Coordinates, Timeline, TMP_IMAGE_EXTENSION, TMP_IMAGE_NAME,
+};
+use screen_pds::view::ViewTransform;
 use serde::{Deserialize, Serialize};
 use serde_json;
+use std::cell::{Cell, RefCell};
 use std::io::Read;
-use std::path::PathBuf;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::{AtomicBool, Ordering};
 use std::sync::{mpsc, Arc, Condvar, Mutex};
 use std::{fs, time};
 use std::{fs::File, thread};
 
-#[derive(Clone)]
-struct Coordinates {
-    start_x: f64,
-    start_y: f64,
-    offset_x: f64,
-    offset_y: f64,
-}
-
-impl Default for Coordinates {
-    fn default() -> Self {
-        Coordinates {
-            start_x: f64::NAN,
-            start_y: f64::NAN,
-            offset_x: f64::NAN,
-            offset_y: f64::NAN,
-        }
-    }
-}
-
 #[derive(Clone, Debug, Serialize, Deserialize)]
+#[serde(default)]
 struct JSONStruct {
     new_shortcut_modif: String,
     new_shortcut_key: String,
@@ -49,30 +69,142 @@ struct JSONStruct {
     redo_shortcut_key: String,
     cancel_shortcut_modif: String,
     cancel_shortcut_key: String,
+    pick_shortcut_modif: String,
+    pick_shortcut_key: String,
+    scan_shortcut_modif: String,
+    scan_shortcut_key: String,
     default_location: String,
+    copy_policy: String,
+    editor_mode: String,
+    color_format: String,
+    recent_colors: Vec<String>,
+    crop_presets: Vec<String>,
+    last_region: Option<Region>,
+    saved_regions: Vec<NamedRegion>,
+    include_cursor: bool,
+    click_ring: bool,
+    settle_delay_ms: u64,
+    restore_main_window: bool,
+    ocr_language: String,
+    redact_patterns: Vec<RedactPattern>,
+    auto_scroll: bool,
+    effects: Effects,
+}
+
+impl Default for JSONStruct {
+    fn default() -> Self {
+        JSONStruct {
+            new_shortcut_modif: "CONTROL".to_string(),
+            new_shortcut_key: "N".to_string(),
+            save_shortcut_modif: "CONTROL".to_string(),
+            save_shortcut_key: "S".to_string(),
+            undo_shortcut_modif: "CONTROL".to_string(),
+            undo_shortcut_key: "Z".to_string(),
+            redo_shortcut_modif: "CONTROL".to_string(),
+            redo_shortcut_key: "Y".to_string(),
+            cancel_shortcut_modif: "CONTROL".to_string(),
+            cancel_shortcut_key: "E".to_string(),
+            pick_shortcut_modif: "CONTROL".to_string(),
+            pick_shortcut_key: "P".to_string(),
+            scan_shortcut_modif: "CONTROL".to_string(),
+            scan_shortcut_key: "B".to_string(),
+            default_location: "/.".to_string(),
+            copy_policy: CopyPolicy::EveryStep.to_json(),
+            editor_mode: "FULLSCREEN".to_string(),
+            color_format: "HEX".to_string(),
+            recent_colors: Vec::new(),
+            crop_presets: default_crop_presets(),
+            last_region: None,
+            saved_regions: Vec::new(),
+            include_cursor: false,
+            click_ring: false,
+            settle_delay_ms: 500,
+            restore_main_window: false,
+            ocr_language: "eng".to_string(),
+            redact_patterns: default_redact_patterns(),
+            auto_scroll: false,
+            effects: Effects::default(),
+        }
+    }
 }
 
 const APP_ID: &str = "org.gtk_rs.Screen-PDS";
-const TMP_FOLDER_NAME: &str = "screenshots";
-const TMP_IMAGE_NAME: &str = "tmp";
-const TMP_IMAGE_EXTENSION: &str = "png";
+// default save location on a fresh install; the timeline steps are kept apart in
+// TMP_FOLDER_NAME inside it, the only folder removed at exit
+const SAVE_FOLDER_NAME: &str = "screenshots";
+const TMP_FOLDER_NAME: &str = ".tmp";
 const SETTINGS_FILENAME: &str = "settings.json";
 const DEFAULT_IMAGE_NAME: &str = "capture";
+const EDITOR_TOOLS: [&str; 8] = [
+    "Crop",
+    "View",
+    "Pick colour",
+    "Measure",
+    "Cursor",
+    "Copy text",
+    "Scan codes",
+    "Transform",
+];
+const COLOR_HISTORY_SIZE: usize = 8;
+const ZOOM_STEP: f64 = 1.25;
+const MIN_ZOOM: f64 = 0.05;
+const MAX_ZOOM: f64 = 32.0;
+// pixels shown on each side of the hovered one and their size in the loupe
+const LOUPE_RADIUS: u32 = 7;
+const LOUPE_CELL: f64 = 10.0;
+// largest side of the effects preview in the editor
+const EFFECTS_PREVIEW_SIZE: u32 = 240;
+// band below every image of a collage for its label
+const COLLAGE_LABEL_HEIGHT: u32 = 28;
+// largest shift between two captures that the compare view makes up for
+const COMPARE_MAX_SHIFT: u32 = 32;
+// how far and how loosely the measurement tool looks for the edge of a uniform region
+const SNAP_DISTANCE: u32 = 8;
+const SNAP_TOLERANCE: u8 = 8;
+// average brightness step (0-255) along a side of the selection that counts as an edge
+const STRONG_EDGE: f64 = 40.0;
+const TRIM_TOLERANCE: f64 = 8.0;
 
 fn main() -> glib::ExitCode {
+    // with arguments a single command runs without opening any window
+    let args: Vec<String> = std::env::args().skip(1).collect();
+    if !args.is_empty() {
+        return run_command(&args);
+    }
+
     let app = Application::builder().application_id(APP_ID).build();
 
     app.connect_activate(build_ui);
+    // hand the last copied capture over to the clipboard manager before leaving
+    app.connect_shutdown(|_| store_clipboard());
 
     let output = app.run();
 
+    release_clipboard();
     let _ = clean_tmp();
 
     output
 }
 
+/* command line use, see screen_pds::cli */
+fn run_command(args: &[String]) -> glib::ExitCode {
+    let result = parse_args(args)
+        .map_err(Failure::Error)
+        .and_then(|command| run(&command, &SystemBackend));
+    match result {
+        Ok(output) => {
+            println!("{}", output);
+            glib::ExitCode::SUCCESS
+        }
+        Err(failure) => {
+            eprintln!("{}", failure.message());
+            glib::ExitCode::from(failure.exit_code())
+        }
+    }
+}
+
 fn build_ui(app: &Application) {
-    let label = build_label("  HOW TO\n1) Click <+ New> to capture screen with chosen delay.\n2) Then, draw rectangles to crop the capture.\n3) Click <Undo> and <Redo> to cycle through cropped images timeline.\n4) When you are done, press OS key, select the main window and click <Save> with the chosen format.\n5) Or click <Cancel> if you want to quit the cropping procedure.".to_string());
+    let label = build_label("  HOW TO\n1) Click <+ New> to capture screen with chosen delay.\n2) Then, draw rectangles to crop the capture.\n3) Click <Undo> and <Redo> to cycle through cropped images timeline.\n4) When you are done, click <Save> in the editor toolbar, or <Save As> to choose where to save it.\n5) Or click <Cancel> if you want to quit the cropping procedure.\n6) Click <Copy> to put the current image on the clipboard in the chosen flavour.\n7) Click <Open>, drop an image on this window or click <Paste> to edit an existing image.\n8) Click <Region> to draw the part of the screen to capture, <Repeat region> to capture it again.\n9) Click <Scrolling> and scroll the last region (or the screen) to capture a long page, then wait.\n10) Every capture opens in its own editor: click <Save all> or <Close all> to finish them together.".to_string());
     let button_new = build_button("+ New".to_string());
     let button_save = build_button("Save".to_string());
     let button_settings = build_button("Settings".to_string());
@@ -86,6 +218,32 @@ fn build_ui(app: &Application) {
     let button_undo = build_button("Undo".to_string());
     let button_redo = build_button("Redo".to_string());
     let button_cancel = build_button("Cancel".to_string());
+    let button_copy = build_button("Copy".to_string());
+    let copy_format_list = build_dropdown(&["Image (PNG)", "HTML", "File URI", "Markdown"]);
+    let button_open = build_button("Open".to_string());
+    let button_paste = build_button("Paste".to_string());
+    let button_region = build_button("Region".to_string());
+    let button_repeat_region = build_button("Repeat region".to_string());
+    let saved_region_names: Vec<String> = retrieve_data_from_json()
+        .saved_regions
+        .into_iter()
+        .map(|saved| saved.name)
+        .collect();
+    let saved_region_list = build_dropdown(
+        &saved_region_names
+            .iter()
+            .map(|name| name.as_str())
+            .collect::<Vec<&str>>(),
+    );
+    let button_capture_saved = build_button("Capture saved".to_string());
+    let region_name_entry = Entry::builder()
+        .placeholder_text("Region name")
+        .valign(Align::Center)
+        .build();
+    let button_save_region = build_button("Save last region".to_string());
+    let button_scrolling = build_button("Scrolling".to_string());
+    let button_save_all = build_button("Save all".to_string());
+    let button_close_all = build_button("Close all".to_string());
 
     let content = Grid::new();
     content.attach(&label, 0, 0, 7, 1);
@@ -97,6 +255,19 @@ fn build_ui(app: &Application) {
     content.attach(&button_undo, 0, 2, 1, 1);
     content.attach(&button_redo, 1, 2, 1, 1);
     content.attach(&button_cancel, 2, 2, 1, 1);
+    content.attach(&button_copy, 3, 2, 1, 1);
+    content.attach(&copy_format_list, 4, 2, 1, 1);
+    content.attach(&button_open, 0, 3, 1, 1);
+    content.attach(&button_paste, 1, 3, 1, 1);
+    content.attach(&button_region, 0, 4, 1, 1);
+    content.attach(&button_repeat_region, 1, 4, 1, 1);
+    content.attach(&saved_region_list, 2, 4, 1, 1);
+    content.attach(&button_capture_saved, 3, 4, 1, 1);
+    content.attach(&region_name_entry, 4, 4, 1, 1);
+    content.attach(&button_save_region, 5, 4, 1, 1);
+    content.attach(&button_scrolling, 0, 5, 1, 1);
+    content.attach(&button_save_all, 1, 5, 1, 1);
+    content.attach(&button_close_all, 2, 5, 1, 1);
 
     let window = ApplicationWindow::builder()
         .application(app)
@@ -112,10 +283,25 @@ fn build_ui(app: &Application) {
     let tx_thread_function_number_undo = tx_thread_function_number.clone();
     let tx_thread_function_number_redo = tx_thread_function_number.clone();
     let tx_thread_function_number_cancel = tx_thread_function_number.clone();
+    let tx_thread_function_number_copy = tx_thread_function_number.clone();
+    let tx_thread_function_number_open = tx_thread_function_number.clone();
+    let tx_thread_function_number_drop = tx_thread_function_number.clone();
+    let tx_thread_function_number_paste = tx_thread_function_number.clone();
+    let tx_thread_function_number_region = tx_thread_function_number.clone();
+    let tx_thread_function_number_repeat_region = tx_thread_function_number.clone();
+    let tx_thread_function_number_capture_saved = tx_thread_function_number.clone();
+    let tx_thread_function_number_scrolling = tx_thread_function_number.clone();
+    let tx_thread_function_number_save_all = tx_thread_function_number.clone();
+    let tx_thread_function_number_close_all = tx_thread_function_number.clone();
+    let tx_thread_function_number_editor = tx_thread_function_number.clone();
 
     let flag_function_selector = Arc::new(Mutex::new(0 as u32));
     let flag_function_selector_worker_thread = Arc::clone(&flag_function_selector);
 
+    // file dropped on the main window, opened by the next "open" tick
+    let dropped_path: Arc<Mutex<Option<PathBuf>>> = Arc::new(Mutex::new(None));
+    let dropped_path_target = Arc::clone(&dropped_path);
+
     button_new.connect_clicked(move |_| {
         let result = tx_thread_function_number_new.send(1);
         match result {
@@ -170,6 +356,142 @@ fn build_ui(app: &Application) {
         }
     });
 
+    button_copy.connect_clicked(move |_| {
+        let result = tx_thread_function_number_copy.send(6);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    button_open.connect_clicked(move |_| {
+        let result = tx_thread_function_number_open.send(7);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    button_region.connect_clicked(move |_| {
+        let result = tx_thread_function_number_region.send(12);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    button_repeat_region.connect_clicked(move |_| {
+        let result = tx_thread_function_number_repeat_region.send(13);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    button_capture_saved.connect_clicked(move |_| {
+        let result = tx_thread_function_number_capture_saved.send(14);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    button_scrolling.connect_clicked(move |_| {
+        let result = tx_thread_function_number_scrolling.send(22);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    button_save_all.connect_clicked(move |_| {
+        let result = tx_thread_function_number_save_all.send(25);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    button_close_all.connect_clicked(move |_| {
+        let result = tx_thread_function_number_close_all.send(26);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    // the last region drawn or repeated is kept under the typed name
+    let saved_region_list_save = saved_region_list.clone();
+    button_save_region.connect_clicked(move |_| {
+        let mut settings = retrieve_data_from_json();
+        let name = region_name_entry.text().trim().to_string();
+        match settings.last_region {
+            Some(region) if !name.is_empty() => {
+                settings.saved_regions.push(NamedRegion {
+                    name: name.clone(),
+                    region,
+                });
+                write_settings(&settings);
+                if let Some(model) = saved_region_list_save.model().and_downcast::<StringList>() {
+                    model.append(&name);
+                    saved_region_list_save.set_selected(settings.saved_regions.len() as u32 - 1);
+                }
+                region_name_entry.set_text("");
+            }
+            Some(_) => eprintln!("Type a name for the region"),
+            None => eprintln!("No region captured yet"),
+        }
+    });
+
+    button_paste.connect_clicked(move |_| {
+        let result = tx_thread_function_number_paste.send(8);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+
+    let drop_target = DropTarget::new(gdk::FileList::static_type(), gdk::DragAction::COPY);
+    drop_target.connect_drop(move |_, value, _, _| {
+        let files = match value.get::<gdk::FileList>() {
+            Ok(files) => files.files(),
+            Err(_) => return false,
+        };
+        match files.first().and_then(|file| file.path()) {
+            Some(path) => {
+                *dropped_path_target.lock().unwrap() = Some(path);
+                let result = tx_thread_function_number_drop.send(7);
+                match result {
+                    Ok(_) => {}
+                    Err(error) => {
+                        eprintln!("{}", error);
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    });
+    window.add_controller(drop_target);
+
     let _hotkey_thread = thread::spawn(move || loop {
         let shortcuts = retrieve_data_from_json();
 
@@ -185,12 +507,18 @@ fn build_ui(app: &Application) {
             &shortcuts.cancel_shortcut_modif,
             &shortcuts.cancel_shortcut_key,
         );
+        let pick_hotkey =
+            retrieve_hotkey(&shortcuts.pick_shortcut_modif, &shortcuts.pick_shortcut_key);
+        let scan_hotkey =
+            retrieve_hotkey(&shortcuts.scan_shortcut_modif, &shortcuts.scan_shortcut_key);
 
         let tx_thread_function_number_hotkey_new = tx_thread_function_number_hotkey.clone();
         let tx_thread_function_number_hotkey_save = tx_thread_function_number_hotkey.clone();
         let tx_thread_function_number_hotkey_undo = tx_thread_function_number_hotkey.clone();
         let tx_thread_function_number_hotkey_redo = tx_thread_function_number_hotkey.clone();
         let tx_thread_function_number_hotkey_cancel = tx_thread_function_number_hotkey.clone();
+        let tx_thread_function_number_hotkey_pick = tx_thread_function_number_hotkey.clone();
+        let tx_thread_function_number_hotkey_scan = tx_thread_function_number_hotkey.clone();
 
         let hook = Hook::new().unwrap();
         hook.register(new_hotkey, move || {
@@ -215,337 +543,3956 @@ fn build_ui(app: &Application) {
         })
         .unwrap();
 
-        hook.register(undo_hotkey, move || {
-            let result = tx_thread_function_number_hotkey_undo.send(3);
-            match result {
+        hook.register(undo_hotkey, move || {
+            let result = tx_thread_function_number_hotkey_undo.send(3);
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+        })
+        .unwrap();
+
+        hook.register(redo_hotkey, move || {
+            let result = tx_thread_function_number_hotkey_redo.send(4);
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+        })
+        .unwrap();
+
+        hook.register(cancel_hotkey, move || {
+            let result = tx_thread_function_number_hotkey_cancel.send(5);
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+        })
+        .unwrap();
+
+        hook.register(pick_hotkey, move || {
+            let result = tx_thread_function_number_hotkey_pick.send(10);
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+        })
+        .unwrap();
+
+        hook.register(scan_hotkey, move || {
+            let result = tx_thread_function_number_hotkey_scan.send(20);
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+        })
+        .unwrap();
+
+        std::thread::sleep(time::Duration::from_millis(5000));
+    });
+
+    let _worker_thread = thread::spawn(move || loop {
+        let output = rx_thread_function_number.recv();
+
+        match output {
+            Ok(result) => match result {
+                1 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 1;
+                }
+                2 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 2;
+                }
+                3 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 3;
+                }
+                4 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 4;
+                }
+                5 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 5;
+                }
+                6 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 6;
+                }
+                7 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 7;
+                }
+                8 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 8;
+                }
+                9 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 9;
+                }
+                10 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 10;
+                }
+                11 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 11;
+                }
+                12 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 12;
+                }
+                13 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 13;
+                }
+                14 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 14;
+                }
+                15 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 15;
+                }
+                16 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 16;
+                }
+                17 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 17;
+                }
+                18 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 18;
+                }
+                19 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 19;
+                }
+                20 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 20;
+                }
+                21 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 21;
+                }
+                22 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 22;
+                }
+                23 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 23;
+                }
+                24 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 24;
+                }
+                25 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 25;
+                }
+                26 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 26;
+                }
+                27 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 27;
+                }
+                28 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 28;
+                }
+                29 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 29;
+                }
+                30 => {
+                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
+                    *flag = 30;
+                }
+                _ => eprintln!("Other"),
+            },
+            Err(error) => {
+                eprintln!("Error: {}", error);
+            }
+        }
+    });
+
+    set_default_json();
+    let backend: std::boxed::Box<dyn CaptureBackend> = std::boxed::Box::new(SystemBackend);
+    // every capture opens in its own editor with its own timeline; actions go to the focused
+    // editor, or to the last one focused
+    let mut sessions: Vec<Session> = Vec::new();
+    let mut active: usize = 0;
+    let mut session_count: u32 = 0;
+    let mut region_coor: Arc<Mutex<Coordinates>> = Arc::new(Mutex::new(Coordinates::default()));
+    let mut region_condvar = Arc::new(Condvar::new());
+    let mut region_overlay: Option<RegionOverlay> = None;
+    let mut region_session: u32 = 0;
+    // a scrolling capture stitches frames on another thread until the content stops moving
+    let mut scrolling: Option<Scrolling> = None;
+    let scroll_result: ResultSlot<Result<ScrollCapture, String>> = Arc::new(Mutex::new(None));
+
+    // a capture hides the app and returns, the tick runs again every HIDDEN_POLL until the
+    // windows are gone and the capture can be taken
+    let mut hiding: Option<Hiding> = None;
+    let tick_handle: Rc<RefCell<Option<TickFn>>> = Rc::new(RefCell::new(None));
+    let tick_again = Rc::clone(&tick_handle);
+
+    let tick = move || {
+        if let Some(index) = sessions
+            .iter()
+            .position(|session| session.editor.window.is_active())
+        {
+            active = index;
+        }
+        let (action, mut hidden) = match hiding.take() {
+            Some(mut waiting) => {
+                if !waiting.is_ready() {
+                    hiding = Some(waiting);
+                    return glib::ControlFlow::Continue;
+                }
+                (waiting.action, Some(waiting))
+            }
+            None => (
+                std::mem::replace(&mut *flag_function_selector.lock().unwrap(), 0),
+                None,
+            ),
+        };
+        // the screen is being captured until the scrolling capture ends, no other capture;
+        // its end is picked up here too in case another action took the place of 30
+        let action = match scrolling {
+            Some(_) if scroll_result.lock().unwrap().is_some() => 30,
+            Some(_) if hides_app(action, false, false) => 0,
+            _ => action,
+        };
+        if hidden.is_none()
+            && hides_app(
+                action,
+                sessions.get(active).is_some(),
+                region_overlay.is_some(),
+            )
+        {
+            let started = Hiding::start(&window, action);
+            let waiting = Rc::clone(&started.windows.waiting);
+            hiding = Some(started);
+            let tick_hidden = Rc::clone(&tick_again);
+            glib::timeout_add_local(HIDDEN_POLL, move || {
+                if waiting.get() {
+                    run_tick(&tick_hidden);
+                }
+                if waiting.get() {
+                    glib::ControlFlow::Continue
+                } else {
+                    glib::ControlFlow::Break
+                }
+            });
+            return glib::ControlFlow::Continue;
+        }
+        match action {
+            1 => {
+                // new
+                session_count += 1;
+                let timeline = Timeline::new(create_starting_tmp_path_file(session_count));
+                let result = capture_hidden(&mut hidden, || {
+                    capture_screenshot_with_delay(
+                        backend.as_ref(),
+                        timer_list.selected(),
+                        &timeline.first_path(),
+                    )
+                    .map(|_| capture_context(backend.as_ref()))
+                });
+                match result {
+                    Ok(context) => {
+                        let mut session = Session::open(
+                            timeline,
+                            session_count,
+                            &tx_thread_function_number_editor,
+                            &extension_list,
+                        );
+                        set_capture_scale_factor(&session.editor, backend.as_ref());
+                        session.editor.set_capture_context(context);
+                        copy_step_to_clipboard(
+                            || output_path(&session.timeline, &session.editor),
+                            copy_format_list.selected(),
+                        );
+                        sessions.push(session);
+                        active = sessions.len() - 1;
+                    }
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        window.present();
+                    }
+                }
+            }
+            2 | 9 => {
+                // save as (2) asks where to save, save (9) uses the default location
+                let ask_path = action == 2;
+                if let Some(session) = sessions.get(active) {
+                    let current_path = output_path(&session.timeline, &session.editor);
+                    let result = save_image(extension_list.selected(), &current_path, ask_path);
+                    if let Some(saved_path) = result {
+                        if copy_policy() != CopyPolicy::Never {
+                            copy_to_clipboard(
+                                &current_path,
+                                Some(&saved_path),
+                                copy_format_list.selected(),
+                            );
+                        }
+                        sessions.remove(active).editor.close();
+                        if sessions.is_empty() {
+                            window.present();
+                        }
+                    }
+                }
+            }
+            3 => {
+                // undo
+                if let Some(session) = sessions.get_mut(active) {
+                    if let Some(current_path) = session.timeline.undo() {
+                        session.show(&current_path);
+                        copy_step_to_clipboard(
+                            || output_path(&session.timeline, &session.editor),
+                            copy_format_list.selected(),
+                        );
+                    }
+                }
+            }
+            4 => {
+                // redo
+                if let Some(session) = sessions.get_mut(active) {
+                    if let Some(current_path) = session.timeline.redo() {
+                        session.show(&current_path);
+                        copy_step_to_clipboard(
+                            || output_path(&session.timeline, &session.editor),
+                            copy_format_list.selected(),
+                        );
+                    }
+                }
+            }
+            5 => {
+                // cancel
+                if let Some(running) = scrolling.as_ref() {
+                    running.stop.store(true, Ordering::Relaxed);
+                } else if let Some(overlay) = region_overlay.take() {
+                    overlay.close();
+                    window.present();
+                } else if active < sessions.len() {
+                    sessions.remove(active).editor.close();
+                    if sessions.is_empty() {
+                        window.present();
+                    }
+                }
+            }
+            6 => {
+                // copy
+                if let Some(session) = sessions.get(active) {
+                    copy_to_clipboard(
+                        &output_path(&session.timeline, &session.editor),
+                        None,
+                        copy_format_list.selected(),
+                    );
+                }
+            }
+            7 => {
+                // open a dropped file or one chosen in the dialog
+                let dropped = dropped_path.lock().unwrap().take();
+                let source = match dropped {
+                    Some(path) => Some(path),
+                    None => choose_image_to_open(),
+                };
+                if let Some(source) = source {
+                    session_count += 1;
+                    let mut timeline = Timeline::new(create_starting_tmp_path_file(session_count));
+                    let result = open(source.as_path())
+                        .map_err(|error| error.to_string())
+                        .and_then(|img| timeline.start(&img));
+                    match result {
+                        Ok(_) => {
+                            sessions.push(Session::open(
+                                timeline,
+                                session_count,
+                                &tx_thread_function_number_editor,
+                                &extension_list,
+                            ));
+                            active = sessions.len() - 1;
+                        }
+                        Err(error) => {
+                            eprintln!("Error: {}", error);
+                        }
+                    }
+                }
+            }
+            8 => {
+                // paste an image from the clipboard
+                session_count += 1;
+                let mut timeline = Timeline::new(create_starting_tmp_path_file(session_count));
+                let result = clipboard_image().and_then(|img| timeline.start(&img));
+                match result {
+                    Ok(_) => {
+                        sessions.push(Session::open(
+                            timeline,
+                            session_count,
+                            &tx_thread_function_number_editor,
+                            &extension_list,
+                        ));
+                        active = sessions.len() - 1;
+                    }
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                    }
+                }
+            }
+            10 => {
+                // pick colour: capture the whole screen right away and open it with the eyedropper,
+                // in a new editor even when others are open, so that the current screen is sampled
+                session_count += 1;
+                let timeline = Timeline::new(create_starting_tmp_path_file(session_count));
+                let result = capture_hidden(&mut hidden, || {
+                    capture_fullscreen(backend.as_ref(), &timeline.first_path())
+                        .map(|_| capture_context(backend.as_ref()))
+                });
+                match result {
+                    Ok(context) => {
+                        let mut session = Session::open(
+                            timeline,
+                            session_count,
+                            &tx_thread_function_number_editor,
+                            &extension_list,
+                        );
+                        set_capture_scale_factor(&session.editor, backend.as_ref());
+                        session.editor.set_capture_context(context);
+                        session.editor.set_tool(2);
+                        sessions.push(session);
+                        active = sessions.len() - 1;
+                    }
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        window.present();
+                    }
+                }
+            }
+            11 => {
+                // crop to the rectangle typed in the crop panel
+                if let Some(session) = sessions.get_mut(active) {
+                    if let Some(new_path) = session.timeline.crop(&session.editor.typed_crop()) {
+                        session.show(&new_path);
+                        copy_step_to_clipboard(
+                            || output_path(&session.timeline, &session.editor),
+                            copy_format_list.selected(),
+                        );
+                    }
+                }
+            }
+            12 if region_overlay.is_none() => {
+                // region: draw the rectangle on a frozen capture of the screen
+                session_count += 1;
+                region_session = session_count;
+                let capture_path = create_starting_tmp_path_file(session_count);
+                let result = capture_hidden(&mut hidden, || {
+                    capture_fullscreen(backend.as_ref(), &capture_path)
+                        .map(|screen| (screen, capture_context(backend.as_ref())))
+                });
+                match result {
+                    Ok((screen, context)) => {
+                        let (overlay, new_coor, new_condvar) = RegionOverlay::open(
+                            &capture_path,
+                            screen,
+                            context.windows,
+                            &tx_thread_function_number_editor,
+                        );
+                        (region_overlay, region_coor, region_condvar) =
+                            (Some(overlay), new_coor, new_condvar);
+                    }
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        window.present();
+                    }
+                }
+            }
+            13 | 14 => {
+                // capture again the last region (13) or the saved one chosen in the list (14)
+                let settings = retrieve_data_from_json();
+                let region = if action == 13 {
+                    settings.last_region
+                } else {
+                    settings
+                        .saved_regions
+                        .get(saved_region_list.selected() as usize)
+                        .map(|saved| saved.region)
+                };
+                if region_overlay.is_none() {
+                    match region {
+                        Some(region) => {
+                            session_count += 1;
+                            let timeline =
+                                Timeline::new(create_starting_tmp_path_file(session_count));
+                            let result = capture_hidden(&mut hidden, || {
+                                capture_region_to(backend.as_ref(), region, &timeline.first_path())
+                            });
+                            match result {
+                                Ok(_) => {
+                                    copy_step_to_clipboard(
+                                        || timeline.first_path(),
+                                        copy_format_list.selected(),
+                                    );
+                                    let session = Session::open(
+                                        timeline,
+                                        session_count,
+                                        &tx_thread_function_number_editor,
+                                        &extension_list,
+                                    );
+                                    set_capture_scale_factor(&session.editor, backend.as_ref());
+                                    sessions.push(session);
+                                    active = sessions.len() - 1;
+                                }
+                                Err(error) => {
+                                    eprintln!("Error: {}", error);
+                                    window.present();
+                                }
+                            }
+                        }
+                        None => eprintln!("No region to capture"),
+                    }
+                }
+            }
+            15 => {
+                // auto-trim: crop away the uniform borders of the current image
+                if let Some(session) = sessions.get_mut(active) {
+                    let tolerance = session.editor.trim_tolerance();
+                    if let Some(new_path) = session.timeline.trim(tolerance) {
+                        session.show(&new_path);
+                        copy_step_to_clipboard(
+                            || output_path(&session.timeline, &session.editor),
+                            copy_format_list.selected(),
+                        );
+                    }
+                }
+            }
+            16 => {
+                // copy text: read the whole current image
+                if let Some(session) = sessions.get(active) {
+                    read_text(
+                        &session.editor,
+                        &session.timeline,
+                        None,
+                        &tx_thread_function_number_editor,
+                    );
+                }
+            }
+            17 => {
+                // auto-redact: look for sensitive text and let the user review it
+                if let Some(session) = sessions.get(active) {
+                    let settings = retrieve_data_from_json();
+                    match session.timeline.current_image() {
+                        Ok(img) => run_in_background(
+                            &session.editor.text_result,
+                            28,
+                            &tx_thread_function_number_editor,
+                            move || {
+                                TextResult::Sensitive(
+                                    recognize_area(&img, None, &settings.ocr_language).and_then(
+                                        |words| find_sensitive(&words, &settings.redact_patterns),
+                                    ),
+                                )
+                            },
+                        ),
+                        Err(error) => {
+                            eprintln!("{}", error);
+                        }
+                    }
+                }
+            }
+            18 => {
+                // redact the detections kept in the review as a new step
+                for session in sessions.iter_mut() {
+                    let detections = session.editor.redactions.borrow_mut().take();
+                    if let Some(detections) = detections {
+                        let style = session.editor.redact_style.get();
+                        let result = session.timeline.current_image().and_then(|img| {
+                            session.timeline.push(&redact(&img, &detections, style))
+                        });
+                        match result {
+                            Ok(new_path) => {
+                                session.show(&new_path);
+                                copy_step_to_clipboard(
+                                    || output_path(&session.timeline, &session.editor),
+                                    copy_format_list.selected(),
+                                );
+                            }
+                            Err(error) => {
+                                eprintln!("{}", error);
+                            }
+                        }
+                    }
+                }
+            }
+            19 => {
+                // scan codes in the whole current image
+                if let Some(session) = sessions.get(active) {
+                    scan_codes(
+                        &session.editor,
+                        &session.timeline,
+                        None,
+                        &tx_thread_function_number_editor,
+                    );
+                }
+            }
+            20 => {
+                // scan codes: capture the whole screen right away and look for codes in it
+                match sessions.get(active) {
+                    Some(session) => {
+                        session.editor.set_tool(6);
+                        scan_codes(
+                            &session.editor,
+                            &session.timeline,
+                            None,
+                            &tx_thread_function_number_editor,
+                        );
+                    }
+                    None => {
+                        session_count += 1;
+                        let timeline = Timeline::new(create_starting_tmp_path_file(session_count));
+                        let result = capture_hidden(&mut hidden, || {
+                            capture_fullscreen(backend.as_ref(), &timeline.first_path())
+                                .map(|_| capture_context(backend.as_ref()))
+                        });
+                        match result {
+                            Ok(context) => {
+                                let mut session = Session::open(
+                                    timeline,
+                                    session_count,
+                                    &tx_thread_function_number_editor,
+                                    &extension_list,
+                                );
+                                set_capture_scale_factor(&session.editor, backend.as_ref());
+                                session.editor.set_capture_context(context);
+                                session.editor.set_tool(6);
+                                scan_codes(
+                                    &session.editor,
+                                    &session.timeline,
+                                    None,
+                                    &tx_thread_function_number_editor,
+                                );
+                                sessions.push(session);
+                                active = sessions.len() - 1;
+                            }
+                            Err(error) => {
+                                eprintln!("Error: {}", error);
+                                window.present();
+                            }
+                        }
+                    }
+                }
+            }
+            21 => {
+                // compare: the first step against the current one, others can be chosen
+                if let Some(session) = sessions.get(active) {
+                    CompareView::open(
+                        &session.editor.window,
+                        session.timeline.step_paths(),
+                        session.timeline.current_index(),
+                    );
+                }
+            }
+            22 if region_overlay.is_none() => {
+                // scrolling capture of the last region, or of the screen, stitched in one image;
+                // the app stays hidden until it ends (30) or is stopped with cancel
+                let settings = retrieve_data_from_json();
+                let options = ScrollOptions {
+                    auto_scroll: settings.auto_scroll,
+                    ..ScrollOptions::default()
+                };
+                let region = match settings.last_region {
+                    Some(region) => Ok(region),
+                    None => origin_screen(backend.as_ref()).map(|screen| screen.region()),
+                };
+                match region {
+                    Ok(region) => {
+                        session_count += 1;
+                        let stop = Arc::new(AtomicBool::new(false));
+                        let stop_worker = Arc::clone(&stop);
+                        run_in_background(
+                            &scroll_result,
+                            30,
+                            &tx_thread_function_number_editor,
+                            move || {
+                                scrolling_capture(&SystemBackend, region, &options, &stop_worker)
+                            },
+                        );
+                        scrolling = Some(Scrolling {
+                            stop,
+                            number: session_count,
+                            hidden: hidden.take(),
+                        });
+                    }
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                        window.present();
+                    }
+                }
+            }
+            23 => {
+                // collage: choose steps and files to lay out
+                if let Some(session) = sessions.get(active) {
+                    CollageView::open(
+                        &session.editor,
+                        session.timeline.step_paths(),
+                        &tx_thread_function_number_editor,
+                    );
+                }
+            }
+            24 => {
+                // add the collage made in a collage window as a new step of its capture
+                for session in sessions.iter_mut() {
+                    let made = session.editor.collage.take();
+                    if let Some(image) = made {
+                        match session.timeline.push(&DynamicImage::ImageRgba8(image)) {
+                            Ok(new_path) => {
+                                session.show(&new_path);
+                                copy_step_to_clipboard(
+                                    || output_path(&session.timeline, &session.editor),
+                                    copy_format_list.selected(),
+                                );
+                            }
+                            Err(error) => {
+                                eprintln!("{}", error);
+                            }
+                        }
+                    }
+                }
+            }
+            25 => {
+                // save all: every open capture in the default location, the saved ones close;
+                // none is saved or closed while the default location is the tmp folder
+                match check_default_location() {
+                    Ok(_) => {
+                        sessions.retain(|session| {
+                            let current_path = output_path(&session.timeline, &session.editor);
+                            match save_image(extension_list.selected(), &current_path, false) {
+                                Some(_) => {
+                                    session.editor.close();
+                                    false
+                                }
+                                None => true,
+                            }
+                        });
+                        if sessions.is_empty() {
+                            window.present();
+                        }
+                    }
+                    Err(error) => {
+                        eprintln!("Error: {}", error);
+                    }
+                }
+            }
+            26 => {
+                // close all without saving
+                for session in sessions.drain(..) {
+                    session.editor.close();
+                }
+                window.present();
+            }
+            27 => {
+                // resize, rotate or flip the current image, a new step for each edit clicked
+                for session in sessions.iter_mut() {
+                    let edits = session.editor.transform_panel.pending.take();
+                    for edit in edits {
+                        // the cursor layer cannot follow the edit, it is merged into the image
+                        // and only comes back on the steps before it, see set_merged
+                        let layer = session.editor.view.cursor_layer.borrow().clone();
+                        let origin = session.timeline.current_origin();
+                        let result = session.timeline.current_image().and_then(|img| {
+                            let mut image = img.to_rgba8();
+                            if let Some(layer) = layer {
+                                image = layer.compose(&image, origin);
+                            }
+                            let edited = edit.apply(&image)?;
+                            session
+                                .timeline
+                                .push_transformed(&DynamicImage::ImageRgba8(edited))
+                        });
+                        match result {
+                            Ok(new_path) => {
+                                session.show(&new_path);
+                                copy_step_to_clipboard(
+                                    || output_path(&session.timeline, &session.editor),
+                                    copy_format_list.selected(),
+                                );
+                            }
+                            Err(error) => {
+                                eprintln!("{}", error);
+                            }
+                        }
+                    }
+                }
+            }
+            28 => {
+                // text recognised in the background: copied and shown, or sensitive text to review
+                for session in sessions.iter() {
+                    let result = session.editor.text_result.lock().unwrap().take();
+                    match result {
+                        Some(TextResult::Read(Ok(text))) => {
+                            match set_clipboard_text(&text) {
+                                Ok(_) => {}
+                                Err(error) => {
+                                    eprintln!("{}", error);
+                                }
+                            }
+                            show_text_window(&session.editor.window, &text);
+                        }
+                        Some(TextResult::Sensitive(Ok(detections))) if detections.is_empty() => {
+                            eprintln!("Nothing to redact");
+                        }
+                        Some(TextResult::Sensitive(Ok(detections))) => {
+                            session.editor.view.set_detections(detections);
+                            show_redact_review(&session.editor, &tx_thread_function_number_editor);
+                        }
+                        Some(TextResult::Read(Err(error)))
+                        | Some(TextResult::Sensitive(Err(error))) => {
+                            eprintln!("{}", error);
+                        }
+                        None => {}
+                    }
+                }
+            }
+            29 => {
+                // codes scanned in the background: listed, and copied when there is only one
+                for session in sessions.iter() {
+                    let result = session.editor.code_result.lock().unwrap().take();
+                    match result {
+                        Some(Ok(codes)) => {
+                            if let [code] = codes.as_slice() {
+                                match set_clipboard_text(&code.payload) {
+                                    Ok(_) => {}
+                                    Err(error) => {
+                                        eprintln!("{}", error);
+                                    }
+                                }
+                            }
+                            session.editor.code_panel.show_codes(Some(&codes));
+                        }
+                        Some(Err(error)) => {
+                            eprintln!("{}", error);
+                        }
+                        None => {}
+                    }
+                }
+            }
+            30 => {
+                // scrolling capture ended: the stitched image opens in a new editor
+                let result = scroll_result.lock().unwrap().take();
+                if let (Some(result), Some(mut running)) = (result, scrolling.take()) {
+                    let mut timeline = Timeline::new(create_starting_tmp_path_file(running.number));
+                    let result = capture_hidden(&mut running.hidden, || {
+                        result.and_then(|capture| {
+                            if let Some(error) = capture.error {
+                                eprintln!("The scrolling capture stopped early: {}", error);
+                            }
+                            timeline.start(&DynamicImage::ImageRgba8(capture.image))
+                        })
+                    });
+                    match result {
+                        Ok(first_path) => {
+                            copy_step_to_clipboard(
+                                || first_path.clone(),
+                                copy_format_list.selected(),
+                            );
+                            let session = Session::open(
+                                timeline,
+                                running.number,
+                                &tx_thread_function_number_editor,
+                                &extension_list,
+                            );
+                            set_capture_scale_factor(&session.editor, backend.as_ref());
+                            sessions.push(session);
+                            active = sessions.len() - 1;
+                        }
+                        Err(error) => {
+                            eprintln!("Error: {}", error);
+                            window.present();
+                        }
+                    }
+                }
+            }
+            _ => {}
+        }
+        // hidden for a capture the action did not take after all
+        if let Some(mut hiding) = hidden.take() {
+            hiding.windows.restore();
+        }
+
+        // editors closed from the window manager
+        let open_sessions = sessions.len();
+        sessions.retain(|session| {
+            if session.editor.close_requested.get() {
+                session.editor.close();
+                false
+            } else {
+                true
+            }
+        });
+        if sessions.len() < open_sessions && sessions.is_empty() {
+            window.present();
+        }
+        active = active.min(sessions.len().saturating_sub(1));
+
+        for session in sessions.iter_mut() {
+            let mut coor = session.coor.lock().unwrap();
+            if coor.is_complete() {
+                let open_editor = &session.editor;
+                if open_editor.tool() == 0 {
+                    // crop
+                    let image_coor =
+                        open_editor.shape_selection(&open_editor.to_image_coordinates(&coor));
+                    if let Some(new_path) = session.timeline.crop(&image_coor) {
+                        session
+                            .editor
+                            .show(&new_path, session.timeline.current_origin());
+                        copy_step_to_clipboard(
+                            || output_path(&session.timeline, &session.editor),
+                            copy_format_list.selected(),
+                        );
+                        coor.reset();
+                    }
+                } else if open_editor.tool() == 5 {
+                    // copy text of the dragged rectangle
+                    let image_coor = open_editor.to_image_coordinates(&coor);
+                    coor.reset();
+                    open_editor.view.set_selection(None);
+                    read_text(
+                        open_editor,
+                        &session.timeline,
+                        Some(&image_coor),
+                        &tx_thread_function_number_editor,
+                    );
+                } else if open_editor.tool() == 6 {
+                    // scan codes in the dragged rectangle
+                    let image_coor = open_editor.to_image_coordinates(&coor);
+                    coor.reset();
+                    open_editor.view.set_selection(None);
+                    scan_codes(
+                        open_editor,
+                        &session.timeline,
+                        Some(&image_coor),
+                        &tx_thread_function_number_editor,
+                    );
+                } else {
+                    coor.reset();
+                }
+            }
+            drop(coor);
+            session.condvar.notify_one();
+        }
+
+        let selection = match &region_overlay {
+            Some(overlay) => {
+                let mut coor = region_coor.lock().unwrap();
+                let selection = if let Some(window) = overlay.clicked_window.take() {
+                    Some(window_selection(window))
+                } else if coor.is_complete() {
+                    let image_coor = overlay.view.transform().to_image_coordinates(&coor);
+                    coor.reset();
+                    Some(overlay.view.snap_rectangle(&image_coor))
+                } else {
+                    None
+                };
+                region_condvar.notify_one();
+                selection
+            }
+            None => None,
+        };
+        if let Some(image_coor) = selection {
+            // region drawn: cut it from the frozen capture and open it in the editor
+            if let Some(overlay) = region_overlay.take() {
+                overlay.close();
+                match overlay.cut(&image_coor) {
+                    Some((image, region)) => {
+                        let mut settings = retrieve_data_from_json();
+                        settings.last_region = Some(region);
+                        write_settings(&settings);
+                        // the frozen capture was stored in the folder of the new capture
+                        let mut timeline = Timeline::new(overlay.capture_path.clone());
+                        match timeline.start(&image) {
+                            Ok(first_path) => {
+                                copy_step_to_clipboard(
+                                    || first_path.clone(),
+                                    copy_format_list.selected(),
+                                );
+                                let session = Session::open(
+                                    timeline,
+                                    region_session,
+                                    &tx_thread_function_number_editor,
+                                    &extension_list,
+                                );
+                                session
+                                    .editor
+                                    .view
+                                    .scale_factor
+                                    .set(Some(overlay.screen.scale_factor as f64));
+                                sessions.push(session);
+                                active = sessions.len() - 1;
+                            }
+                            Err(error) => {
+                                eprintln!("Error: {}", error);
+                                window.present();
+                            }
+                        }
+                    }
+                    None => window.present(),
+                }
+            }
+        }
+
+        glib::ControlFlow::Continue
+    };
+
+    *tick_handle.borrow_mut() = Some(std::boxed::Box::new(tick));
+    glib::timeout_add_seconds_local(1, move || {
+        run_tick(&tick_handle);
+        glib::ControlFlow::Continue
+    });
+}
+
+/* image to save or copy: the current step, with the cursor layer drawn over it if there is one
+and the output effects around it when they are enabled */
+fn output_path(timeline: &Timeline, editor: &Editor) -> PathBuf {
+    let current_path = timeline.current_path();
+    let layer = editor.view.cursor_layer.borrow();
+    editor.effects_panel.store();
+    let effects = retrieve_data_from_json().effects;
+    if layer.is_none() && !effects.enabled {
+        return current_path;
+    }
+    let mut composed_path = current_path.clone();
+    composed_path.set_file_name(TMP_IMAGE_NAME.to_owned() + "_output." + TMP_IMAGE_EXTENSION);
+    let result = timeline.current_image().and_then(|img| {
+        let mut image = img.to_rgba8();
+        if let Some(layer) = layer.as_ref() {
+            image = layer.compose(&image, timeline.current_origin());
+        }
+        if effects.enabled {
+            image = effects.apply(&image)?;
+        }
+        DynamicImage::ImageRgba8(image)
+            .save(&composed_path)
+            .map_err(|error| error.to_string())
+    });
+    match result {
+        Ok(_) => composed_path,
+        Err(error) => {
+            eprintln!("{}", error);
+            current_path
+        }
+    }
+}
+
+/* what else was on the captured screen: the windows, to snap the selection to and select
+them with a click, and the pointer if the settings ask for it */
+#[derive(Default)]
+struct CaptureContext {
+    windows: Vec<Region>,
+    cursor: Option<CursorLayer>,
+}
+
+/* read while the app is still hidden, so that its own windows are not among them */
+fn capture_context(backend: &dyn CaptureBackend) -> CaptureContext {
+    let screen = match origin_screen(backend) {
+        Ok(screen) => screen,
+        Err(error) => {
+            eprintln!("{}", error);
+            return CaptureContext::default();
+        }
+    };
+    let windows = match backend.list_windows() {
+        Ok(windows) => windows_in_capture(&windows, &screen),
+        Err(error) => {
+            eprintln!("{}", error);
+            Vec::new()
+        }
+    };
+    let settings = retrieve_data_from_json();
+    let cursor = if settings.include_cursor {
+        match backend.cursor() {
+            Ok(cursor) => CursorLayer::on_screen(cursor, &screen, settings.click_ring),
+            Err(error) => {
+                eprintln!("{}", error);
+                None
+            }
+        }
+    } else {
+        None
+    };
+    CaptureContext { windows, cursor }
+}
+
+/* every window of the app, hidden for a capture; the main window only comes back
+afterwards if the settings ask for it, otherwise when the editor is closed */
+struct AppWindows {
+    main: ApplicationWindow,
+    hidden: Vec<Window>,
+    restore_main: bool,
+    // set until the windows are restored, keeps the quick re-runs of the tick going
+    waiting: Rc<Cell<bool>>,
+}
+
+impl SelfHiding for AppWindows {
+    fn hide(&mut self) {
+        for toplevel in Window::list_toplevels() {
+            if let Ok(window) = toplevel.downcast::<Window>() {
+                if window.is_visible() {
+                    window.set_visible(false);
+                    self.hidden.push(window);
+                }
+            }
+        }
+    }
+
+    /* the unmap happens once the main loop has handled the hide, so this is polled from it */
+    fn is_hidden(&mut self) -> bool {
+        self.hidden.iter().all(|window| !window.is_mapped())
+    }
+
+    fn restore(&mut self) {
+        for window in self.hidden.drain(..) {
+            if self.restore_main || &window != self.main.upcast_ref::<Window>() {
+                window.present();
+            }
+        }
+        self.waiting.set(false);
+    }
+}
+
+/* the app hidden for the capture of action, which runs on a later tick once the windows
+are off the screen, see HidingWait */
+struct Hiding {
+    windows: AppWindows,
+    wait: HidingWait,
+    action: u32,
+}
+
+impl Hiding {
+    fn start(window: &ApplicationWindow, action: u32) -> Hiding {
+        let settings = retrieve_data_from_json();
+        let mut windows = AppWindows {
+            main: window.clone(),
+            hidden: Vec::new(),
+            restore_main: settings.restore_main_window,
+            waiting: Rc::new(Cell::new(true)),
+        };
+        let wait = HidingWait::start(
+            &mut windows,
+            time::Duration::from_millis(settings.settle_delay_ms),
+        );
+        Hiding {
+            windows,
+            wait,
+            action,
+        }
+    }
+
+    fn is_ready(&mut self) -> bool {
+        self.wait.is_ready(&mut self.windows)
+    }
+}
+
+/* a scrolling capture running on another thread, with the app hidden until it ends */
+struct Scrolling {
+    stop: Arc<AtomicBool>,
+    number: u32,
+    hidden: Option<Hiding>,
+}
+
+/* actions capturing the screen, for which the app gets out of the way first */
+fn hides_app(action: u32, editor_open: bool, region_open: bool) -> bool {
+    match action {
+        1 | 10 => true,
+        20 => !editor_open,
+        12 | 13 | 14 | 22 => !region_open,
+        _ => false,
+    }
+}
+
+/* runs the capture while the app is hidden, then brings it back */
+fn capture_hidden<T>(hidden: &mut Option<Hiding>, capture: impl FnOnce() -> T) -> T {
+    let result = capture();
+    if let Some(mut hiding) = hidden.take() {
+        hiding.windows.restore();
+    }
+    result
+}
+
+// the tick of build_ui, run every second and while the app is hidden for a capture
+type TickFn = std::boxed::Box<dyn FnMut() -> glib::ControlFlow>;
+
+/* runs the tick unless it is already running */
+fn run_tick(tick: &RefCell<Option<TickFn>>) {
+    if let Ok(mut tick) = tick.try_borrow_mut() {
+        if let Some(tick) = tick.as_mut() {
+            tick();
+        }
+    }
+}
+
+/* logical points of a fresh capture are pixels of the captured screen over its scale factor */
+fn set_capture_scale_factor(editor: &Editor, backend: &dyn CaptureBackend) {
+    match origin_screen(backend) {
+        Ok(screen) => editor
+            .view
+            .scale_factor
+            .set(Some(screen.scale_factor as f64)),
+        Err(error) => {
+            eprintln!("{}", error);
+        }
+    }
+}
+
+/* save the image in a chosen extension in a chosen path (or in the default location
+when ask_path is false), returns where it was saved */
+fn save_image(current_selected: u32, tmp_path: &Path, ask_path: bool) -> Option<PathBuf> {
+    let tmp = open(tmp_path);
+
+    match tmp {
+        Ok(tmp_image) => match extension_for(current_selected) {
+            Some(extension) => {
+                let result = if ask_path {
+                    choose_path(extension)
+                } else {
+                    match default_save_path(extension) {
+                        Ok(path) => Some(path),
+                        Err(error) => {
+                            eprintln!("Error: {}", error);
+                            None
+                        }
+                    }
+                };
+                match result {
+                    Some(path) => match write_image(
+                        &tmp_image,
+                        current_selected,
+                        &path,
+                        retrieve_data_from_json().effects.matte(),
+                    ) {
+                        Ok(_) => Some(path),
+                        Err(error) => {
+                            eprintln!("Error: {}", error);
+                            None
+                        }
+                    },
+                    None => None,
+                }
+            }
+            None => {
+                eprintln!("Error");
+                None
+            }
+        },
+        Err(_) => None,
+    }
+}
+
+/* frozen capture of the whole screen, shown fullscreen to draw the region to capture on */
+struct RegionOverlay {
+    window: Window,
+    view: EditorView,
+    screen: ScreenInfo,
+    capture_path: PathBuf,
+    clicked_window: Rc<Cell<Option<Region>>>,
+}
+
+impl RegionOverlay {
+    /* windows are the bounds of the windows on the screen, in pixels of the capture */
+    fn open(
+        path: &PathBuf,
+        screen: ScreenInfo,
+        windows: Vec<Region>,
+        tx: &mpsc::Sender<u32>,
+    ) -> (RegionOverlay, Arc<Mutex<Coordinates>>, Arc<Condvar>) {
+        let window = Window::builder().title("Screen-PDS region").build();
+        let view = EditorView::new(path);
+        window.set_child(Some(&view.overlay));
+        view.connect_keys(&window);
+        *view.windows.borrow_mut() = windows;
+        view.connect_window_hover(|| true);
+
+        // a click on a window takes the whole window as the region
+        let clicked_window = Rc::new(Cell::new(None));
+        let click = GestureClick::new();
+        let view_click = view.clone();
+        let clicked_window_click = Rc::clone(&clicked_window);
+        click.connect_released(move |_, _, _, _| {
+            if let Some(window) = view_click.hovered_window.get() {
+                clicked_window_click.set(Some(window));
+            }
+        });
+        view.picture.add_controller(click);
+
+        // Escape or closing the window gives up the region, the same as Cancel
+        let key_ctrl = EventControllerKey::new();
+        let tx_escape = tx.clone();
+        key_ctrl.connect_key_pressed(move |_, key, _, _| {
+            if key != gdk::Key::Escape {
+                return glib::Propagation::Proceed;
+            }
+            let result = tx_escape.send(5);
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+            glib::Propagation::Stop
+        });
+        window.add_controller(key_ctrl);
+        let tx_close = tx.clone();
+        window.connect_close_request(move |_| {
+            let result = tx_close.send(5);
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+            glib::Propagation::Stop
+        });
+
+        // outline of the region while it is drawn
+        let drag = GestureDrag::new();
+        let start = Rc::new(Cell::new(None));
+        let view_begin = view.clone();
+        let start_begin = Rc::clone(&start);
+        drag.connect_drag_begin(move |_, x, y| {
+            if view_begin.panning.get() {
+                start_begin.set(None);
+            } else {
+                start_begin.set(Some((x, y)));
+            }
+        });
+        let view_update = view.clone();
+        drag.connect_drag_update(move |_, offset_x, offset_y| {
+            if let Some((start_x, start_y)) = start.get() {
+                let coor = view_update
+                    .snap_rectangle(&view_update.transform().to_image_coordinates(&Coordinates {
+                        start_x,
+                        start_y,
+                        offset_x,
+                        offset_y,
+                    }))
+                    .normalized();
+                view_update.set_selection(Some((
+                    coor.start_x,
+                    coor.start_y,
+                    coor.offset_x,
+                    coor.offset_y,
+                )));
+            }
+        });
+        view.picture.add_controller(drag);
+
+        window.present();
+        window.fullscreen();
+
+        let (coor, condvar) = draw_area(&view.picture, &view.panning);
+        (
+            RegionOverlay {
+                window,
+                view,
+                screen,
+                capture_path: path.clone(),
+                clicked_window,
+            },
+            coor,
+            condvar,
+        )
+    }
+
+    /* the drawn rectangle, in pixels of the capture, cut out of the full resolution capture */
+    fn cut(&self, coor: &Coordinates) -> Option<(DynamicImage, Region)> {
+        match open(&self.capture_path) {
+            Ok(capture) => cut_region(&capture, &self.screen, coor),
+            Err(error) => {
+                eprintln!("{}", error);
+                None
+            }
+        }
+    }
+
+    fn close(&self) {
+        self.window.destroy();
+    }
+}
+
+/* a capture being edited: its editor window, the timeline of its steps and the rectangle
+dragged on it */
+struct Session {
+    editor: Editor,
+    timeline: Timeline,
+    coor: Arc<Mutex<Coordinates>>,
+    condvar: Arc<Condvar>,
+}
+
+impl Session {
+    /* opens an editor on the first step of the timeline, numbered in its title */
+    fn open(
+        timeline: Timeline,
+        number: u32,
+        tx: &mpsc::Sender<u32>,
+        extension_list: &DropDown,
+    ) -> Session {
+        let (editor, coor, condvar) = Editor::open(&timeline.first_path(), tx, extension_list);
+        editor
+            .window
+            .set_title(Some(&format!("Screen-PDS editor {}", number)));
+        Session {
+            editor,
+            timeline,
+            coor,
+            condvar,
+        }
+    }
+
+    /* shows the step of the timeline at path */
+    fn show(&mut self, path: &PathBuf) {
+        self.editor.show(path, self.timeline.current_origin());
+        self.editor
+            .cursor_panel
+            .set_merged(self.timeline.is_transformed());
+    }
+}
+
+/* capture editor: toolbar on top of the current step of the timeline */
+struct Editor {
+    window: Window,
+    view: EditorView,
+    tool_list: DropDown,
+    crop_panel: CropPanel,
+    cursor_panel: CursorPanel,
+    code_panel: CodePanel,
+    transform_panel: TransformPanel,
+    effects_panel: EffectsPanel,
+    redact_style: Rc<Cell<RedactStyle>>,
+    // collage made in the collage window, added to the timeline by the next tick
+    collage: Rc<RefCell<Option<RgbaImage>>>,
+    // detections kept in the redact review, redacted by the next tick
+    redactions: Rc<RefCell<Option<Vec<Detection>>>>,
+    // set when the window is closed from the window manager, the next tick closes the editor
+    close_requested: Rc<Cell<bool>>,
+    // filled by the OCR thread, see read_text
+    text_result: ResultSlot<TextResult>,
+    // filled by the zbarimg thread, see scan_codes
+    code_result: ResultSlot<Result<Vec<Barcode>, String>>,
+    first_path: PathBuf,
+    capture_windows: Vec<Region>,
+}
+
+impl Editor {
+    /* opens the editor on the first step of the timeline, fullscreen or as a normal window */
+    fn open(
+        path: &PathBuf,
+        tx: &mpsc::Sender<u32>,
+        extension_list: &DropDown,
+    ) -> (Editor, Arc<Mutex<Coordinates>>, Arc<Condvar>) {
+        let window = Window::builder().title("Screen-PDS editor").build();
+        let view = EditorView::new(path);
+
+        let toolbar = Box::new(Orientation::Horizontal, 0);
+        toolbar.append(&build_toolbar_button("Save", 9, tx));
+        toolbar.append(&build_toolbar_button("Save As", 2, tx));
+        toolbar.append(&build_toolbar_button("Copy", 6, tx));
+        toolbar.append(&build_toolbar_button("Undo", 3, tx));
+        toolbar.append(&build_toolbar_button("Redo", 4, tx));
+        toolbar.append(&build_toolbar_button("Cancel", 5, tx));
+        toolbar.append(&build_toolbar_button("Compare", 21, tx));
+        toolbar.append(&build_toolbar_button("Collage", 23, tx));
+        let effects_button = build_button("Effects".to_string());
+        toolbar.append(&effects_button);
+        let tool_list = build_dropdown(&EDITOR_TOOLS);
+        toolbar.append(&build_label("Tool:".to_string()));
+        toolbar.append(&tool_list);
+        let color_panel = ColorPanel::new();
+        color_panel.container.set_visible(false);
+        let measure_panel = MeasurePanel::new(&view);
+        measure_panel.container.set_visible(false);
+        let crop_panel = CropPanel::new(&view, tx);
+        let cursor_panel = CursorPanel::new(&view);
+        cursor_panel.container.set_visible(false);
+        let text_panel = build_text_panel(tx);
+        text_panel.set_visible(false);
+        let code_panel = CodePanel::new(&view, tx);
+        code_panel.container.set_visible(false);
+        let transform_panel = TransformPanel::new(&view, tx);
+        transform_panel.container.set_visible(false);
+        let effects_panel = EffectsPanel::new(&view);
+        effects_panel.container.set_visible(false);
+        let effects_panel_button = effects_panel.clone();
+        effects_button.connect_clicked(move |_| {
+            let visible = !effects_panel_button.container.is_visible();
+            effects_panel_button.container.set_visible(visible);
+            if visible {
+                effects_panel_button.render();
+            }
+        });
+        // the loupe helps placing the corners of the selection, picking colours and measuring
+        let view_tool = view.clone();
+        let color_panel_tool = color_panel.clone();
+        let measure_panel_tool = measure_panel.clone();
+        let crop_panel_tool = crop_panel.clone();
+        let cursor_panel_tool = cursor_panel.clone();
+        let text_panel_tool = text_panel.clone();
+        let code_panel_tool = code_panel.clone();
+        let transform_panel_tool = transform_panel.clone();
+        tool_list.connect_selected_notify(move |list| {
+            transform_panel_tool
+                .container
+                .set_visible(list.selected() == 7);
+            if list.selected() == 7 {
+                transform_panel_tool.reset();
+            }
+            text_panel_tool.set_visible(list.selected() == 5);
+            code_panel_tool.container.set_visible(list.selected() == 6);
+            if list.selected() != 6 {
+                code_panel_tool.show_codes(None);
+            }
+            cursor_panel_tool
+                .container
+                .set_visible(list.selected() == 4);
+            crop_panel_tool.container.set_visible(list.selected() == 0);
+            if list.selected() != 0 {
+                crop_panel_tool.view.set_selection(None);
+            }
+            view_tool.set_loupe_visible(matches!(list.selected(), 0 | 2 | 3));
+            color_panel_tool.container.set_visible(list.selected() == 2);
+            measure_panel_tool
+                .container
+                .set_visible(list.selected() == 3);
+            if list.selected() != 3 {
+                measure_panel_tool.clear();
+            }
+        });
+        let format_list = build_dropdown(&["PNG", "JPG", "GIF"]);
+        extension_list
+            .bind_property("selected", &format_list, "selected")
+            .bidirectional()
+            .sync_create()
+            .build();
+        toolbar.append(&build_label("Format:".to_string()));
+        toolbar.append(&format_list);
+        view.append_zoom_buttons(&toolbar);
+
+        let content = Box::new(Orientation::Vertical, 0);
+        content.append(&toolbar);
+        content.append(&crop_panel.container);
+        content.append(&color_panel.container);
+        content.append(&measure_panel.container);
+        content.append(&cursor_panel.container);
+        content.append(&text_panel);
+        content.append(&code_panel.container);
+        content.append(&transform_panel.container);
+        content.append(&effects_panel.container);
+        content.append(&view.overlay);
+        window.set_child(Some(&content));
+        view.connect_keys(&window);
+
+        // closing the window from the window manager is the same as Cancel on this editor
+        let close_requested = Rc::new(Cell::new(false));
+        let close_requested_window = Rc::clone(&close_requested);
+        window.connect_close_request(move |_| {
+            close_requested_window.set(true);
+            glib::Propagation::Stop
+        });
+
+        if retrieve_data_from_json().editor_mode == "WINDOW" {
+            window.set_default_size(1024, 720);
+            window.present();
+        } else {
+            window.present();
+            window.fullscreen();
+        }
+
+        // the eyedropper picks on click
+        let click = GestureClick::new();
+        let view_click = view.clone();
+        let tool_click = tool_list.clone();
+        let color_panel_click = color_panel.clone();
+        click.connect_released(move |_, _, x, y| {
+            if tool_click.selected() == 2 && !view_click.panning.get() {
+                if let Some(pixel) = view_click.color_at(x, y) {
+                    color_panel_click.pick(pixel);
+                }
+            }
+        });
+        view.picture.add_controller(click);
+        measure_panel.connect_drag(&tool_list);
+        crop_panel.connect_drag(&tool_list);
+        cursor_panel.connect_drag(&tool_list);
+
+        // the rectangle to read with Copy text or to scan for codes is shown while dragging
+        let text_drag = GestureDrag::new();
+        let text_start = Rc::new(Cell::new(None));
+        let tool_text = tool_list.clone();
+        let view_text = view.clone();
+        let text_start_begin = Rc::clone(&text_start);
+        text_drag.connect_drag_begin(move |_, x, y| {
+            if !matches!(tool_text.selected(), 5 | 6) || view_text.panning.get() {
+                text_start_begin.set(None);
+                return;
+            }
+            text_start_begin.set(Some((x, y)));
+        });
+        let view_text = view.clone();
+        text_drag.connect_drag_update(move |_, offset_x, offset_y| {
+            if let Some((start_x, start_y)) = text_start.get() {
+                let coor = view_text
+                    .transform()
+                    .to_image_coordinates(&Coordinates {
+                        start_x,
+                        start_y,
+                        offset_x,
+                        offset_y,
+                    })
+                    .normalized();
+                view_text.set_selection(Some((
+                    coor.start_x,
+                    coor.start_y,
+                    coor.offset_x,
+                    coor.offset_y,
+                )));
+            }
+        });
+        view.picture.add_controller(text_drag);
+
+        // with the Crop tool the window under the pointer is highlighted, a click crops to it
+        let tool_hover = tool_list.clone();
+        view.connect_window_hover(move || tool_hover.selected() == 0);
+        let window_click = GestureClick::new();
+        let view_window_click = view.clone();
+        let tool_window_click = tool_list.clone();
+        let crop_panel_click = crop_panel.clone();
+        let tx_window_click = tx.clone();
+        window_click.connect_released(move |_, _, _, _| {
+            if tool_window_click.selected() != 0 {
+                return;
+            }
+            if let Some(window) = view_window_click.hovered_window.get() {
+                crop_panel_click.show_rectangle(&window_selection(window));
+                let result = tx_window_click.send(11);
+                match result {
+                    Ok(_) => {}
+                    Err(error) => {
+                        eprintln!("{}", error);
+                    }
+                }
+            }
+        });
+        view.picture.add_controller(window_click);
+
+        let (coor, condvar) = draw_area(&view.picture, &view.panning);
+        (
+            Editor {
+                window,
+                view,
+                tool_list,
+                crop_panel,
+                cursor_panel,
+                code_panel,
+                transform_panel,
+                effects_panel,
+                redact_style: Rc::new(Cell::new(RedactStyle::Solid)),
+                collage: Rc::new(RefCell::new(None)),
+                redactions: Rc::new(RefCell::new(None)),
+                close_requested,
+                text_result: Arc::new(Mutex::new(None)),
+                code_result: Arc::new(Mutex::new(None)),
+                first_path: path.clone(),
+                capture_windows: Vec::new(),
+            },
+            coor,
+            condvar,
+        )
+    }
+
+    /* shows a timeline step in place of the current picture; origin is where the step lies
+    in the first one, see Timeline::current_origin */
+    fn show(&mut self, path: &PathBuf, origin: (f64, f64)) {
+        self.view.origin.set(origin);
+        self.view.picture.set_filename(Some(path));
+        self.view.load_pixels(path);
+        self.view.apply_zoom();
+        self.crop_panel.reset();
+        self.transform_panel.reset();
+        self.effects_panel.render();
+        self.code_panel.show_codes(None);
+        // window bounds only match the untouched capture
+        if *path == self.first_path {
+            *self.view.windows.borrow_mut() = self.capture_windows.clone();
+        } else {
+            self.view.windows.borrow_mut().clear();
+        }
+        self.view.set_hovered_window(None);
+    }
+
+    /* windows and pointer of the captured screen, in pixels of the first step */
+    fn set_capture_context(&mut self, context: CaptureContext) {
+        self.capture_windows = context.windows.clone();
+        *self.view.windows.borrow_mut() = context.windows;
+        self.cursor_panel.set_layer(context.cursor);
+    }
+
+    fn close(&self) {
+        // effects edited last in this editor outlive it
+        self.effects_panel.store();
+        self.window.destroy();
+    }
+
+    /* index of the selected tool in EDITOR_TOOLS */
+    fn tool(&self) -> u32 {
+        self.tool_list.selected()
+    }
+
+    fn set_tool(&self, tool: u32) {
+        self.tool_list.set_selected(tool);
+    }
+
+    /* dragged rectangle in image pixels, snapped and reshaped as chosen in the crop panel */
+    fn shape_selection(&self, coor: &Coordinates) -> Coordinates {
+        self.crop_panel.shape_selection(coor)
+    }
+
+    fn typed_crop(&self) -> Coordinates {
+        self.crop_panel.typed_crop()
+    }
+
+    fn trim_tolerance(&self) -> u8 {
+        self.crop_panel.trim_tolerance.value() as u8
+    }
+
+    /* drag rectangle on the picture converted to pixels of the shown image */
+    fn to_image_coordinates(&self, coor: &Coordinates) -> Coordinates {
+        self.view.transform().to_image_coordinates(coor)
+    }
+}
+
+// rectangle as x, y, width and height in image pixels
+type Rect = (f64, f64, f64, f64);
+
+/* scrollable picture of the editor with its zoom level, None fits the image in the window,
+and the loupe drawn above it */
+#[derive(Clone)]
+struct EditorView {
+    overlay: Overlay,
+    scrolled: ScrolledWindow,
+    picture: Picture,
+    zoom_label: Label,
+    zoom: Rc<Cell<Option<f64>>>,
+    panning: Rc<Cell<bool>>,
+    loupe_area: DrawingArea,
+    loupe_visible: Rc<Cell<bool>>,
+    pointer: Rc<Cell<Option<(f64, f64)>>>,
+    pixels: Rc<RefCell<Option<RgbaImage>>>,
+    measurement: Rc<Cell<Option<Measurement>>>,
+    measure_box: Rc<Cell<bool>>,
+    selection: Rc<Cell<Option<Rect>>>,
+    windows: Rc<RefCell<Vec<Region>>>,
+    hovered_window: Rc<Cell<Option<Region>>>,
+    scale_factor: Rc<Cell<Option<f64>>>,
+    cursor_layer: Rc<RefCell<Option<CursorLayer>>>,
+    origin: Rc<Cell<(f64, f64)>>,
+    detections: Rc<RefCell<Vec<Detection>>>,
+    barcodes: Rc<RefCell<Vec<Barcode>>>,
+}
+
+impl EditorView {
+    fn new(path: &PathBuf) -> EditorView {
+        let picture = Picture::for_filename(path);
+        picture.set_halign(Align::Start);
+        picture.set_valign(Align::Start);
+        let scrolled = ScrolledWindow::builder()
+            .child(&picture)
+            .hexpand(true)
+            .vexpand(true)
+            .build();
+        // the loupe does not take events, drags still reach the picture below
+        let loupe_area = DrawingArea::new();
+        loupe_area.set_can_target(false);
+        let overlay = Overlay::new();
+        overlay.set_child(Some(&scrolled));
+        overlay.add_overlay(&loupe_area);
+        let view = EditorView {
+            overlay,
+            scrolled,
+            picture,
+            zoom_label: build_label("Fit".to_string()),
+            zoom: Rc::new(Cell::new(None)),
+            panning: Rc::new(Cell::new(false)),
+            loupe_area,
+            loupe_visible: Rc::new(Cell::new(true)),
+            pointer: Rc::new(Cell::new(None)),
+            pixels: Rc::new(RefCell::new(None)),
+            measurement: Rc::new(Cell::new(None)),
+            measure_box: Rc::new(Cell::new(false)),
+            selection: Rc::new(Cell::new(None)),
+            windows: Rc::new(RefCell::new(Vec::new())),
+            hovered_window: Rc::new(Cell::new(None)),
+            scale_factor: Rc::new(Cell::new(None)),
+            cursor_layer: Rc::new(RefCell::new(None)),
+            origin: Rc::new(Cell::new((0.0, 0.0))),
+            detections: Rc::new(RefCell::new(Vec::new())),
+            barcodes: Rc::new(RefCell::new(Vec::new())),
+        };
+        view.load_pixels(path);
+        view.apply_zoom();
+        view.connect_zoom_gestures();
+        view.connect_pan_gesture();
+        view.connect_loupe();
+        view
+    }
+
+    /* keeps the shown image in memory for the loupe */
+    fn load_pixels(&self, path: &PathBuf) {
+        let pixels = match open(path) {
+            Ok(img) => Some(img.to_rgba8()),
+            Err(error) => {
+                eprintln!("{}", error);
+                None
+            }
+        };
+        *self.pixels.borrow_mut() = pixels;
+        self.loupe_area.queue_draw();
+    }
+
+    /* size in pixels of the shown image */
+    fn image_size(&self) -> (f64, f64) {
+        match self.picture.paintable() {
+            Some(paintable) => (
+                paintable.intrinsic_width() as f64,
+                paintable.intrinsic_height() as f64,
+            ),
+            None => (0.0, 0.0),
+        }
+    }
+
+    fn transform(&self) -> ViewTransform {
+        let (image_width, image_height) = self.image_size();
+        match self.zoom.get() {
+            Some(zoom) => ViewTransform::zoom(zoom),
+            None => ViewTransform::scale_down(
+                self.picture.width() as f64,
+                self.picture.height() as f64,
+                image_width,
+                image_height,
+            ),
+        }
+    }
+
+    fn set_zoom(&self, zoom: Option<f64>) {
+        self.zoom
+            .set(zoom.map(|zoom| zoom.clamp(MIN_ZOOM, MAX_ZOOM)));
+        self.apply_zoom();
+    }
+
+    /* multiplies the zoom, starting from the scale the image is shown at when fitted */
+    fn zoom_by(&self, factor: f64) {
+        self.set_zoom(Some(self.transform().scale * factor));
+    }
+
+    /* one image pixel on one device pixel */
+    fn zoom_one_to_one(&self) {
+        self.set_zoom(Some(1.0 / self.picture.scale_factor() as f64));
+    }
+
+    fn apply_zoom(&self) {
+        match self.zoom.get() {
+            None => {
+                self.scrolled
+                    .set_policy(PolicyType::Never, PolicyType::Never);
+                self.picture.set_size_request(-1, -1);
+                self.picture.set_content_fit(ContentFit::ScaleDown);
+                self.zoom_label.set_label("Fit");
+            }
+            Some(zoom) => {
+                let (image_width, image_height) = self.image_size();
+                self.scrolled
+                    .set_policy(PolicyType::Automatic, PolicyType::Automatic);
+                self.picture.set_size_request(
+                    (image_width * zoom).round() as i32,
+                    (image_height * zoom).round() as i32,
+                );
+                self.picture.set_content_fit(ContentFit::Fill);
+                self.zoom_label.set_label(&format!("{:.0}%", zoom * 100.0));
+            }
+        }
+    }
+
+    fn append_zoom_buttons(&self, toolbar: &Box) {
+        let buttons = [("-", 0), ("+", 1), ("1:1", 2), ("Fit", 3)];
+        for (label, action) in buttons {
+            let button = build_button(label.to_string());
+            let view = self.clone();
+            button.connect_clicked(move |_| match action {
+                0 => view.zoom_by(1.0 / ZOOM_STEP),
+                1 => view.zoom_by(ZOOM_STEP),
+                2 => view.zoom_one_to_one(),
+                _ => view.set_zoom(None),
+            });
+            toolbar.append(&button);
+        }
+        toolbar.append(&self.zoom_label);
+    }
+
+    /* Ctrl+scroll and pinch */
+    fn connect_zoom_gestures(&self) {
+        let scroll_ctrl = EventControllerScroll::new(EventControllerScrollFlags::VERTICAL);
+        scroll_ctrl.set_propagation_phase(PropagationPhase::Capture);
+        let view = self.clone();
+        scroll_ctrl.connect_scroll(move |ctrl, _, dy| {
+            if !ctrl
+                .current_event_state()
+                .contains(gdk::ModifierType::CONTROL_MASK)
+            {
+                return glib::Propagation::Proceed;
+            }
+            if dy < 0.0 {
+                view.zoom_by(ZOOM_STEP);
+            } else if dy > 0.0 {
+                view.zoom_by(1.0 / ZOOM_STEP);
+            }
+            glib::Propagation::Stop
+        });
+        self.scrolled.add_controller(scroll_ctrl);
+
+        let pinch = GestureZoom::new();
+        let start_zoom = Rc::new(Cell::new(1.0));
+        let view = self.clone();
+        let start_zoom_begin = Rc::clone(&start_zoom);
+        pinch.connect_begin(move |_, _| {
+            start_zoom_begin.set(view.transform().scale);
+        });
+        let view = self.clone();
+        pinch.connect_scale_changed(move |_, scale| {
+            view.set_zoom(Some(start_zoom.get() * scale));
+        });
+        self.scrolled.add_controller(pinch);
+    }
+
+    /* space+drag moves the scrollbars */
+    fn connect_pan_gesture(&self) {
+        let pan = GestureDrag::new();
+        let start = Rc::new(Cell::new((0.0, 0.0)));
+        let view = self.clone();
+        let start_begin = Rc::clone(&start);
+        pan.connect_drag_begin(move |_, _, _| {
+            start_begin.set((
+                view.scrolled.hadjustment().value(),
+                view.scrolled.vadjustment().value(),
+            ));
+        });
+        let view = self.clone();
+        pan.connect_drag_update(move |_, offset_x, offset_y| {
+            if view.panning.get() {
+                let (start_x, start_y) = start.get();
+                view.scrolled.hadjustment().set_value(start_x - offset_x);
+                view.scrolled.vadjustment().set_value(start_y - offset_y);
+            }
+        });
+        self.scrolled.add_controller(pan);
+    }
+
+    fn set_loupe_visible(&self, visible: bool) {
+        self.loupe_visible.set(visible);
+        self.loupe_area.queue_draw();
+    }
+
+    /* pixel of the shown image under a point of the picture, None outside of the image */
+    fn image_pixel(&self, x: f64, y: f64) -> Option<(u32, u32)> {
+        let (image_x, image_y) = self.transform().to_image(x, y);
+        let (image_width, image_height) = self.image_size();
+        if image_x < 0.0 || image_y < 0.0 || image_x >= image_width || image_y >= image_height {
+            return None;
+        }
+        Some((image_x as u32, image_y as u32))
+    }
+
+    /* same as image_pixel for a point of the overlay */
+    fn pixel_at(&self, x: f64, y: f64) -> Option<(u32, u32)> {
+        let (picture_x, picture_y) = self.overlay.translate_coordinates(&self.picture, x, y)?;
+        self.image_pixel(picture_x, picture_y)
+    }
+
+    /* point of the image shown at a point of the overlay */
+    fn image_to_overlay(&self, x: f64, y: f64) -> Option<(f64, f64)> {
+        let (picture_x, picture_y) = self.transform().to_widget(x, y);
+        self.picture
+            .translate_coordinates(&self.overlay, picture_x, picture_y)
+    }
+
+    /* point of the image moved onto the nearest edge of a uniform-colour region */
+    fn snap(&self, x: f64, y: f64) -> (f64, f64) {
+        match self.pixels.borrow().as_ref() {
+            Some(pixels) => snap_to_edges(pixels, x, y, SNAP_DISTANCE, SNAP_TOLERANCE),
+            None => (x, y),
+        }
+    }
+
+    /* rectangle in image pixels with its corners moved onto nearby window borders or edges */
+    fn snap_rectangle(&self, coor: &Coordinates) -> Coordinates {
+        snap_rectangle(
+            self.pixels.borrow().as_ref(),
+            &self.windows.borrow(),
+            coor,
+            SNAP_DISTANCE as f64,
+            STRONG_EDGE,
+        )
+    }
+
+    fn set_hovered_window(&self, window: Option<Region>) {
+        if self.hovered_window.get() != window {
+            self.hovered_window.set(window);
+            self.loupe_area.queue_draw();
+        }
+    }
+
+    /* keeps hovered_window on the window under the pointer while active returns true */
+    fn connect_window_hover<F: Fn() -> bool + 'static>(&self, active: F) {
+        let motion = EventControllerMotion::new();
+        let view = self.clone();
+        motion.connect_motion(move |_, x, y| {
+            if !active() || view.panning.get() {
+                view.set_hovered_window(None);
+                return;
+            }
+            let (image_x, image_y) = view.transform().to_image(x, y);
+            let window = window_at(&view.windows.borrow(), image_x, image_y);
+            view.set_hovered_window(window);
+        });
+        let view = self.clone();
+        motion.connect_leave(move |_| {
+            view.set_hovered_window(None);
+        });
+        self.picture.add_controller(motion);
+    }
+
+    /* scale factor of the captured screen, or of the display for opened images */
+    fn points_scale_factor(&self) -> f64 {
+        self.scale_factor
+            .get()
+            .unwrap_or(self.picture.scale_factor() as f64)
+    }
+
+    /* colour of the image under a point of the picture */
+    fn color_at(&self, x: f64, y: f64) -> Option<Rgba<u8>> {
+        let (image_x, image_y) = self.image_pixel(x, y)?;
+        let pixels = self.pixels.borrow();
+        let pixels = pixels.as_ref()?;
+        if image_x < pixels.width() && image_y < pixels.height() {
+            Some(*pixels.get_pixel(image_x, image_y))
+        } else {
+            None
+        }
+    }
+
+    /* follows the pointer and redraws the loupe next to it */
+    fn connect_loupe(&self) {
+        let motion = EventControllerMotion::new();
+        let view = self.clone();
+        motion.connect_motion(move |_, x, y| {
+            view.pointer.set(Some((x, y)));
+            view.loupe_area.queue_draw();
+        });
+        let view = self.clone();
+        motion.connect_leave(move |_| {
+            view.pointer.set(None);
+            view.loupe_area.queue_draw();
+        });
+        self.overlay.add_controller(motion);
+
+        let view = self.clone();
+        self.loupe_area.set_draw_func(move |_, cr, width, height| {
+            if let Some(layer) = view.cursor_layer.borrow().as_ref() {
+                view.draw_cursor(cr, layer);
+            }
+            for detection in view.detections.borrow().iter() {
+                view.draw_detection(cr, detection);
+            }
+            for barcode in view.barcodes.borrow().iter() {
+                view.draw_barcode(cr, barcode);
+            }
+            if let Some(window) = view.hovered_window.get() {
+                view.draw_hovered_window(cr, window);
+            }
+            if let Some(selection) = view.selection.get() {
+                view.draw_selection(cr, selection);
+            }
+            if let Some(measurement) = view.measurement.get() {
+                view.draw_measurement(cr, &measurement);
+            }
+            if !view.loupe_visible.get() || view.panning.get() {
+                return;
+            }
+            let (x, y) = match view.pointer.get() {
+                Some(pointer) => pointer,
+                None => return,
+            };
+            let (image_x, image_y) = match view.pixel_at(x, y) {
+                Some(pixel) => pixel,
+                None => return,
+            };
+            let pixels = view.pixels.borrow();
+            let loupe = match pixels.as_ref() {
+                Some(pixels) => Loupe::sample(pixels, image_x, image_y, LOUPE_RADIUS),
+                None => return,
+            };
+            draw_loupe(cr, &loupe, x, y, width as f64, height as f64);
+        });
+    }
+
+    /* crop rectangle (x, y, width, height) in image pixels, None hides it */
+    fn set_selection(&self, selection: Option<Rect>) {
+        self.selection.set(selection);
+        self.loupe_area.queue_draw();
+    }
+
+    /* cursor layer at the zoom of the picture, with its click ring */
+    fn draw_cursor(&self, cr: &cairo::Context, layer: &CursorLayer) {
+        let origin = self.origin.get();
+        let scale = self.transform().scale;
+        let (hot_x, hot_y) = layer.position(origin);
+        if layer.ring {
+            if let Some((x, y)) = self.image_to_overlay(hot_x.round() + 0.5, hot_y.round() + 0.5) {
+                cr.set_source_rgba(
+                    RING_COLOR[0] as f64 / 255.0,
+                    RING_COLOR[1] as f64 / 255.0,
+                    RING_COLOR[2] as f64 / 255.0,
+                    RING_COLOR[3] as f64 / 255.0,
+                );
+                cr.set_line_width(RING_WIDTH * scale);
+                cr.new_sub_path();
+                cr.arc(x, y, RING_RADIUS * scale, 0.0, 2.0 * std::f64::consts::PI);
+                let _ = cr.stroke();
+            }
+        }
+        let (left, top) = layer.image_corner(origin);
+        let (x, y) = match self.image_to_overlay(left, top) {
+            Some(corner) => corner,
+            None => return,
+        };
+        let surface = match image_surface(&layer.cursor.image) {
+            Some(surface) => surface,
+            None => return,
+        };
+        let _ = cr.save();
+        cr.translate(x, y);
+        cr.scale(scale, scale);
+        let _ = cr.set_source_surface(&surface, 0.0, 0.0);
+        let _ = cr.paint();
+        let _ = cr.restore();
+    }
+
+    /* sensitive text under review: red when it will be redacted, grey when it is kept */
+    fn draw_detection(&self, cr: &cairo::Context, detection: &Detection) {
+        let start = self.image_to_overlay(detection.left as f64, detection.top as f64);
+        let end = self.image_to_overlay(
+            (detection.left + detection.width) as f64,
+            (detection.top + detection.height) as f64,
+        );
+        let ((start_x, start_y), (end_x, end_y)) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+        cr.rectangle(start_x, start_y, end_x - start_x, end_y - start_y);
+        if detection.enabled {
+            cr.set_source_rgba(1.0, 0.0, 0.0, 0.3);
+            let _ = cr.fill_preserve();
+            cr.set_source_rgb(1.0, 0.0, 0.0);
+        } else {
+            cr.set_source_rgb(0.5, 0.5, 0.5);
+        }
+        cr.set_line_width(2.0);
+        let _ = cr.stroke();
+    }
+
+    fn set_detections(&self, detections: Vec<Detection>) {
+        *self.detections.borrow_mut() = detections;
+        self.loupe_area.queue_draw();
+    }
+
+    /* green outline around a decoded code */
+    fn draw_barcode(&self, cr: &cairo::Context, barcode: &Barcode) {
+        let start = self.image_to_overlay(barcode.left as f64, barcode.top as f64);
+        let end = self.image_to_overlay(
+            (barcode.left + barcode.width) as f64,
+            (barcode.top + barcode.height) as f64,
+        );
+        let ((start_x, start_y), (end_x, end_y)) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+        cr.rectangle(start_x, start_y, end_x - start_x, end_y - start_y);
+        cr.set_source_rgba(0.0, 0.8, 0.2, 0.2);
+        let _ = cr.fill_preserve();
+        cr.set_source_rgb(0.0, 0.8, 0.2);
+        cr.set_line_width(3.0);
+        let _ = cr.stroke();
+    }
+
+    /* translucent fill over the window that a click would select */
+    fn draw_hovered_window(&self, cr: &cairo::Context, window: Region) {
+        let start = self.image_to_overlay(window.x as f64, window.y as f64);
+        let end = self.image_to_overlay(
+            window.x as f64 + window.width as f64,
+            window.y as f64 + window.height as f64,
+        );
+        let ((start_x, start_y), (end_x, end_y)) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+        cr.rectangle(start_x, start_y, end_x - start_x, end_y - start_y);
+        cr.set_source_rgba(0.2, 0.5, 1.0, 0.25);
+        let _ = cr.fill_preserve();
+        cr.set_source_rgb(0.2, 0.5, 1.0);
+        cr.set_line_width(2.0);
+        let _ = cr.stroke();
+    }
+
+    /* dashed outline of the crop rectangle */
+    fn draw_selection(&self, cr: &cairo::Context, (x, y, width, height): Rect) {
+        let start = self.image_to_overlay(x, y);
+        let end = self.image_to_overlay(x + width, y + height);
+        let ((start_x, start_y), (end_x, end_y)) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+        cr.set_line_width(1.0);
+        cr.rectangle(start_x, start_y, end_x - start_x, end_y - start_y);
+        cr.set_source_rgb(1.0, 1.0, 1.0);
+        let _ = cr.stroke_preserve();
+        cr.set_source_rgb(0.0, 0.0, 0.0);
+        cr.set_dash(&[4.0, 4.0], 0.0);
+        let _ = cr.stroke();
+        cr.set_dash(&[], 0.0);
+    }
+
+    /* the measured segment, or the box it spans, outlined so that it shows on any background */
+    fn draw_measurement(&self, cr: &cairo::Context, measurement: &Measurement) {
+        let start = self.image_to_overlay(measurement.start_x, measurement.start_y);
+        let end = self.image_to_overlay(measurement.end_x, measurement.end_y);
+        let ((start_x, start_y), (end_x, end_y)) = match (start, end) {
+            (Some(start), Some(end)) => (start, end),
+            _ => return,
+        };
+        for (width, red, green, blue) in [(3.0, 0.0, 0.0, 0.0), (1.0, 1.0, 0.9, 0.0)] {
+            cr.set_source_rgb(red, green, blue);
+            cr.set_line_width(width);
+            if self.measure_box.get() {
+                cr.rectangle(
+                    start_x.min(end_x),
+                    start_y.min(end_y),
+                    (end_x - start_x).abs(),
+                    (end_y - start_y).abs(),
+                );
+            } else {
+                cr.move_to(start_x, start_y);
+                cr.line_to(end_x, end_y);
+                for (x, y) in [(start_x, start_y), (end_x, end_y)] {
+                    cr.new_sub_path();
+                    cr.arc(x, y, 3.0, 0.0, 2.0 * std::f64::consts::PI);
+                }
+            }
+            let _ = cr.stroke();
+        }
+    }
+
+    /* space held down to pan, Ctrl+plus/minus/1/0 to zoom */
+    fn connect_keys(&self, window: &Window) {
+        let key_ctrl = EventControllerKey::new();
+        key_ctrl.set_propagation_phase(PropagationPhase::Capture);
+        let view = self.clone();
+        key_ctrl.connect_key_pressed(move |_, key, _, state| {
+            if key == gdk::Key::space {
+                view.panning.set(true);
+                view.picture.set_cursor_from_name(Some("grab"));
+                return glib::Propagation::Stop;
+            }
+            if !state.contains(gdk::ModifierType::CONTROL_MASK) {
+                return glib::Propagation::Proceed;
+            }
+            match key {
+                gdk::Key::plus | gdk::Key::equal | gdk::Key::KP_Add => view.zoom_by(ZOOM_STEP),
+                gdk::Key::minus | gdk::Key::KP_Subtract => view.zoom_by(1.0 / ZOOM_STEP),
+                gdk::Key::_1 | gdk::Key::KP_1 => view.zoom_one_to_one(),
+                gdk::Key::_0 | gdk::Key::KP_0 => view.set_zoom(None),
+                _ => return glib::Propagation::Proceed,
+            }
+            glib::Propagation::Stop
+        });
+        let view = self.clone();
+        key_ctrl.connect_key_released(move |_, key, _, _| {
+            if key == gdk::Key::space {
+                view.panning.set(false);
+                view.picture.set_cursor_from_name(None);
+            }
+        });
+        window.add_controller(key_ctrl);
+    }
+}
+
+/* colour picked with the eyedropper in hex, RGB and HSL, each copied by clicking it,
+and the recently picked colours, which are kept in the settings */
+#[derive(Clone)]
+struct ColorPanel {
+    container: Box,
+    swatch: DrawingArea,
+    color: Rc<Cell<Option<Rgba<u8>>>>,
+    format_buttons: Vec<(ColorFormat, Button)>,
+    history: Box,
+}
+
+impl ColorPanel {
+    fn new() -> ColorPanel {
+        let container = Box::new(Orientation::Horizontal, 6);
+        let color = Rc::new(Cell::new(None));
+        let swatch = build_swatch(&color);
+        container.append(&swatch);
+        let mut format_buttons = Vec::new();
+        for format in [ColorFormat::Hex, ColorFormat::Rgb, ColorFormat::Hsl] {
+            let button = build_button("-".to_string());
+            container.append(&button);
+            format_buttons.push((format, button));
+        }
+        container.append(&build_label("Recent:".to_string()));
+        let history = Box::new(Orientation::Horizontal, 2);
+        container.append(&history);
+
+        let panel = ColorPanel {
+            container,
+            swatch,
+            color,
+            format_buttons,
+            history,
+        };
+        // the notation clicked last is also the one copied by the next pick
+        for (format, button) in &panel.format_buttons {
+            let format = *format;
+            let panel_format = panel.clone();
+            button.connect_clicked(move |_| {
+                let mut settings = retrieve_data_from_json();
+                settings.color_format = format.to_json();
+                write_settings(&settings);
+                panel_format.copy();
+            });
+        }
+        panel.show_history();
+        panel
+    }
+
+    /* shows the colour, copies it and adds it to the recent colours */
+    fn pick(&self, pixel: Rgba<u8>) {
+        self.color.set(Some(pixel));
+        self.swatch.queue_draw();
+        for (format, button) in &self.format_buttons {
+            button.set_label(&format.format(pixel));
+        }
+        self.copy();
+        let mut settings = retrieve_data_from_json();
+        color::push_recent(&mut settings.recent_colors, pixel, COLOR_HISTORY_SIZE);
+        write_settings(&settings);
+        self.show_history();
+    }
+
+    /* copies the shown colour in the chosen notation */
+    fn copy(&self) {
+        if let Some(pixel) = self.color.get() {
+            let format = ColorFormat::from_json(&retrieve_data_from_json().color_format);
+            let result = set_clipboard_text(&format.format(pixel));
+            match result {
+                Ok(_) => {}
+                Err(error) => {
+                    eprintln!("{}", error);
+                }
+            }
+        }
+    }
+
+    /* one button per recent colour, clicking it picks the colour again */
+    fn show_history(&self) {
+        while let Some(child) = self.history.first_child() {
+            self.history.remove(&child);
+        }
+        for entry in retrieve_data_from_json().recent_colors {
+            if let Some(pixel) = color::parse_hex(&entry) {
+                let button = Button::new();
+                button.set_child(Some(&build_swatch(&Rc::new(Cell::new(Some(pixel))))));
+                button.set_tooltip_text(Some(&entry));
+                let panel = self.clone();
+                button.connect_clicked(move |_| panel.pick(pixel));
+                self.history.append(&button);
+            }
+        }
+    }
+}
+
+/* rectangle typed in image pixels and the constraint (ratio or fixed size preset)
+applied to dragging with the Crop tool */
+#[derive(Clone)]
+struct CropPanel {
+    container: Box,
+    constraint_list: DropDown,
+    snap_check: CheckButton,
+    spins: [SpinButton; 4],
+    trim_tolerance: SpinButton,
+    view: EditorView,
+}
+
+impl CropPanel {
+    fn new(view: &EditorView, tx: &mpsc::Sender<u32>) -> CropPanel {
+        let container = Box::new(Orientation::Horizontal, 6);
+        let mut constraints = vec!["Free".to_string()];
+        constraints.extend(retrieve_data_from_json().crop_presets);
+        let constraint_names: Vec<&str> = constraints.iter().map(|name| name.as_str()).collect();
+        let constraint_list = build_dropdown(&constraint_names);
+        container.append(&build_label("Constraint:".to_string()));
+        container.append(&constraint_list);
+
+        let custom_entry = Entry::builder()
+            .placeholder_text("3:2 or 800x600")
+            .valign(Align::Center)
+            .build();
+        let add_button = build_button("Add preset".to_string());
+        container.append(&custom_entry);
+        container.append(&add_button);
+        let snap_check = CheckButton::with_label("Snap");
+        snap_check.set_active(true);
+        container.append(&snap_check);
+
+        let spins = ["X", "Y", "W", "H"].map(|name| {
+            container.append(&build_label(name.to_string()));
+            let spin = SpinButton::with_range(0.0, 100000.0, 1.0);
+            spin.set_valign(Align::Center);
+            container.append(&spin);
+            spin
+        });
+        container.append(&build_toolbar_button("Apply crop", 11, tx));
+        // channels differing by at most the tolerance count as the border colour
+        container.append(&build_label("Tolerance".to_string()));
+        let trim_tolerance = SpinButton::with_range(0.0, 255.0, 1.0);
+        trim_tolerance.set_value(TRIM_TOLERANCE);
+        trim_tolerance.set_valign(Align::Center);
+        container.append(&trim_tolerance);
+        container.append(&build_toolbar_button("Auto-trim", 15, tx));
+
+        let panel = CropPanel {
+            container,
+            constraint_list,
+            snap_check,
+            spins,
+            trim_tolerance,
+            view: view.clone(),
+        };
+        panel.reset();
+
+        // a new preset is kept in the settings and chosen right away
+        let panel_add = panel.clone();
+        add_button.connect_clicked(move |_| {
+            let constraint = match CropConstraint::parse(&custom_entry.text()) {
+                Some(constraint) => constraint,
+                None => {
+                    eprintln!("Invalid preset: use width:height or widthxheight");
+                    return;
+                }
+            };
+            let mut settings = retrieve_data_from_json();
+            settings.crop_presets.push(constraint.to_text());
+            write_settings(&settings);
+            if let Some(model) = panel_add
+                .constraint_list
+                .model()
+                .and_downcast::<StringList>()
+            {
+                model.append(&constraint.to_text());
+                panel_add
+                    .constraint_list
+                    .set_selected(settings.crop_presets.len() as u32);
+            }
+            custom_entry.set_text("");
+        });
+
+        // choosing a constraint reshapes the typed rectangle
+        let panel_constraint = panel.clone();
+        panel.constraint_list.connect_selected_notify(move |_| {
+            let coor = panel_constraint.constrain(&panel_constraint.typed_crop());
+            panel_constraint.show_rectangle(&coor.normalized());
+        });
+
+        for spin in &panel.spins {
+            let panel_spin = panel.clone();
+            spin.connect_value_changed(move |_| {
+                let coor = panel_spin.typed_crop();
+                panel_spin.view.set_selection(Some((
+                    coor.start_x,
+                    coor.start_y,
+                    coor.offset_x,
+                    coor.offset_y,
+                )));
+            });
+        }
+        panel
+    }
+
+    fn constraint(&self) -> CropConstraint {
+        let presets = retrieve_data_from_json().crop_presets;
+        match self.constraint_list.selected() {
+            0 => CropConstraint::Free,
+            index => presets
+                .get(index as usize - 1)
+                .and_then(|preset| CropConstraint::parse(preset))
+                .unwrap_or(CropConstraint::Free),
+        }
+    }
+
+    fn constrain(&self, coor: &Coordinates) -> Coordinates {
+        let (image_width, image_height) = self.view.image_size();
+        self.constraint()
+            .apply(coor, image_width as u32, image_height as u32)
+    }
+
+    /* dragged rectangle snapped to windows and edges, if asked, then constrained */
+    fn shape_selection(&self, coor: &Coordinates) -> Coordinates {
+        if self.snap_check.is_active() {
+            self.constrain(&self.view.snap_rectangle(coor))
+        } else {
+            self.constrain(coor)
+        }
+    }
+
+    fn typed_crop(&self) -> Coordinates {
+        Coordinates {
+            start_x: self.spins[0].value(),
+            start_y: self.spins[1].value(),
+            offset_x: self.spins[2].value(),
+            offset_y: self.spins[3].value(),
+        }
+    }
+
+    /* fills the fields with a rectangle with positive offsets */
+    fn show_rectangle(&self, coor: &Coordinates) {
+        let values = [coor.start_x, coor.start_y, coor.offset_x, coor.offset_y];
+        for (spin, value) in self.spins.iter().zip(values) {
+            spin.set_value(value.round());
+        }
+    }
+
+    /* whole image, without any selection shown */
+    fn reset(&self) {
+        let (image_width, image_height) = self.view.image_size();
+        self.show_rectangle(&Coordinates {
+            start_x: 0.0,
+            start_y: 0.0,
+            offset_x: image_width,
+            offset_y: image_height,
+        });
+        self.view.set_selection(None);
+    }
+
+    /* shows the constrained rectangle while dragging with the Crop tool */
+    fn connect_drag(&self, tool_list: &DropDown) {
+        let drag = GestureDrag::new();
+        let start = Rc::new(Cell::new(None));
+        let panel = self.clone();
+        let tool_begin = tool_list.clone();
+        let start_begin = Rc::clone(&start);
+        drag.connect_drag_begin(move |_, x, y| {
+            if tool_begin.selected() != 0 || panel.view.panning.get() {
+                start_begin.set(None);
+                return;
+            }
+            start_begin.set(Some((x, y)));
+        });
+        let panel = self.clone();
+        drag.connect_drag_update(move |_, offset_x, offset_y| {
+            if let Some((start_x, start_y)) = start.get() {
+                let coor = panel.view.transform().to_image_coordinates(&Coordinates {
+                    start_x,
+                    start_y,
+                    offset_x,
+                    offset_y,
+                });
+                panel.show_rectangle(&panel.shape_selection(&coor).normalized());
+            }
+        });
+        self.view.picture.add_controller(drag);
+    }
+}
+
+/* shape and snapping of the measurement tool and the measured sizes */
+#[derive(Clone)]
+struct MeasurePanel {
+    container: Box,
+    snap_check: CheckButton,
+    result_label: Label,
+    view: EditorView,
+}
+
+impl MeasurePanel {
+    fn new(view: &EditorView) -> MeasurePanel {
+        let container = Box::new(Orientation::Horizontal, 6);
+        let shape_list = build_dropdown(&["Line", "Box"]);
+        let snap_check = CheckButton::with_label("Snap to edges");
+        let result_label = build_label("Drag on the image to measure".to_string());
+        container.append(&build_label("Shape:".to_string()));
+        container.append(&shape_list);
+        container.append(&snap_check);
+        container.append(&result_label);
+
+        let view_shape = view.clone();
+        shape_list.connect_selected_notify(move |list| {
+            view_shape.measure_box.set(list.selected() == 1);
+            view_shape.loupe_area.queue_draw();
+        });
+        MeasurePanel {
+            container,
+            snap_check,
+            result_label,
+            view: view.clone(),
+        }
+    }
+
+    /* dragging on the picture with the Measure tool selected measures from the start point */
+    fn connect_drag(&self, tool_list: &DropDown) {
+        let drag = GestureDrag::new();
+        let start = Rc::new(Cell::new(None));
+        let panel = self.clone();
+        let tool_begin = tool_list.clone();
+        let start_begin = Rc::clone(&start);
+        drag.connect_drag_begin(move |_, x, y| {
+            if tool_begin.selected() != 3 || panel.view.panning.get() {
+                start_begin.set(None);
+                return;
+            }
+            start_begin.set(Some((x, y)));
+            panel.update(x, y, x, y);
+        });
+        let panel = self.clone();
+        drag.connect_drag_update(move |_, offset_x, offset_y| {
+            if let Some((x, y)) = start.get() {
+                panel.update(x, y, x + offset_x, y + offset_y);
+            }
+        });
+        self.view.picture.add_controller(drag);
+    }
+
+    /* measures between two points of the picture */
+    fn update(&self, start_x: f64, start_y: f64, end_x: f64, end_y: f64) {
+        let transform = self.view.transform();
+        let (mut start_x, mut start_y) = transform.to_image(start_x, start_y);
+        let (mut end_x, mut end_y) = transform.to_image(end_x, end_y);
+        if self.snap_check.is_active() {
+            (start_x, start_y) = self.view.snap(start_x, start_y);
+            (end_x, end_y) = self.view.snap(end_x, end_y);
+        }
+        let measurement = Measurement {
+            start_x,
+            start_y,
+            end_x,
+            end_y,
+        };
+        self.result_label
+            .set_label(&measurement.describe(self.view.points_scale_factor()));
+        self.view.measurement.set(Some(measurement));
+        self.view.loupe_area.queue_draw();
+    }
+
+    fn clear(&self) {
+        self.view.measurement.set(None);
+        self.view.loupe_area.queue_draw();
+        self.result_label.set_label("Drag on the image to measure");
+    }
+}
+
+/* options of the Cursor tool: the pointer layer is dragged on the picture, can get a ring
+highlighting a click or be removed */
+#[derive(Clone)]
+struct CursorPanel {
+    container: Box,
+    ring_check: CheckButton,
+    // layer put aside while a transformed step, where it is merged, is shown
+    merged: Rc<RefCell<Option<CursorLayer>>>,
+    view: EditorView,
+}
+
+impl CursorPanel {
+    fn new(view: &EditorView) -> CursorPanel {
+        let container = Box::new(Orientation::Horizontal, 6);
+        let ring_check = CheckButton::with_label("Highlight click");
+        let remove_button = build_button("Remove cursor".to_string());
+        container.append(&build_label("Drag the cursor to move it".to_string()));
+        container.append(&ring_check);
+        container.append(&remove_button);
+        // nothing to edit until a cursor is captured
+        container.set_sensitive(false);
+
+        let view_ring = view.clone();
+        ring_check.connect_toggled(move |check| {
+            if let Some(layer) = view_ring.cursor_layer.borrow_mut().as_mut() {
+                layer.ring = check.is_active();
+            }
+            view_ring.loupe_area.queue_draw();
+        });
+        let panel = CursorPanel {
+            container,
+            ring_check,
+            merged: Rc::new(RefCell::new(None)),
+            view: view.clone(),
+        };
+        let panel_remove = panel.clone();
+        remove_button.connect_clicked(move |_| {
+            panel_remove.set_layer(None);
+        });
+        panel
+    }
+
+    fn set_layer(&self, layer: Option<CursorLayer>) {
+        self.merged.borrow_mut().take();
+        let ring = layer.as_ref().map(|layer| layer.ring).unwrap_or(false);
+        self.container.set_sensitive(layer.is_some());
+        *self.view.cursor_layer.borrow_mut() = layer;
+        self.ring_check.set_active(ring);
+        self.view.loupe_area.queue_draw();
+    }
+
+    /* on transformed steps the cursor is part of the pixels: the layer is put aside, as it was
+    last placed, and given back on the steps before them */
+    fn set_merged(&self, merged: bool) {
+        if merged {
+            let layer = self.view.cursor_layer.borrow_mut().take();
+            if layer.is_some() {
+                *self.merged.borrow_mut() = layer;
+                self.container.set_sensitive(false);
+                self.view.loupe_area.queue_draw();
+            }
+        } else {
+            let layer = self.merged.borrow_mut().take();
+            if layer.is_some() {
+                self.set_layer(layer);
+            }
+        }
+    }
+
+    /* dragging the cursor with the Cursor tool selected moves it */
+    fn connect_drag(&self, tool_list: &DropDown) {
+        let drag = GestureDrag::new();
+        let start = Rc::new(Cell::new(None));
+        let panel = self.clone();
+        let tool_begin = tool_list.clone();
+        let start_begin = Rc::clone(&start);
+        drag.connect_drag_begin(move |_, x, y| {
+            start_begin.set(None);
+            if tool_begin.selected() != 4 || panel.view.panning.get() {
+                return;
+            }
+            let (image_x, image_y) = panel.view.transform().to_image(x, y);
+            if let Some(layer) = panel.view.cursor_layer.borrow().as_ref() {
+                if layer.contains(panel.view.origin.get(), image_x, image_y) {
+                    start_begin.set(Some((layer.x, layer.y)));
+                }
+            }
+        });
+        let panel = self.clone();
+        drag.connect_drag_update(move |_, offset_x, offset_y| {
+            if let Some((x, y)) = start.get() {
+                let scale = panel.view.transform().scale;
+                if let Some(layer) = panel.view.cursor_layer.borrow_mut().as_mut() {
+                    layer.x = x + offset_x / scale;
+                    layer.y = y + offset_y / scale;
+                }
+                panel.view.loupe_area.queue_draw();
+            }
+        });
+        self.view.picture.add_controller(drag);
+    }
+}
+
+/* options of the Copy text tool; the language is a Tesseract code such as eng or ita+eng */
+fn build_text_panel(tx: &mpsc::Sender<u32>) -> Box {
+    let container = Box::new(Orientation::Horizontal, 6);
+    container.append(&build_label(
+        "Drag a rectangle to read its text, or".to_string(),
+    ));
+    container.append(&build_toolbar_button("Read whole image", 16, tx));
+    container.append(&build_toolbar_button("Auto-redact", 17, tx));
+    container.append(&build_label("Language:".to_string()));
+    let language_entry = Entry::builder()
+        .text(retrieve_data_from_json().ocr_language)
+        .valign(Align::Center)
+        .build();
+    // saved once typed, not at every key
+    language_entry.connect_activate(save_ocr_language);
+    let focus = EventControllerFocus::new();
+    let entry_focus = language_entry.clone();
+    focus.connect_leave(move |_| {
+        save_ocr_language(&entry_focus);
+    });
+    language_entry.add_controller(focus);
+    container.append(&language_entry);
+    container
+}
+
+fn save_ocr_language(entry: &Entry) {
+    let mut settings = retrieve_data_from_json();
+    if settings.ocr_language != entry.text().as_str() {
+        settings.ocr_language = entry.text().to_string();
+        write_settings(&settings);
+    }
+}
+
+/* options of the Transform tool; every button queues its edit in pending and the next tick
+applies them in order to the current image */
+#[derive(Clone)]
+struct TransformPanel {
+    container: Box,
+    width: SpinButton,
+    height: SpinButton,
+    unit_list: DropDown,
+    keep_ratio_check: CheckButton,
+    pending: Rc<RefCell<Vec<GeometryEdit>>>,
+    view: EditorView,
+}
+
+impl TransformPanel {
+    fn new(view: &EditorView, tx: &mpsc::Sender<u32>) -> TransformPanel {
+        let container = Box::new(Orientation::Horizontal, 6);
+        // the range follows the unit, see reset
+        let width = SpinButton::with_range(1.0, MAX_DIMENSION as f64, 1.0);
+        width.set_valign(Align::Center);
+        let height = SpinButton::with_range(1.0, MAX_DIMENSION as f64, 1.0);
+        height.set_valign(Align::Center);
+        let unit_list = build_dropdown(&["px", "%"]);
+        let keep_ratio_check = CheckButton::with_label("Keep ratio");
+        keep_ratio_check.set_active(true);
+        height.set_sensitive(false);
+        let filter_names: Vec<&str> = RESIZE_FILTERS.iter().map(|(name, _)| *name).collect();
+        let filter_list = build_dropdown(&filter_names);
+        filter_list.set_selected(RESIZE_FILTERS.len() as u32 - 1);
+        let resize_button = build_button("Resize".to_string());
+        let left_button = build_button("Rotate left".to_string());
+        let right_button = build_button("Rotate right".to_string());
+        let angle = SpinButton::with_range(-360.0, 360.0, 0.5);
+        angle.set_digits(1);
+        angle.set_valign(Align::Center);
+        let background_entry = Entry::builder()
+            .text("#FFFFFF")
+            .valign(Align::Center)
+            .build();
+        let transparent_check = CheckButton::with_label("Transparent");
+        let rotate_button = build_button("Rotate".to_string());
+        let flip_horizontal_button = build_button("Flip horizontal".to_string());
+        let flip_vertical_button = build_button("Flip vertical".to_string());
+
+        container.append(&build_label("W".to_string()));
+        container.append(&width);
+        container.append(&build_label("H".to_string()));
+        container.append(&height);
+        container.append(&unit_list);
+        container.append(&keep_ratio_check);
+        container.append(&filter_list);
+        container.append(&resize_button);
+        container.append(&left_button);
+        container.append(&right_button);
+        container.append(&build_label("Angle".to_string()));
+        container.append(&angle);
+        container.append(&background_entry);
+        container.append(&transparent_check);
+        container.append(&rotate_button);
+        container.append(&flip_horizontal_button);
+        container.append(&flip_vertical_button);
+
+        let panel = TransformPanel {
+            container,
+            width,
+            height,
+            unit_list,
+            keep_ratio_check,
+            pending: Rc::new(RefCell::new(Vec::new())),
+            view: view.clone(),
+        };
+        let panel_unit = panel.clone();
+        panel
+            .unit_list
+            .connect_selected_notify(move |_| panel_unit.reset());
+        let height_ratio = panel.height.clone();
+        panel
+            .keep_ratio_check
+            .connect_toggled(move |check| height_ratio.set_sensitive(!check.is_active()));
+
+        let panel_resize = panel.clone();
+        panel.connect_edit(&resize_button, tx, move || {
+            let (image_width, image_height) = panel_resize.view.image_size();
+            let (width, height) = match target_size(
+                (image_width as u32, image_height as u32),
+                panel_resize.width.value(),
+                panel_resize.height.value(),
+                panel_resize.unit_list.selected() == 1,
+                panel_resize.keep_ratio_check.is_active(),
+            ) {
+                Ok(size) => size,
+                Err(error) => {
+                    eprintln!("{}", error);
+                    return None;
+                }
+            };
+            let filter = RESIZE_FILTERS
+                .get(filter_list.selected() as usize)
+                .map(|(_, filter)| *filter)?;
+            Some(GeometryEdit::Resize {
+                width,
+                height,
+                filter,
+            })
+        });
+        panel.connect_edit(&left_button, tx, || Some(GeometryEdit::RotateQuarter(3)));
+        panel.connect_edit(&right_button, tx, || Some(GeometryEdit::RotateQuarter(1)));
+        panel.connect_edit(&rotate_button, tx, move || {
+            let background = if transparent_check.is_active() {
+                Rgba([0, 0, 0, 0])
+            } else {
+                match color::parse_hex(background_entry.text().trim()) {
+                    Some(background) => background,
+                    None => {
+                        eprintln!("The background must be a colour like #FFFFFF");
+                        return None;
+                    }
+                }
+            };
+            Some(GeometryEdit::Rotate {
+                degrees: angle.value(),
+                background,
+            })
+        });
+        panel.connect_edit(&flip_horizontal_button, tx, || {
+            Some(GeometryEdit::FlipHorizontal)
+        });
+        panel.connect_edit(&flip_vertical_button, tx, || {
+            Some(GeometryEdit::FlipVertical)
+        });
+        panel
+    }
+
+    /* clicking the button queues the edit made by edit, if any, and asks the tick to apply it */
+    fn connect_edit(
+        &self,
+        button: &Button,
+        tx: &mpsc::Sender<u32>,
+        edit: impl Fn() -> Option<GeometryEdit> + 'static,
+    ) {
+        let pending = Rc::clone(&self.pending);
+        let tx_edit = tx.clone();
+        button.connect_clicked(move |_| {
+            if let Some(edit) = edit() {
+                pending.borrow_mut().push(edit);
+                let result = tx_edit.send(27);
+                match result {
+                    Ok(_) => {}
+                    Err(error) => {
+                        eprintln!("{}", error);
+                    }
+                }
+            }
+        });
+    }
+
+    /* the size fields show the current image: its pixels, or 100 in percent; they go up to
+    MAX_DIMENSION pixels */
+    fn reset(&self) {
+        if self.unit_list.selected() == 1 {
+            let (width, height) = self.view.image_size();
+            let max_percent = MAX_DIMENSION as f64 * 100.0 / width.max(height).max(1.0);
+            self.width.set_range(1.0, max_percent.max(100.0));
+            self.height.set_range(1.0, max_percent.max(100.0));
+            self.width.set_value(100.0);
+            self.height.set_value(100.0);
+        } else {
+            let (width, height) = self.view.image_size();
+            self.width.set_range(1.0, MAX_DIMENSION as f64);
+            self.height.set_range(1.0, MAX_DIMENSION as f64);
+            self.width.set_value(width);
+            self.height.set_value(height);
+        }
+    }
+}
+
+/* output effects with a small preview of the current image decorated by them; edits are kept
+in pending and stored in the settings when the focus leaves the panel or the image is saved
+or copied */
+#[derive(Clone)]
+struct EffectsPanel {
+    container: Box,
+    preview: Picture,
+    view: EditorView,
+    pending: Rc<RefCell<Option<Effects>>>,
+}
+
+impl EffectsPanel {
+    fn new(view: &EditorView) -> EffectsPanel {
+        let effects = retrieve_data_from_json().effects;
+        let container = Box::new(Orientation::Horizontal, 6);
+        let controls = Grid::new();
+        let enabled_check = CheckButton::with_label("Apply when saving and copying");
+        enabled_check.set_active(effects.enabled);
+        let spin = |min: f64, max: f64, value: f64| {
+            let spin = SpinButton::with_range(min, max, 1.0);
+            spin.set_value(value);
+            spin.set_valign(Align::Center);
+            spin
+        };
+        let entry = |text: &str, placeholder: &str| {
+            Entry::builder()
+                .text(text)
+                .placeholder_text(placeholder)
+                .valign(Align::Center)
+                .build()
+        };
+        let padding = spin(0.0, 1000.0, effects.padding as f64);
+        let background_entry = entry(&effects.background, TRANSPARENT);
+        let gradient_entry = entry(&effects.gradient_end, "Gradient to");
+        let border_width = spin(0.0, 200.0, effects.border_width as f64);
+        let border_entry = entry(&effects.border_color, "#000000");
+        let corner_radius = spin(0.0, 500.0, effects.corner_radius as f64);
+        let shadow_blur = spin(0.0, 200.0, effects.shadow_blur as f64);
+        let shadow_x = spin(-200.0, 200.0, effects.shadow_offset_x as f64);
+        let shadow_y = spin(-200.0, 200.0, effects.shadow_offset_y as f64);
+        let shadow_opacity = spin(0.0, 100.0, effects.shadow_opacity as f64);
+
+        controls.attach(&enabled_check, 0, 0, 4, 1);
+        controls.attach(&build_label("Padding".to_string()), 0, 1, 1, 1);
+        controls.attach(&padding, 1, 1, 1, 1);
+        controls.attach(&build_label("Background".to_string()), 2, 1, 1, 1);
+        controls.attach(&background_entry, 3, 1, 1, 1);
+        controls.attach(&gradient_entry, 4, 1, 1, 1);
+        controls.attach(&build_label("Border".to_string()), 0, 2, 1, 1);
+        controls.attach(&border_width, 1, 2, 1, 1);
+        controls.attach(&build_label("Border colour".to_string()), 2, 2, 1, 1);
+        controls.attach(&border_entry, 3, 2, 1, 1);
+        controls.attach(&build_label("Corner radius".to_string()), 4, 2, 1, 1);
+        controls.attach(&corner_radius, 5, 2, 1, 1);
+        controls.attach(&build_label("Shadow blur".to_string()), 0, 3, 1, 1);
+        controls.attach(&shadow_blur, 1, 3, 1, 1);
+        controls.attach(&build_label("Offset X".to_string()), 2, 3, 1, 1);
+        controls.attach(&shadow_x, 3, 3, 1, 1);
+        controls.attach(&build_label("Offset Y".to_string()), 4, 3, 1, 1);
+        controls.attach(&shadow_y, 5, 3, 1, 1);
+        controls.attach(&build_label("Opacity %".to_string()), 6, 3, 1, 1);
+        controls.attach(&shadow_opacity, 7, 3, 1, 1);
+
+        let preview = Picture::new();
+        preview.set_size_request(EFFECTS_PREVIEW_SIZE as i32, EFFECTS_PREVIEW_SIZE as i32);
+        preview.set_content_fit(ContentFit::Contain);
+        container.append(&controls);
+        container.append(&preview);
+        let panel = EffectsPanel {
+            container,
+            preview,
+            view: view.clone(),
+            pending: Rc::new(RefCell::new(None)),
+        };
+
+        // every change is shown in the preview, the settings are written once done editing
+        let update: Rc<dyn Fn()> = {
+            let panel = panel.clone();
+            let enabled_check = enabled_check.clone();
+            let padding = padding.clone();
+            let background_entry = background_entry.clone();
+            let gradient_entry = gradient_entry.clone();
+            let border_width = border_width.clone();
+            let border_entry = border_entry.clone();
+            let corner_radius = corner_radius.clone();
+            let shadow_blur = shadow_blur.clone();
+            let shadow_x = shadow_x.clone();
+            let shadow_y = shadow_y.clone();
+            let shadow_opacity = shadow_opacity.clone();
+            Rc::new(move || {
+                *panel.pending.borrow_mut() = Some(Effects {
+                    enabled: enabled_check.is_active(),
+                    padding: padding.value() as u32,
+                    background: background_entry.text().trim().to_string(),
+                    gradient_end: gradient_entry.text().trim().to_string(),
+                    border_width: border_width.value() as u32,
+                    border_color: border_entry.text().trim().to_string(),
+                    corner_radius: corner_radius.value() as u32,
+                    shadow_blur: shadow_blur.value() as u32,
+                    shadow_offset_x: shadow_x.value() as i32,
+                    shadow_offset_y: shadow_y.value() as i32,
+                    shadow_opacity: shadow_opacity.value() as u32,
+                });
+                panel.render();
+            })
+        };
+        let update_check = Rc::clone(&update);
+        enabled_check.connect_toggled(move |_| update_check());
+        for spin in [
+            &padding,
+            &border_width,
+            &corner_radius,
+            &shadow_blur,
+            &shadow_x,
+            &shadow_y,
+            &shadow_opacity,
+        ] {
+            let update_spin = Rc::clone(&update);
+            spin.connect_value_changed(move |_| update_spin());
+        }
+        for entry in [&background_entry, &gradient_entry, &border_entry] {
+            let update_entry = Rc::clone(&update);
+            entry.connect_changed(move |_| update_entry());
+        }
+        let focus = EventControllerFocus::new();
+        let panel_focus = panel.clone();
+        focus.connect_leave(move |_| {
+            panel_focus.store();
+        });
+        panel.container.add_controller(focus);
+        panel
+    }
+
+    /* writes the pending effects in the settings */
+    fn store(&self) {
+        let pending = self.pending.borrow_mut().take();
+        if let Some(effects) = pending {
+            let mut settings = retrieve_data_from_json();
+            if settings.effects != effects {
+                settings.effects = effects;
+                write_settings(&settings);
+            }
+        }
+    }
+
+    fn effects(&self) -> Effects {
+        match self.pending.borrow().as_ref() {
+            Some(effects) => effects.clone(),
+            None => retrieve_data_from_json().effects,
+        }
+    }
+
+    /* the current image made small enough for the preview, decorated with the effects scaled
+    down the same way */
+    fn render(&self) {
+        if !self.container.is_visible() {
+            return;
+        }
+        let pixels = self.view.pixels.borrow();
+        let image = match pixels.as_ref() {
+            Some(image) => image,
+            None => return,
+        };
+        let factor =
+            (EFFECTS_PREVIEW_SIZE as f64 / image.width().max(image.height()) as f64).min(1.0);
+        let small = image::imageops::resize(
+            image,
+            ((image.width() as f64 * factor).round() as u32).max(1),
+            ((image.height() as f64 * factor).round() as u32).max(1),
+            image::imageops::FilterType::Triangle,
+        );
+        match self.effects().scaled(factor).apply(&small) {
+            Ok(decorated) => self.preview.set_paintable(Some(&image_texture(&decorated))),
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    }
+}
+
+/* options of the Scan codes tool and what the last scan found, each code with a button
+copying its payload */
+#[derive(Clone)]
+struct CodePanel {
+    container: Box,
+    results: Box,
+    view: EditorView,
+}
+
+impl CodePanel {
+    fn new(view: &EditorView, tx: &mpsc::Sender<u32>) -> CodePanel {
+        let container = Box::new(Orientation::Vertical, 6);
+        let options = Box::new(Orientation::Horizontal, 6);
+        options.append(&build_label(
+            "Drag a rectangle to scan it for QR codes and barcodes, or".to_string(),
+        ));
+        options.append(&build_toolbar_button("Scan whole image", 19, tx));
+        container.append(&options);
+        let results = Box::new(Orientation::Vertical, 2);
+        container.append(&results);
+        CodePanel {
+            container,
+            results,
+            view: view.clone(),
+        }
+    }
+
+    /* lists and highlights the codes of the last scan, None clears them */
+    fn show_codes(&self, codes: Option<&[Barcode]>) {
+        while let Some(child) = self.results.first_child() {
+            self.results.remove(&child);
+        }
+        match codes {
+            Some([]) => self
+                .results
+                .append(&build_label("No codes found".to_string())),
+            Some(codes) => {
+                for code in codes {
+                    let row = Box::new(Orientation::Horizontal, 6);
+                    let label = build_label(code.describe());
+                    label.set_selectable(true);
+                    row.append(&label);
+                    let copy_button = build_button("Copy".to_string());
+                    let payload = code.payload.clone();
+                    copy_button.connect_clicked(move |_| match set_clipboard_text(&payload) {
+                        Ok(_) => {}
+                        Err(error) => {
+                            eprintln!("{}", error);
+                        }
+                    });
+                    row.append(&copy_button);
+                    self.results.append(&row);
+                }
+            }
+            None => {}
+        }
+        *self.view.barcodes.borrow_mut() = codes.map(|codes| codes.to_vec()).unwrap_or_default();
+        self.view.loupe_area.queue_draw();
+    }
+}
+
+/* scans the current step, or a rectangle of it, for codes on another thread; the tick copies
+a single code right away, otherwise the user picks one in the panel */
+fn scan_codes(
+    editor: &Editor,
+    timeline: &Timeline,
+    area: Option<&Coordinates>,
+    tx: &mpsc::Sender<u32>,
+) {
+    let area = area.cloned();
+    match timeline.current_image() {
+        Ok(img) => run_in_background(&editor.code_result, 29, tx, move || {
+            scan_area(&img, area.as_ref())
+        }),
+        Err(error) => {
+            eprintln!("{}", error);
+        }
+    }
+}
+
+/* recognises the text of the current step, or of a rectangle of it, on another thread; the
+tick then copies it and shows it so that it can be corrected and copied again */
+fn read_text(
+    editor: &Editor,
+    timeline: &Timeline,
+    area: Option<&Coordinates>,
+    tx: &mpsc::Sender<u32>,
+) {
+    let language = retrieve_data_from_json().ocr_language;
+    let area = area.cloned();
+    match timeline.current_image() {
+        Ok(img) => run_in_background(&editor.text_result, 28, tx, move || {
+            TextResult::Read(
+                recognize_area(&img, area.as_ref(), &language).map(|words| words_to_text(&words)),
+            )
+        }),
+        Err(error) => {
+            eprintln!("{}", error);
+        }
+    }
+}
+
+/* text recognised for an editor by Tesseract, left for the tick by action 28 */
+enum TextResult {
+    // Copy text: copied and shown
+    Read(Result<String, String>),
+    // Auto-redact: reviewed before it is redacted
+    Sensitive(Result<Vec<Detection>, String>),
+}
+
+// result of a run_in_background, taken by the tick
+type ResultSlot<T> = Arc<Mutex<Option<T>>>;
+
+/* runs work on another thread so that the window stays responsive; its result is put in
+slot and action is sent for the tick to pick it up */
+fn run_in_background<T: Send + 'static>(
+    slot: &ResultSlot<T>,
+    action: u32,
+    tx: &mpsc::Sender<u32>,
+    work: impl FnOnce() -> T + Send + 'static,
+) {
+    let slot = Arc::clone(slot);
+    let tx = tx.clone();
+    thread::spawn(move || {
+        let result = work();
+        *slot.lock().unwrap() = Some(result);
+        match tx.send(action) {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+}
+
+fn show_text_window(parent: &Window, text: &str) {
+    let text_view = TextView::builder()
+        .wrap_mode(WrapMode::WordChar)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    text_view.buffer().set_text(text);
+    let scrolled = ScrolledWindow::builder().child(&text_view).build();
+    let copy_button = build_button("Copy".to_string());
+    let close_button = build_button("Close".to_string());
+    let buttons = Box::new(Orientation::Horizontal, 0);
+    buttons.append(&copy_button);
+    buttons.append(&close_button);
+    let content = Box::new(Orientation::Vertical, 0);
+    content.append(&scrolled);
+    content.append(&buttons);
+    let window = Window::builder()
+        .title("Screen-PDS text")
+        .default_width(480)
+        .default_height(360)
+        .transient_for(parent)
+        .child(&content)
+        .build();
+
+    copy_button.connect_clicked(move |_| {
+        let buffer = text_view.buffer();
+        let text = buffer.text(&buffer.start_iter(), &buffer.end_iter(), false);
+        match set_clipboard_text(&text) {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+    let window_close = window.clone();
+    close_button.connect_clicked(move |_| {
+        window_close.close();
+    });
+    window.present();
+}
+
+/* list of the sensitive text found, each one can be kept or left out before the boxes are
+applied with the chosen style */
+fn show_redact_review(editor: &Editor, tx: &mpsc::Sender<u32>) {
+    let list = Box::new(Orientation::Vertical, 0);
+    for (index, detection) in editor.view.detections.borrow().iter().enumerate() {
+        let check = CheckButton::with_label(&format!("{}: {}", detection.pattern, detection.text));
+        check.set_active(detection.enabled);
+        let view_check = editor.view.clone();
+        check.connect_toggled(move |check| {
+            if let Some(detection) = view_check.detections.borrow_mut().get_mut(index) {
+                detection.enabled = check.is_active();
+            }
+            view_check.loupe_area.queue_draw();
+        });
+        list.append(&check);
+    }
+    let scrolled = ScrolledWindow::builder()
+        .child(&list)
+        .hexpand(true)
+        .vexpand(true)
+        .build();
+    let style_list = build_dropdown(&["Solid", "Blur"]);
+    let apply_button = build_button("Apply".to_string());
+    let cancel_button = build_button("Cancel".to_string());
+    let buttons = Box::new(Orientation::Horizontal, 0);
+    buttons.append(&build_label("Style:".to_string()));
+    buttons.append(&style_list);
+    buttons.append(&apply_button);
+    buttons.append(&cancel_button);
+    let content = Box::new(Orientation::Vertical, 0);
+    content.append(&scrolled);
+    content.append(&buttons);
+    let window = Window::builder()
+        .title("Screen-PDS redact")
+        .default_width(480)
+        .default_height(360)
+        .transient_for(&editor.window)
+        .child(&content)
+        .build();
+
+    let redact_style = Rc::clone(&editor.redact_style);
+    style_list.connect_selected_notify(move |list| {
+        redact_style.set(match list.selected() {
+            1 => RedactStyle::Blur,
+            _ => RedactStyle::Solid,
+        });
+    });
+    editor.redact_style.set(RedactStyle::Solid);
+
+    // closing the review without applying drops the detections
+    let applied = Rc::new(Cell::new(false));
+    let applied_close = Rc::clone(&applied);
+    let view_close = editor.view.clone();
+    window.connect_close_request(move |_| {
+        if !applied_close.get() {
+            view_close.set_detections(Vec::new());
+        }
+        glib::Propagation::Proceed
+    });
+    let window_apply = window.clone();
+    let tx_apply = tx.clone();
+    let view_apply = editor.view.clone();
+    let redactions = Rc::clone(&editor.redactions);
+    apply_button.connect_clicked(move |_| {
+        applied.set(true);
+        *redactions.borrow_mut() = Some(view_apply.detections.take());
+        view_apply.loupe_area.queue_draw();
+        let result = tx_apply.send(18);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+        window_apply.close();
+    });
+    let window_cancel = window.clone();
+    cancel_button.connect_clicked(move |_| {
+        window_cancel.close();
+    });
+    window.present();
+}
+
+/* window comparing two images, steps of the timeline or files: after is aligned on before,
+then shown next to it, over it with an adjustable opacity or as a heatmap of the changes */
+#[derive(Clone)]
+struct CompareView {
+    window: Window,
+    picture: Picture,
+    sources: Rc<RefCell<Vec<PathBuf>>>,
+    before_list: DropDown,
+    after_list: DropDown,
+    mode_list: DropDown,
+    opacity: Scale,
+    tolerance: SpinButton,
+    score_label: Label,
+    // before and after on canvases of the same size, None if one could not be opened
+    pair: Rc<RefCell<Option<(RgbaImage, RgbaImage)>>>,
+}
+
+impl CompareView {
+    fn open(parent: &Window, steps: Vec<PathBuf>, current: u32) -> CompareView {
+        let names: Vec<String> = (1..=steps.len())
+            .map(|step| format!("Step {}", step))
+            .collect();
+        let names: Vec<&str> = names.iter().map(|name| name.as_str()).collect();
+        let before_list = build_dropdown(&names);
+        let after_list = build_dropdown(&names);
+        after_list.set_selected(current);
+        let before_file = build_button("File...".to_string());
+        let after_file = build_button("File...".to_string());
+        let mode_list = build_dropdown(&["Side by side", "Onion skin", "Heatmap"]);
+        let opacity = Scale::with_range(Orientation::Horizontal, 0.0, 1.0, 0.05);
+        opacity.set_value(0.5);
+        opacity.set_width_request(160);
+        opacity.set_visible(false);
+        let tolerance = SpinButton::with_range(0.0, 255.0, 1.0);
+        tolerance.set_valign(Align::Center);
+        let score_label = build_label(String::new());
+        let export_button = build_button("Export diff".to_string());
+
+        let sources_row = Box::new(Orientation::Horizontal, 0);
+        sources_row.append(&build_label("Before:".to_string()));
+        sources_row.append(&before_list);
+        sources_row.append(&before_file);
+        sources_row.append(&build_label("After:".to_string()));
+        sources_row.append(&after_list);
+        sources_row.append(&after_file);
+        let options_row = Box::new(Orientation::Horizontal, 0);
+        options_row.append(&mode_list);
+        options_row.append(&opacity);
+        options_row.append(&build_label("Tolerance:".to_string()));
+        options_row.append(&tolerance);
+        options_row.append(&score_label);
+        options_row.append(&export_button);
+        let picture = Picture::new();
+        let scrolled = ScrolledWindow::builder()
+            .child(&picture)
+            .hexpand(true)
+            .vexpand(true)
+            .build();
+        let content = Box::new(Orientation::Vertical, 0);
+        content.append(&sources_row);
+        content.append(&options_row);
+        content.append(&scrolled);
+        let window = Window::builder()
+            .title("Screen-PDS compare")
+            .default_width(1024)
+            .default_height(720)
+            .transient_for(parent)
+            .child(&content)
+            .build();
+
+        let view = CompareView {
+            window,
+            picture,
+            sources: Rc::new(RefCell::new(steps)),
+            before_list,
+            after_list,
+            mode_list,
+            opacity,
+            tolerance,
+            score_label,
+            pair: Rc::new(RefCell::new(None)),
+        };
+        for list in [&view.before_list, &view.after_list] {
+            let view_list = view.clone();
+            list.connect_selected_notify(move |_| view_list.load());
+        }
+        for (button, list) in [
+            (&before_file, view.before_list.clone()),
+            (&after_file, view.after_list.clone()),
+        ] {
+            let view_file = view.clone();
+            button.connect_clicked(move |_| {
+                if let Some(path) = choose_image_to_open() {
+                    view_file.add_file(path, &list);
+                }
+            });
+        }
+        let view_mode = view.clone();
+        view.mode_list.connect_selected_notify(move |list| {
+            view_mode.opacity.set_visible(list.selected() == 1);
+            view_mode.render();
+        });
+        let view_opacity = view.clone();
+        view.opacity
+            .connect_value_changed(move |_| view_opacity.render());
+        let view_tolerance = view.clone();
+        view.tolerance
+            .connect_value_changed(move |_| view_tolerance.render());
+        let view_export = view.clone();
+        export_button.connect_clicked(move |_| view_export.export());
+
+        view.load();
+        view.window.present();
+        view
+    }
+
+    /* adds a file to both lists of sources and selects it in the given one */
+    fn add_file(&self, path: PathBuf, list: &DropDown) {
+        let name = path
+            .file_name()
+            .map(|name| name.to_string_lossy().to_string())
+            .unwrap_or_default();
+        self.sources.borrow_mut().push(path);
+        for each in [&self.before_list, &self.after_list] {
+            if let Some(model) = each.model().and_downcast::<StringList>() {
+                model.append(&name);
+            }
+        }
+        list.set_selected(self.sources.borrow().len() as u32 - 1);
+    }
+
+    /* opens the chosen sources and aligns them */
+    fn load(&self) {
+        let path = |list: &DropDown| self.sources.borrow().get(list.selected() as usize).cloned();
+        let open_source = |list: &DropDown| {
+            path(list)
+                .ok_or("No image chosen".to_string())
+                .and_then(|path| open(path).map_err(|error| error.to_string()))
+                .map(|img| img.to_rgba8())
+        };
+        let pair = open_source(&self.before_list).and_then(|before| {
+            open_source(&self.after_list).map(|after| {
+                let shift = align(&before, &after, COMPARE_MAX_SHIFT);
+                aligned_pair(&before, &after, shift)
+            })
+        });
+        match pair {
+            Ok(pair) => *self.pair.borrow_mut() = Some(pair),
+            Err(error) => {
+                eprintln!("{}", error);
+                *self.pair.borrow_mut() = None;
+            }
+        }
+        self.render();
+    }
+
+    /* shows the pair in the chosen mode, with its score */
+    fn render(&self) {
+        let pair = self.pair.borrow();
+        let (before, after) = match pair.as_ref() {
+            Some(pair) => pair,
+            None => {
+                self.picture.set_paintable(None::<&gdk::Paintable>);
+                self.score_label.set_label("");
+                return;
+            }
+        };
+        let options = self.options();
+        self.score_label
+            .set_label(&score(before, after, &options).describe());
+        let shown = match self.mode_list.selected() {
+            1 => onion_skin(before, after, self.opacity.value()),
+            2 => heatmap(before, after, &options),
+            _ => side_by_side(before, after),
+        };
+        self.picture.set_paintable(Some(&image_texture(&shown)));
+    }
+
+    fn options(&self) -> DiffOptions {
+        DiffOptions {
+            tolerance: self.tolerance.value() as u8,
+            ..DiffOptions::default()
+        }
+    }
+
+    /* saves the heatmap of the pair as a PNG */
+    fn export(&self) {
+        let pair = self.pair.borrow();
+        let (before, after) = match pair.as_ref() {
+            Some(pair) => pair,
+            None => return,
+        };
+        if let Some(path) = choose_path(".png") {
+            let map = heatmap(before, after, &self.options());
+            match map.save(&path) {
                 Ok(_) => {}
                 Err(error) => {
                     eprintln!("{}", error);
                 }
             }
-        })
-        .unwrap();
+        }
+    }
+}
 
-        hook.register(redo_hotkey, move || {
-            let result = tx_thread_function_number_hotkey_redo.send(4);
-            match result {
-                Ok(_) => {}
-                Err(error) => {
-                    eprintln!("{}", error);
-                }
+/* window laying out chosen steps of the timeline and files in one image; Create adds it to
+the timeline, from where it is saved as any other step */
+#[derive(Clone)]
+struct CollageView {
+    window: Window,
+    picture: Picture,
+    sources_box: Box,
+    // decoded once when added, the preview is made again at every change of the options
+    sources: Rc<RefCell<Vec<(CheckButton, String, RgbaImage)>>>,
+    layout_list: DropDown,
+    columns: SpinButton,
+    spacing: SpinButton,
+    background_entry: Entry,
+    labels_check: CheckButton,
+}
+
+impl CollageView {
+    fn open(editor: &Editor, steps: Vec<PathBuf>, tx: &mpsc::Sender<u32>) -> CollageView {
+        let sources_box = Box::new(Orientation::Vertical, 0);
+        let add_file = build_button("Add file...".to_string());
+        let layout_list = build_dropdown(&["Horizontal", "Vertical", "Grid"]);
+        let columns = SpinButton::with_range(1.0, 20.0, 1.0);
+        columns.set_valign(Align::Center);
+        columns.set_sensitive(false);
+        let spacing = SpinButton::with_range(0.0, 200.0, 1.0);
+        spacing.set_value(16.0);
+        spacing.set_valign(Align::Center);
+        let background_entry = Entry::builder()
+            .text("#FFFFFF")
+            .valign(Align::Center)
+            .build();
+        let labels_check = CheckButton::with_label("Labels");
+        let create_button = build_button("Create".to_string());
+
+        let options_row = Box::new(Orientation::Horizontal, 0);
+        options_row.append(&layout_list);
+        options_row.append(&build_label("Columns:".to_string()));
+        options_row.append(&columns);
+        options_row.append(&build_label("Spacing:".to_string()));
+        options_row.append(&spacing);
+        options_row.append(&build_label("Background:".to_string()));
+        options_row.append(&background_entry);
+        options_row.append(&labels_check);
+        options_row.append(&create_button);
+        let sources_column = Box::new(Orientation::Vertical, 0);
+        sources_column.append(
+            &ScrolledWindow::builder()
+                .child(&sources_box)
+                .vexpand(true)
+                .build(),
+        );
+        sources_column.append(&add_file);
+        let picture = Picture::new();
+        let preview = ScrolledWindow::builder()
+            .child(&picture)
+            .hexpand(true)
+            .vexpand(true)
+            .build();
+        let body = Box::new(Orientation::Horizontal, 0);
+        body.append(&sources_column);
+        body.append(&preview);
+        let content = Box::new(Orientation::Vertical, 0);
+        content.append(&options_row);
+        content.append(&body);
+        let window = Window::builder()
+            .title("Screen-PDS collage")
+            .default_width(1024)
+            .default_height(720)
+            .transient_for(&editor.window)
+            .child(&content)
+            .build();
+
+        let view = CollageView {
+            window,
+            picture,
+            sources_box,
+            sources: Rc::new(RefCell::new(Vec::new())),
+            layout_list,
+            columns,
+            spacing,
+            background_entry,
+            labels_check,
+        };
+        for (index, path) in steps.into_iter().enumerate() {
+            view.add_source(format!("Step {}", index + 1), path, false);
+        }
+        // a square grid of the steps until other columns are chosen
+        view.columns
+            .set_value(square_columns(view.sources.borrow().len() as u32).max(1) as f64);
+        let view_file = view.clone();
+        add_file.connect_clicked(move |_| {
+            if let Some(path) = choose_image_to_open() {
+                let name = path
+                    .file_name()
+                    .map(|name| name.to_string_lossy().to_string())
+                    .unwrap_or_default();
+                view_file.add_source(name, path, true);
             }
-        })
-        .unwrap();
+        });
+        let view_layout = view.clone();
+        view.layout_list.connect_selected_notify(move |list| {
+            view_layout.columns.set_sensitive(list.selected() == 2);
+            view_layout.render();
+        });
+        for spin in [&view.columns, &view.spacing] {
+            let view_spin = view.clone();
+            spin.connect_value_changed(move |_| view_spin.render());
+        }
+        let view_background = view.clone();
+        view.background_entry
+            .connect_changed(move |_| view_background.render());
+        let view_labels = view.clone();
+        view.labels_check
+            .connect_toggled(move |_| view_labels.render());
 
-        hook.register(cancel_hotkey, move || {
-            let result = tx_thread_function_number_hotkey_cancel.send(5);
+        let view_create = view.clone();
+        let collage_slot = Rc::clone(&editor.collage);
+        let tx_create = tx.clone();
+        create_button.connect_clicked(move |_| {
+            let made = match view_create.compose() {
+                Some(made) => made,
+                None => return,
+            };
+            *collage_slot.borrow_mut() = Some(made);
+            let result = tx_create.send(24);
             match result {
                 Ok(_) => {}
                 Err(error) => {
                     eprintln!("{}", error);
                 }
             }
-        })
-        .unwrap();
-
-        std::thread::sleep(time::Duration::from_millis(5000));
-    });
+            view_create.window.close();
+        });
 
-    let _worker_thread = thread::spawn(move || loop {
-        let output = rx_thread_function_number.recv();
+        view.window.present();
+        view
+    }
 
-        match output {
-            Ok(result) => match result {
-                1 => {
-                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
-                    *flag = 1;
-                }
-                2 => {
-                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
-                    *flag = 2;
-                }
-                3 => {
-                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
-                    *flag = 3;
-                }
-                4 => {
-                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
-                    *flag = 4;
-                }
-                5 => {
-                    let mut flag = flag_function_selector_worker_thread.lock().unwrap();
-                    *flag = 5;
-                }
-                _ => eprintln!("Other"),
-            },
+    /* adds a capture to the list, to be used when checked */
+    fn add_source(&self, name: String, path: PathBuf, active: bool) {
+        let image = match open(&path) {
+            Ok(img) => img.to_rgba8(),
             Err(error) => {
-                eprintln!("Error: {}", error);
+                eprintln!("{}", error);
+                return;
             }
-        }
-    });
-
-    set_default_json();
-    let tmp_path_file = create_starting_tmp_path_file();
-    let mut full_window = Window::builder().build();
-    let mut screen_image = Picture::builder().build();
-    let mut activate_check_coor = false;
-    let mut coor_outer: Arc<Mutex<Coordinates>> = Arc::new(Mutex::new(Coordinates::default()));
-    let mut timeline_current_index: u32 = 0;
-    let mut timeline_last_index: u32 = 0;
-    let mut condvar = Arc::new(Condvar::new());
+        };
+        let check = CheckButton::with_label(&name);
+        check.set_active(active);
+        let view_check = self.clone();
+        check.connect_toggled(move |_| view_check.render());
+        self.sources_box.append(&check);
+        self.sources.borrow_mut().push((check, name, image));
+        self.render();
+    }
 
-    let tick = move || {
-        let mut flag = flag_function_selector.lock().unwrap();
-        match *flag {
-            1 => {
-                // new
-                *flag = 0;
-                if !activate_check_coor {
-                    timeline_current_index = 0;
-                    timeline_last_index = 0;
-                    full_window = Window::builder().build();
-                    window.minimize();
-                    capture_screenshot_with_delay(timer_list.selected(), &tmp_path_file);
-                    set_image_to_clipboard(&tmp_path_file);
-                    screen_image = Picture::for_filename(&tmp_path_file);
-                    build_fullscreen_window(&screen_image, &full_window);
-                    (coor_outer, condvar) = draw_area(&full_window);
-                    activate_check_coor = true;
-                }
-            }
-            2 => {
-                // save
-                *flag = 0;
-                if activate_check_coor {
-                    let current_path = create_new_path(&tmp_path_file, timeline_current_index);
-                    if save_image(extension_list.selected(), &current_path) {
-                        full_window.close();
-                        window.present();
-                        activate_check_coor = false;
-                    }
-                }
-            }
-            3 => {
-                // undo
-                *flag = 0;
-                if timeline_current_index > 0 && activate_check_coor {
-                    timeline_current_index -= 1;
-                    let current_path = create_new_path(&tmp_path_file, timeline_current_index);
-                    full_window
-                        .child()
-                        .unwrap()
-                        .downcast::<gtk::Box>()
-                        .unwrap()
-                        .remove(&screen_image);
-                    screen_image = Picture::for_filename(&current_path);
-                    screen_image.set_content_fit(ContentFit::ScaleDown);
-                    screen_image.set_halign(Align::Start);
-                    screen_image.set_valign(Align::Start);
-                    full_window
-                        .child()
-                        .unwrap()
-                        .downcast::<gtk::Box>()
-                        .unwrap()
-                        .append(&screen_image);
-                    set_image_to_clipboard(&current_path);
-                }
+    /* collage of the checked captures with the chosen options, None without any */
+    fn compose(&self) -> Option<RgbaImage> {
+        let mut names = Vec::new();
+        let mut images = Vec::new();
+        for (check, name, image) in self.sources.borrow().iter() {
+            if check.is_active() {
+                names.push(name.clone());
+                images.push(image.clone());
             }
-            4 => {
-                // redo
-                *flag = 0;
-                if timeline_current_index < timeline_last_index && activate_check_coor {
-                    timeline_current_index += 1;
-                    let current_path = create_new_path(&tmp_path_file, timeline_current_index);
-                    full_window
-                        .child()
-                        .unwrap()
-                        .downcast::<gtk::Box>()
-                        .unwrap()
-                        .remove(&screen_image);
-                    screen_image = Picture::for_filename(&current_path);
-                    screen_image.set_content_fit(ContentFit::ScaleDown);
-                    screen_image.set_halign(Align::Start);
-                    screen_image.set_valign(Align::Start);
-                    full_window
-                        .child()
-                        .unwrap()
-                        .downcast::<gtk::Box>()
-                        .unwrap()
-                        .append(&screen_image);
-                    set_image_to_clipboard(&current_path);
-                }
-            }
-            5 => {
-                // cancel
-                *flag = 0;
-                if activate_check_coor {
-                    full_window.close();
-                    window.present();
-                    activate_check_coor = false;
+        }
+        if images.is_empty() {
+            return None;
+        }
+        let background = color::parse_hex(self.background_entry.text().trim())
+            .unwrap_or(Rgba([255, 255, 255, 255]));
+        let options = CollageOptions {
+            layout: CollageLayout::from_index(
+                self.layout_list.selected(),
+                self.columns.value() as u32,
+            ),
+            spacing: self.spacing.value() as u32,
+            background,
+            label_height: if self.labels_check.is_active() {
+                COLLAGE_LABEL_HEIGHT
+            } else {
+                0
+            },
+        };
+        let mut made = collage(&images, &options);
+        // dark text on light backgrounds, light text on dark ones
+        let luminance =
+            (background[0] as u32 * 299 + background[1] as u32 * 587 + background[2] as u32 * 114)
+                / 1000;
+        let text_color = if luminance > 128 {
+            Rgba([0, 0, 0, 255])
+        } else {
+            Rgba([255, 255, 255, 255])
+        };
+        if options.label_height > 0 {
+            for (name, (left, top, width, height)) in names.iter().zip(made.labels.iter()) {
+                if let Some(label) = text_image(name, *width, *height, text_color) {
+                    image::imageops::overlay(&mut made.image, &label, *left as i64, *top as i64);
                 }
             }
-            _ => {}
         }
-        if activate_check_coor {
-            let mut coor = coor_outer.lock().unwrap();
-            if !coor.offset_x.is_nan() && (coor.offset_x != 0.0 || coor.offset_y != 0.0) {
-                let mut local_coor = Coordinates {
-                    start_x: coor.start_x,
-                    start_y: coor.start_y,
-                    offset_x: coor.offset_x,
-                    offset_y: coor.offset_y,
-                };
+        Some(made.image)
+    }
 
-                if local_coor.offset_x.is_sign_negative() {
-                    local_coor.start_x += local_coor.offset_x;
-                    local_coor.offset_x = -local_coor.offset_x;
-                }
-                if local_coor.offset_y.is_sign_negative() {
-                    local_coor.start_y += local_coor.offset_y;
-                    local_coor.offset_y = -local_coor.offset_y;
-                }
-
-                let prev_path = create_new_path(&tmp_path_file, timeline_current_index);
-                let mut img = open(prev_path.as_path()).unwrap();
-                if (local_coor.start_x as u32) < img.width()
-                    && (local_coor.start_y as u32) < img.height()
-                {
-                    timeline_current_index += 1;
-                    timeline_last_index = timeline_current_index;
-                    let new_path = create_new_path(&tmp_path_file, timeline_current_index);
-                    img.crop(
-                        local_coor.start_x as u32,
-                        local_coor.start_y as u32,
-                        local_coor.offset_x as u32,
-                        local_coor.offset_y as u32,
-                    )
-                    .save(&new_path)
-                    .unwrap();
-                    set_image_to_clipboard(&new_path);
-                    full_window
-                        .child()
-                        .unwrap()
-                        .downcast::<gtk::Box>()
-                        .unwrap()
-                        .remove(&screen_image);
-                    screen_image = Picture::for_filename(&new_path);
-                    screen_image.set_content_fit(ContentFit::ScaleDown);
-                    screen_image.set_halign(Align::Start);
-                    screen_image.set_valign(Align::Start);
-                    full_window
-                        .child()
-                        .unwrap()
-                        .downcast::<gtk::Box>()
-                        .unwrap()
-                        .append(&screen_image);
-                    coor.start_x = f64::NAN;
-                    coor.start_y = f64::NAN;
-                    coor.offset_x = f64::NAN;
-                    coor.offset_y = f64::NAN;
-                }
-            }
-            condvar.notify_one();
+    fn render(&self) {
+        match self.compose() {
+            Some(made) => self.picture.set_paintable(Some(&image_texture(&made))),
+            None => self.picture.set_paintable(None::<&gdk::Paintable>),
         }
-
-        glib::ControlFlow::Continue
-    };
-
-    glib::timeout_add_seconds_local(1, tick);
+    }
 }
 
-fn capture_screenshot_with_delay(delay: u32, path: &PathBuf) {
-    match delay {
-        0 => {}
-        1 => {
-            std::thread::sleep(time::Duration::from_millis(3000));
-        }
-        2 => {
-            std::thread::sleep(time::Duration::from_millis(5000));
-        }
-        3 => {
-            std::thread::sleep(time::Duration::from_millis(10000));
+/* text centred in a transparent image of the given size */
+fn text_image(text: &str, width: u32, height: u32, color: Rgba<u8>) -> Option<RgbaImage> {
+    let mut surface =
+        cairo::ImageSurface::create(cairo::Format::ARgb32, width as i32, height as i32).ok()?;
+    {
+        let cr = cairo::Context::new(&surface).ok()?;
+        cr.set_source_rgba(
+            color[0] as f64 / 255.0,
+            color[1] as f64 / 255.0,
+            color[2] as f64 / 255.0,
+            color[3] as f64 / 255.0,
+        );
+        cr.select_font_face("Sans", cairo::FontSlant::Normal, cairo::FontWeight::Normal);
+        cr.set_font_size(height as f64 * 0.6);
+        let extents = cr.text_extents(text).ok()?;
+        cr.move_to(
+            (width as f64 - extents.width()) / 2.0 - extents.x_bearing(),
+            (height as f64 - extents.height()) / 2.0 - extents.y_bearing(),
+        );
+        let _ = cr.show_text(text);
+    }
+    surface.flush();
+    let stride = surface.stride() as usize;
+    let data = surface.data().ok()?;
+    let mut pixels = Vec::with_capacity((width * height) as usize);
+    for row in 0..height as usize {
+        for bytes in data[row * stride..row * stride + width as usize * 4].chunks_exact(4) {
+            pixels.push(u32::from_ne_bytes([bytes[0], bytes[1], bytes[2], bytes[3]]));
         }
-        _ => eprintln!("Error"),
     }
-
-    capture_fullscreen(path);
+    Some(from_premultiplied_argb(width, height, &pixels))
 }
 
-/* captures fullscreen screenshot */
-fn capture_fullscreen(path: &PathBuf) {
-    let screen = Screen::from_point(0, 0).unwrap();
-    let image = screen.capture().unwrap();
-    let result = image.save(path.as_path());
-    match result {
-        Ok(_v) => {}
-        Err(error) => eprintln!("Error: {}", error),
-    }
+/* texture with the pixels of the image, for a Picture */
+fn image_texture(image: &RgbaImage) -> gdk::MemoryTexture {
+    gdk::MemoryTexture::new(
+        image.width() as i32,
+        image.height() as i32,
+        gdk::MemoryFormat::R8g8b8a8,
+        &glib::Bytes::from(image.as_raw()),
+        image.width() as usize * 4,
+    )
 }
 
-/* save the image in a chosen extension in a chosen path */
-fn save_image(current_selected: u32, tmp_path: &PathBuf) -> bool {
-    let tmp = open(tmp_path.as_path());
-
-    match tmp {
-        Ok(tmp_image) => match current_selected {
-            0 => {
-                let result = choose_path(".png");
-                match result {
-                    Some(path) => {
-                        path.clone().set_extension("png");
-                        let _ = tmp_image.save_with_format(path, ImageFormat::Png);
-                        true
-                    }
-                    None => false,
-                }
-            }
-            1 => {
-                let result = choose_path(".jpg");
-                match result {
-                    Some(path) => {
-                        path.clone().set_extension("jpg");
-                        let _ = tmp_image.save_with_format(path, ImageFormat::Jpeg);
-                        true
-                    }
-                    None => false,
-                }
-            }
-            2 => {
-                let result = choose_path(".gif");
-                match result {
-                    Some(path) => {
-                        let mut pixels = tmp_image.to_rgb8().into_raw();
-                        let frame = gif::Frame::from_rgb(
-                            tmp_image.width() as u16,
-                            tmp_image.height() as u16,
-                            &mut *pixels,
-                        );
-                        let mut image = File::create(path).unwrap();
-                        let mut encoder =
-                            gif::Encoder::new(&mut image, frame.width, frame.height, &[]).unwrap();
-                        encoder.write_frame(&frame).unwrap();
-                        true
-                    }
-                    None => false,
-                }
-            }
-            _ => {
-                eprintln!("Error");
-                false
-            }
-        },
-        Err(_) => false,
+/* cairo surface with the pixels of the image, premultiplied as cairo expects */
+fn image_surface(image: &RgbaImage) -> Option<cairo::ImageSurface> {
+    let mut data = Vec::with_capacity((image.width() * image.height() * 4) as usize);
+    for pixel in image.pixels() {
+        let alpha = pixel[3] as u32;
+        let premultiplied = |value: u8| value as u32 * alpha / 255;
+        let argb = alpha << 24
+            | premultiplied(pixel[0]) << 16
+            | premultiplied(pixel[1]) << 8
+            | premultiplied(pixel[2]);
+        data.extend_from_slice(&argb.to_ne_bytes());
     }
+    cairo::ImageSurface::create_for_data(
+        data,
+        cairo::Format::ARgb32,
+        image.width() as i32,
+        image.height() as i32,
+        image.width() as i32 * 4,
+    )
+    .ok()
 }
 
-/* build the fullscreen window with the new acquisition */
-fn build_fullscreen_window(image: &Picture, window_full: &Window) {
-    let content = Box::new(Orientation::Horizontal, 0);
-    content.append(image);
+/* small square filled with the colour, empty while there is none */
+fn build_swatch(color: &Rc<Cell<Option<Rgba<u8>>>>) -> DrawingArea {
+    let swatch = DrawingArea::new();
+    swatch.set_content_width(20);
+    swatch.set_content_height(20);
+    swatch.set_valign(Align::Center);
+    let color = Rc::clone(color);
+    swatch.set_draw_func(move |_, cr, width, height| {
+        if let Some(pixel) = color.get() {
+            cr.set_source_rgb(
+                pixel[0] as f64 / 255.0,
+                pixel[1] as f64 / 255.0,
+                pixel[2] as f64 / 255.0,
+            );
+            let _ = cr.paint();
+        }
+        cr.set_source_rgb(0.5, 0.5, 0.5);
+        cr.set_line_width(1.0);
+        cr.rectangle(0.5, 0.5, width as f64 - 1.0, height as f64 - 1.0);
+        let _ = cr.stroke();
+    });
+    swatch
+}
 
-    window_full.set_child(Some(&content));
-    window_full.present();
-    window_full.fullscreen();
+/* editor button that sends its action number to the worker thread */
+fn build_toolbar_button(label: &str, number: u32, tx: &mpsc::Sender<u32>) -> Button {
+    let button = build_button(label.to_string());
+    let tx = tx.clone();
+    button.connect_clicked(move |_| {
+        let result = tx.send(number);
+        match result {
+            Ok(_) => {}
+            Err(error) => {
+                eprintln!("{}", error);
+            }
+        }
+    });
+    button
 }
 
 fn build_button(label: String) -> Button {
@@ -583,6 +4530,8 @@ fn build_settings_window() {
     let current_u_shortcut = build_label("Undo action:".to_string());
     let current_r_shortcut = build_label("Redo action:".to_string());
     let current_c_shortcut: Label = build_label("Cancel :".to_string());
+    let current_p_shortcut = build_label("Pick colour:".to_string());
+    let current_q_shortcut = build_label("Scan codes:".to_string());
 
     let button_change_shortcut = build_button("Change Shortcuts".to_string());
     let button_go_back = build_button("<-".to_string().to_string());
@@ -602,6 +4551,8 @@ fn build_settings_window() {
     let u_shortcut = build_label("ERR".to_string());
     let r_shortcut = build_label("ERR".to_string());
     let c_shortcut: Label = build_label("ERR".to_string());
+    let p_shortcut = build_label("ERR".to_string());
+    let q_shortcut = build_label("ERR".to_string());
 
     let mut json_data: JSONStruct = JSONStruct {
         new_shortcut_modif: "CONTROL".to_string(),
@@ -615,6 +4566,7 @@ fn build_settings_window() {
         cancel_shortcut_modif: "CONTROL".to_string(),
         cancel_shortcut_key: "E".to_string(),
         default_location: "./".to_string(),
+        ..JSONStruct::default()
     };
 
     match parsed_data {
@@ -631,8 +4583,26 @@ fn build_settings_window() {
             json_data.redo_shortcut_key = data.redo_shortcut_key;
             json_data.cancel_shortcut_modif = data.cancel_shortcut_modif;
             json_data.cancel_shortcut_key = data.cancel_shortcut_key;
+            json_data.pick_shortcut_modif = data.pick_shortcut_modif;
+            json_data.pick_shortcut_key = data.pick_shortcut_key;
+            json_data.scan_shortcut_modif = data.scan_shortcut_modif;
+            json_data.scan_shortcut_key = data.scan_shortcut_key;
 
             json_data.default_location = data.default_location;
+            json_data.copy_policy = data.copy_policy;
+            json_data.editor_mode = data.editor_mode;
+            json_data.color_format = data.color_format;
+            json_data.recent_colors = data.recent_colors;
+            json_data.crop_presets = data.crop_presets;
+            json_data.last_region = data.last_region;
+            json_data.saved_regions = data.saved_regions;
+            json_data.include_cursor = data.include_cursor;
+            json_data.click_ring = data.click_ring;
+            json_data.settle_delay_ms = data.settle_delay_ms;
+            json_data.restore_main_window = data.restore_main_window;
+            json_data.ocr_language = data.ocr_language;
+            json_data.redact_patterns = data.redact_patterns;
+            json_data.auto_scroll = data.auto_scroll;
 
             n_shortcut
                 .set_label(&(data_clone.new_shortcut_modif + " + " + &json_data.new_shortcut_key));
@@ -648,6 +4618,12 @@ fn build_settings_window() {
             c_shortcut.set_label(
                 &(data_clone.cancel_shortcut_modif + " + " + &json_data.cancel_shortcut_key),
             );
+            p_shortcut.set_label(
+                &(data_clone.pick_shortcut_modif + " + " + &json_data.pick_shortcut_key),
+            );
+            q_shortcut.set_label(
+                &(data_clone.scan_shortcut_modif + " + " + &json_data.scan_shortcut_key),
+            );
         }
         Err(e) => {
             eprintln!("Error parsing JSON: {}", e);
@@ -680,6 +4656,89 @@ fn build_settings_window() {
     settings_grid.attach(&current_c_shortcut, 0, 5, 1, 1);
     settings_grid.attach(&c_shortcut, 1, 5, 1, 1);
 
+    let current_copy_policy = build_label("Copy to clipboard:".to_string());
+    let copy_policy_list = build_dropdown(&["On every step", "On save only", "Never"]);
+    copy_policy_list.set_selected(CopyPolicy::from_json(&json_data.copy_policy).to_index());
+    copy_policy_list.connect_selected_notify(move |list| {
+        let mut settings = retrieve_data_from_json();
+        settings.copy_policy = CopyPolicy::from_index(list.selected()).to_json();
+        write_settings(&settings);
+    });
+    settings_grid.attach(&current_copy_policy, 0, 7, 1, 1);
+    settings_grid.attach(&copy_policy_list, 1, 7, 1, 1);
+
+    let current_editor_mode = build_label("Open editor as:".to_string());
+    let editor_mode_list = build_dropdown(&["Fullscreen", "Window"]);
+    if json_data.editor_mode == "WINDOW" {
+        editor_mode_list.set_selected(1);
+    }
+    editor_mode_list.connect_selected_notify(move |list| {
+        let mut settings = retrieve_data_from_json();
+        settings.editor_mode = match list.selected() {
+            1 => "WINDOW".to_string(),
+            _ => "FULLSCREEN".to_string(),
+        };
+        write_settings(&settings);
+    });
+    settings_grid.attach(&current_editor_mode, 0, 8, 1, 1);
+    settings_grid.attach(&editor_mode_list, 1, 8, 1, 1);
+
+    settings_grid.attach(&current_p_shortcut, 0, 9, 1, 1);
+    settings_grid.attach(&p_shortcut, 1, 9, 1, 1);
+
+    let include_cursor_check = CheckButton::with_label("Include mouse cursor");
+    include_cursor_check.set_active(json_data.include_cursor);
+    include_cursor_check.connect_toggled(move |check| {
+        let mut settings = retrieve_data_from_json();
+        settings.include_cursor = check.is_active();
+        write_settings(&settings);
+    });
+    settings_grid.attach(&include_cursor_check, 1, 10, 1, 1);
+
+    let click_ring_check = CheckButton::with_label("Highlight click");
+    click_ring_check.set_active(json_data.click_ring);
+    click_ring_check.connect_toggled(move |check| {
+        let mut settings = retrieve_data_from_json();
+        settings.click_ring = check.is_active();
+        write_settings(&settings);
+    });
+    settings_grid.attach(&click_ring_check, 1, 11, 1, 1);
+
+    // longest wait for the windows of the app to disappear before capturing
+    let current_settle_delay = build_label("Hide windows for up to (ms):".to_string());
+    let settle_delay_spin = SpinButton::with_range(0.0, 5000.0, 50.0);
+    settle_delay_spin.set_value(json_data.settle_delay_ms as f64);
+    settle_delay_spin.set_valign(Align::Center);
+    settle_delay_spin.connect_value_changed(move |spin| {
+        let mut settings = retrieve_data_from_json();
+        settings.settle_delay_ms = spin.value() as u64;
+        write_settings(&settings);
+    });
+    settings_grid.attach(&current_settle_delay, 0, 12, 1, 1);
+    settings_grid.attach(&settle_delay_spin, 1, 12, 1, 1);
+
+    let restore_main_check = CheckButton::with_label("Show main window after capture");
+    restore_main_check.set_active(json_data.restore_main_window);
+    restore_main_check.connect_toggled(move |check| {
+        let mut settings = retrieve_data_from_json();
+        settings.restore_main_window = check.is_active();
+        write_settings(&settings);
+    });
+    settings_grid.attach(&restore_main_check, 1, 13, 1, 1);
+
+    settings_grid.attach(&current_q_shortcut, 0, 14, 1, 1);
+    settings_grid.attach(&q_shortcut, 1, 14, 1, 1);
+
+    // the scrolling capture turns the wheel itself instead of waiting for the user
+    let auto_scroll_check = CheckButton::with_label("Auto-scroll scrolling captures");
+    auto_scroll_check.set_active(json_data.auto_scroll);
+    auto_scroll_check.connect_toggled(move |check| {
+        let mut settings = retrieve_data_from_json();
+        settings.auto_scroll = check.is_active();
+        write_settings(&settings);
+    });
+    settings_grid.attach(&auto_scroll_check, 1, 15, 1, 1);
+
     let settings_window = ApplicationWindow::builder()
         .title("Settings-PDS")
         .child(&settings_grid)
@@ -745,12 +4804,16 @@ fn build_change_settings_window() -> ApplicationWindow {
     let curr_u_label = build_label("Undo action".to_string());
     let curr_r_label = build_label("Redo action".to_string());
     let curr_c_label = build_label("Cancel action".to_string());
+    let curr_p_label = build_label("Pick colour".to_string());
+    let curr_q_label = build_label("Scan codes".to_string());
 
     change_settings_grid.attach(&curr_n_label, 0, 1, 1, 1);
     change_settings_grid.attach(&curr_s_label, 0, 2, 1, 1);
     change_settings_grid.attach(&curr_u_label, 0, 3, 1, 1);
     change_settings_grid.attach(&curr_r_label, 0, 4, 1, 1);
     change_settings_grid.attach(&curr_c_label, 0, 5, 1, 1);
+    change_settings_grid.attach(&curr_p_label, 0, 6, 1, 1);
+    change_settings_grid.attach(&curr_q_label, 0, 7, 1, 1);
 
     let modif_new_drop = build_dropdown(&["CTRL", "SHIFT", "ALT"]);
     let key_new_drop = build_dropdown(&[
@@ -777,6 +4840,16 @@ fn build_change_settings_window() -> ApplicationWindow {
         "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R",
         "S", "T", "U", "V", "W", "X", "Y", "Z",
     ]);
+    let modif_pick_drop = build_dropdown(&["CTRL", "SHIFT", "ALT"]);
+    let key_pick_drop: DropDown = build_dropdown(&[
+        "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R",
+        "S", "T", "U", "V", "W", "X", "Y", "Z",
+    ]);
+    let modif_scan_drop = build_dropdown(&["CTRL", "SHIFT", "ALT"]);
+    let key_scan_drop: DropDown = build_dropdown(&[
+        "A", "B", "C", "D", "E", "F", "G", "H", "I", "J", "K", "L", "M", "N", "O", "P", "Q", "R",
+        "S", "T", "U", "V", "W", "X", "Y", "Z",
+    ]);
 
     let modif_new_drop_clone = modif_new_drop.clone();
     let key_new_drop_clone = key_new_drop.clone();
@@ -788,6 +4861,10 @@ fn build_change_settings_window() -> ApplicationWindow {
     let key_redo_drop_clone = key_redo_drop.clone();
     let modif_cancel_drop_clone = modif_cancel_drop.clone();
     let key_cancel_drop_clone = key_cancel_drop.clone();
+    let modif_pick_drop_clone = modif_pick_drop.clone();
+    let key_pick_drop_clone = key_pick_drop.clone();
+    let modif_scan_drop_clone = modif_scan_drop.clone();
+    let key_scan_drop_clone = key_scan_drop.clone();
 
     let modif_new_index = json_modif_to_index(&json_data.new_shortcut_modif);
     let key_new_index = json_key_to_index(&json_data.new_shortcut_key);
@@ -799,6 +4876,10 @@ fn build_change_settings_window() -> ApplicationWindow {
     let key_redo_index = json_key_to_index(&json_data.redo_shortcut_key);
     let modif_cancel_index = json_modif_to_index(&json_data.cancel_shortcut_modif);
     let key_cancel_index = json_key_to_index(&json_data.cancel_shortcut_key);
+    let modif_pick_index = json_modif_to_index(&json_data.pick_shortcut_modif);
+    let key_pick_index = json_key_to_index(&json_data.pick_shortcut_key);
+    let modif_scan_index = json_modif_to_index(&json_data.scan_shortcut_modif);
+    let key_scan_index = json_key_to_index(&json_data.scan_shortcut_key);
 
     modif_new_drop.set_selected(modif_new_index);
     key_new_drop.set_selected(key_new_index);
@@ -810,6 +4891,10 @@ fn build_change_settings_window() -> ApplicationWindow {
     key_redo_drop.set_selected(key_redo_index);
     modif_cancel_drop.set_selected(modif_cancel_index);
     key_cancel_drop.set_selected(key_cancel_index);
+    modif_pick_drop.set_selected(modif_pick_index);
+    key_pick_drop.set_selected(key_pick_index);
+    modif_scan_drop.set_selected(modif_scan_index);
+    key_scan_drop.set_selected(key_scan_index);
 
     change_settings_grid.attach(&modif_new_drop, 2, 1, 1, 1);
     change_settings_grid.attach(&key_new_drop, 3, 1, 1, 1);
@@ -821,7 +4906,11 @@ fn build_change_settings_window() -> ApplicationWindow {
     change_settings_grid.attach(&key_redo_drop, 3, 4, 1, 1);
     change_settings_grid.attach(&modif_cancel_drop, 2, 5, 1, 1);
     change_settings_grid.attach(&key_cancel_drop, 3, 5, 1, 1);
-    change_settings_grid.attach(&button_save_changes, 2, 6, 1, 1);
+    change_settings_grid.attach(&modif_pick_drop, 2, 6, 1, 1);
+    change_settings_grid.attach(&key_pick_drop, 3, 6, 1, 1);
+    change_settings_grid.attach(&modif_scan_drop, 2, 7, 1, 1);
+    change_settings_grid.attach(&key_scan_drop, 3, 7, 1, 1);
+    change_settings_grid.attach(&button_save_changes, 2, 8, 1, 1);
 
     let change_shortcut_window = ApplicationWindow::builder()
         .title("Change shortcut")
@@ -841,6 +4930,10 @@ fn build_change_settings_window() -> ApplicationWindow {
         let mr = modif_redo_drop_clone.selected();
         let mc = modif_cancel_drop_clone.selected();
         let kc = key_cancel_drop_clone.selected();
+        let mp = modif_pick_drop_clone.selected();
+        let kp = key_pick_drop_clone.selected();
+        let mq = modif_scan_drop_clone.selected();
+        let kq = key_scan_drop_clone.selected();
 
         if ((mn == ms) && (kn == ks))
             || ((mn == mu) && (kn == ku))
@@ -852,6 +4945,17 @@ fn build_change_settings_window() -> ApplicationWindow {
             || ((mc == ms) && (kc == ks))
             || ((mc == mu) && (kc == ku))
             || ((mc == mr) && (kc == kr))
+            || ((mp == mn) && (kp == kn))
+            || ((mp == ms) && (kp == ks))
+            || ((mp == mu) && (kp == ku))
+            || ((mp == mr) && (kp == kr))
+            || ((mp == mc) && (kp == kc))
+            || ((mq == mn) && (kq == kn))
+            || ((mq == ms) && (kq == ks))
+            || ((mq == mu) && (kq == ku))
+            || ((mq == mr) && (kq == kr))
+            || ((mq == mc) && (kq == kc))
+            || ((mq == mp) && (kq == kp))
         {
             let err_label = build_label("The shortcuts must be different!".to_string());
             change_settings_grid_clone.attach(&err_label, 0, 0, 4, 1);
@@ -866,7 +4970,10 @@ fn build_change_settings_window() -> ApplicationWindow {
             let kr_json = index_to_json_key(kr);
             let kc_json = index_to_json_key(kc);
             let mc_json = index_to_json_modif(mc);
-            let x = retrieve_data_from_json().default_location;
+            let mp_json = index_to_json_modif(mp);
+            let kp_json = index_to_json_key(kp);
+            let mq_json = index_to_json_modif(mq);
+            let kq_json = index_to_json_key(kq);
             let new_json = JSONStruct {
                 new_shortcut_modif: mn_json,
                 new_shortcut_key: kn_json,
@@ -878,7 +4985,11 @@ fn build_change_settings_window() -> ApplicationWindow {
                 redo_shortcut_key: kr_json,
                 cancel_shortcut_modif: mc_json,
                 cancel_shortcut_key: kc_json,
-                default_location: x,
+                pick_shortcut_modif: mp_json,
+                pick_shortcut_key: kp_json,
+                scan_shortcut_modif: mq_json,
+                scan_shortcut_key: kq_json,
+                ..retrieve_data_from_json()
             };
             let json_data = serde_json::to_string(&new_json).unwrap();
 
@@ -892,15 +5003,117 @@ fn build_change_settings_window() -> ApplicationWindow {
     change_shortcut_window
 }
 
+/* magnified pixel grid with a crosshair on the hovered pixel, followed by its
+coordinates and colour; placed below right of the pointer unless it would go out of the area */
+fn draw_loupe(cr: &cairo::Context, loupe: &Loupe, x: f64, y: f64, width: f64, height: f64) {
+    let side = loupe.side() as f64 * LOUPE_CELL;
+    let label_height = 56.0;
+    let gap = 20.0;
+    let left = if x + gap + side > width {
+        x - gap - side
+    } else {
+        x + gap
+    };
+    let top = if y + gap + side + label_height > height {
+        y - gap - side - label_height
+    } else {
+        y + gap
+    };
+
+    for row in 0..loupe.side() {
+        for column in 0..loupe.side() {
+            let cell_x = left + column as f64 * LOUPE_CELL;
+            let cell_y = top + row as f64 * LOUPE_CELL;
+            match loupe.cell(column, row) {
+                Some(pixel) => cr.set_source_rgb(
+                    pixel[0] as f64 / 255.0,
+                    pixel[1] as f64 / 255.0,
+                    pixel[2] as f64 / 255.0,
+                ),
+                // outside of the image
+                None => cr.set_source_rgb(0.3, 0.3, 0.3),
+            }
+            cr.rectangle(cell_x, cell_y, LOUPE_CELL, LOUPE_CELL);
+            let _ = cr.fill();
+        }
+    }
+
+    // pixel grid
+    cr.set_source_rgba(0.5, 0.5, 0.5, 0.5);
+    cr.set_line_width(1.0);
+    for index in 0..=loupe.side() {
+        let position = index as f64 * LOUPE_CELL + 0.5;
+        cr.move_to(left + position, top);
+        cr.line_to(left + position, top + side);
+        cr.move_to(left, top + position);
+        cr.line_to(left + side, top + position);
+    }
+    let _ = cr.stroke();
+
+    // crosshair through the hovered pixel, which is outlined
+    let center = loupe.radius as f64 * LOUPE_CELL;
+    cr.set_source_rgba(1.0, 0.0, 0.0, 0.6);
+    cr.move_to(left + center + LOUPE_CELL / 2.0, top);
+    cr.line_to(left + center + LOUPE_CELL / 2.0, top + side);
+    cr.move_to(left, top + center + LOUPE_CELL / 2.0);
+    cr.line_to(left + side, top + center + LOUPE_CELL / 2.0);
+    let _ = cr.stroke();
+    cr.set_source_rgb(1.0, 0.0, 0.0);
+    cr.set_line_width(2.0);
+    cr.rectangle(left + center, top + center, LOUPE_CELL, LOUPE_CELL);
+    let _ = cr.stroke();
+
+    cr.set_source_rgb(0.0, 0.0, 0.0);
+    cr.rectangle(left, top + side, side, label_height);
+    let _ = cr.fill();
+    cr.rectangle(left, top, side, side + label_height);
+    let _ = cr.stroke();
+
+    cr.set_source_rgb(1.0, 1.0, 1.0);
+    cr.select_font_face(
+        "monospace",
+        cairo::FontSlant::Normal,
+        cairo::FontWeight::Normal,
+    );
+    cr.set_font_size(11.0);
+    cr.move_to(left + 6.0, top + side + 16.0);
+    let _ = cr.show_text(&format!("x {}  y {}", loupe.x, loupe.y));
+    if let Some(pixel) = loupe.center() {
+        cr.move_to(left + 6.0, top + side + 32.0);
+        let _ = cr.show_text(&color::hex(pixel));
+        cr.move_to(left + 6.0, top + side + 48.0);
+        let _ = cr.show_text(&color::rgb(pixel));
+    }
+}
+
+/* rectangle of a window, in image pixels, as a selection */
+fn window_selection(window: Region) -> Coordinates {
+    Coordinates {
+        start_x: window.x as f64,
+        start_y: window.y as f64,
+        offset_x: window.width as f64,
+        offset_y: window.height as f64,
+    }
+}
+
 /* draw rectangle area to crop the screenshot */
-fn draw_area(window: &Window) -> (Arc<Mutex<Coordinates>>, Arc<Condvar>) {
+fn draw_area(
+    picture: &Picture,
+    panning: &Rc<Cell<bool>>,
+) -> (Arc<Mutex<Coordinates>>, Arc<Condvar>) {
     let draw_ctrl = GestureDrag::new();
     let coor: Arc<Mutex<Coordinates>> = Arc::new(Mutex::new(Coordinates::default()));
     let condvar = Arc::new(Condvar::new());
 
     let thread_condvar = Arc::clone(&condvar);
     let thread_coor_begin = Arc::clone(&coor);
-    draw_ctrl.connect_drag_begin(move |_, x, y| {
+    let panning_begin = Rc::clone(panning);
+    draw_ctrl.connect_drag_begin(move |gesture, x, y| {
+        if panning_begin.get() {
+            // the drag moves the view, see EditorView::connect_pan_gesture
+            gesture.set_state(EventSequenceState::Denied);
+            return;
+        }
         let mut coor = thread_coor_begin.lock().unwrap();
         coor.start_x = x;
         coor.start_y = y;
@@ -916,32 +5129,74 @@ fn draw_area(window: &Window) -> (Arc<Mutex<Coordinates>>, Arc<Condvar>) {
         thread_condvar.notify_one();
     });
 
-    window.child().unwrap().add_controller(draw_ctrl);
+    picture.add_controller(draw_ctrl);
     (coor, condvar)
 }
 
-fn create_starting_tmp_path_file() -> PathBuf {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(TMP_FOLDER_NAME);
-    std::fs::create_dir_all(&path).unwrap();
-    path.push(TMP_IMAGE_NAME.to_owned() + "0");
-    path.set_extension(TMP_IMAGE_EXTENSION);
+/* first step of the timeline of the session-th capture, see session_path */
+fn create_starting_tmp_path_file(session: u32) -> PathBuf {
+    let path = session_path(&tmp_folder(), session);
+    std::fs::create_dir_all(path.parent().unwrap()).unwrap();
     path
 }
 
-/* copies the new screenshot to clipboard */
-fn set_image_to_clipboard(path: &PathBuf) {
-    let result = Clipboard::new();
+/* current value of the copy policy in the settings */
+fn copy_policy() -> CopyPolicy {
+    CopyPolicy::from_json(&retrieve_data_from_json().copy_policy)
+}
+
+/* copies a new timeline step, only if the policy asks for it */
+fn copy_step_to_clipboard(output: impl FnOnce() -> PathBuf, copy_format: u32) {
+    // the image to copy, with the cursor composed over it, is only made when it is copied
+    if copy_policy() == CopyPolicy::EveryStep {
+        copy_to_clipboard(&output(), None, copy_format);
+    }
+}
+
+/* copies the image to clipboard in the flavour chosen in the "copy as" dropdown */
+fn copy_to_clipboard(path: &Path, saved_path: Option<&Path>, copy_format: u32) {
+    let mut format = CopyFormat::from_index(copy_format);
+    if format.needs_saved_file() && saved_path.is_none() {
+        // nothing has been saved yet, the image itself is the best we can offer
+        format = CopyFormat::Png;
+    }
+    let payload = match open(path) {
+        Ok(img) => ClipboardPayload::new(&img, saved_path),
+        Err(error) => Err(error.to_string()),
+    };
+    let result = payload.and_then(|payload| set_clipboard(payload, format));
     match result {
-        Ok(value) => {
-            let mut ctx = value;
-            let img = open(path.as_path()).unwrap();
-            let img_data = ImageData {
-                width: img.width() as usize,
-                height: img.height() as usize,
-                bytes: img.to_rgba8().into_raw().into(),
-            };
-            let result = ctx.set_image(img_data);
+        Ok(_) => {}
+        Err(error) => {
+            eprintln!("{}", error);
+        }
+    }
+}
+
+/* offers all the flavours of the payload at once, each under its own MIME type */
+fn set_clipboard(payload: ClipboardPayload, format: CopyFormat) -> Result<(), String> {
+    let display = gdk::Display::default().ok_or("No display to copy to".to_string())?;
+    let providers: Vec<gdk::ContentProvider> = payload
+        .into_targets(format)
+        .into_iter()
+        .map(|(mime_type, content)| {
+            gdk::ContentProvider::for_bytes(mime_type, &glib::Bytes::from_owned(content))
+        })
+        .collect();
+    display
+        .clipboard()
+        .set_content(Some(&gdk::ContentProvider::new_union(&providers)))
+        .map_err(|error| error.to_string())
+}
+
+/* the clipboard content set by this application is served by it, so the clipboard manager
+is asked to keep a copy before the application quits */
+fn store_clipboard() {
+    if let Some(display) = gdk::Display::default() {
+        let clipboard = display.clipboard();
+        if clipboard.is_local() {
+            let result = glib::MainContext::default()
+                .block_on(clipboard.store_future(glib::Priority::DEFAULT));
             match result {
                 Ok(_) => {}
                 Err(error) => {
@@ -949,13 +5204,52 @@ fn set_image_to_clipboard(path: &PathBuf) {
                 }
             }
         }
-        Err(error) => {
-            eprintln!("{}", error);
-        }
     }
 }
 
 fn choose_path(extension: &str) -> Option<PathBuf> {
+    let mut default_path = dated_save_path(extension);
+    let filename = default_path.file_name().unwrap().to_os_string();
+    default_path.pop();
+    FileDialog::new()
+        .set_location(default_path.as_path())
+        .set_filename(&filename.to_string_lossy())
+        .show_save_single_file()
+        .unwrap()
+}
+
+/* where Save puts the image without asking, see dated_save_path; an error when the default
+location is the tmp folder, which is removed at exit */
+fn default_save_path(extension: &str) -> Result<PathBuf, String> {
+    let path = dated_save_path(extension);
+    if let Some(folder) = path.parent() {
+        check_save_folder(folder)?;
+    }
+    Ok(path)
+}
+
+/* an error when the default location is the tmp folder, checked before saving many captures
+in it */
+fn check_default_location() -> Result<(), String> {
+    let mut folder = std::env::current_dir().unwrap();
+    folder.push(retrieve_data_from_json().default_location);
+    check_save_folder(&folder)
+}
+
+/* an error when folder is the tmp folder or inside it */
+fn check_save_folder(folder: &Path) -> Result<(), String> {
+    if in_tmp_folder(folder) {
+        Err(format!(
+            "{} is removed at exit, choose another default location in the settings",
+            folder.display()
+        ))
+    } else {
+        Ok(())
+    }
+}
+
+/* default location plus a file name made from the current date and time */
+fn dated_save_path(extension: &str) -> PathBuf {
     let mut default_path = std::env::current_dir().unwrap();
     default_path.push(retrieve_data_from_json().default_location);
     std::fs::create_dir_all(&default_path).unwrap();
@@ -973,11 +5267,26 @@ fn choose_path(extension: &str) -> Option<PathBuf> {
         + "_"
         + &current_datetime.second().to_string()
         + extension;
-    FileDialog::new()
+    default_path.push(filename);
+    // captures saved together in the same second get numbered names
+    free_path(&default_path)
+}
+
+/* asks for an existing image to edit */
+fn choose_image_to_open() -> Option<PathBuf> {
+    let mut default_path = std::env::current_dir().unwrap();
+    default_path.push(retrieve_data_from_json().default_location);
+    let result = FileDialog::new()
         .set_location(default_path.as_path())
-        .set_filename(&filename)
-        .show_save_single_file()
-        .unwrap()
+        .add_filter("Images", &["png", "jpg", "jpeg", "gif"])
+        .show_open_single_file();
+    match result {
+        Ok(path) => path,
+        Err(error) => {
+            eprintln!("{}", error);
+            None
+        }
+    }
 }
 
 fn json_modif_to_index(modif: &str) -> u32 {
@@ -1071,51 +5380,26 @@ fn retrieve_data_from_json() -> JSONStruct {
     file.read_to_string(&mut file_contents)
         .expect("Failed to read file");
 
-    // Parse JSON
+    // Parse JSON, missing fields take their default value
     let parsed_data: Result<JSONStruct, serde_json::Error> = serde_json::from_str(&file_contents);
-    let mut nm = "CONTROL".to_string();
-    let mut nk = "N".to_string();
-    let mut sm = "CONTROL".to_string();
-    let mut sk = "S".to_string();
-    let mut um = "CONTROL".to_string();
-    let mut uk = "Z".to_string();
-    let mut rm = "CONTROL".to_string();
-    let mut rk = "Y".to_string();
-    let mut cm = "CONTROL".to_string();
-    let mut ck = "E".to_string();
-    let mut dl = "/.".to_string();
     match parsed_data {
-        Ok(data) => {
-            // Access the values from the parsed data
-            nm = data.new_shortcut_modif;
-            nk = data.new_shortcut_key;
-            sm = data.save_shortcut_modif;
-            sk = data.save_shortcut_key;
-            um = data.undo_shortcut_modif;
-            uk = data.undo_shortcut_key;
-            rm = data.redo_shortcut_modif;
-            rk = data.redo_shortcut_key;
-            cm = data.cancel_shortcut_modif;
-            ck = data.cancel_shortcut_key;
-            dl = data.default_location;
-        }
+        Ok(data) => data,
         Err(e) => {
             eprintln!("Error parsing JSON: {}", e);
+            JSONStruct::default()
         }
     }
+}
 
-    JSONStruct {
-        new_shortcut_modif: nm,
-        new_shortcut_key: nk,
-        save_shortcut_modif: sm,
-        save_shortcut_key: sk,
-        undo_shortcut_modif: um,
-        undo_shortcut_key: uk,
-        redo_shortcut_modif: rm,
-        redo_shortcut_key: rk,
-        cancel_shortcut_modif: cm,
-        cancel_shortcut_key: ck,
-        default_location: dl,
+fn write_settings(settings: &JSONStruct) {
+    let json_data = serde_json::to_string(settings);
+    match json_data {
+        Ok(json_data) => {
+            let _ = std::fs::write(SETTINGS_FILENAME, json_data);
+        }
+        Err(err) => {
+            eprintln!("{}", err);
+        }
     }
 }
 
@@ -1165,36 +5449,33 @@ fn retrieve_hotkey(modif: &str, key: &str) -> Hotkey {
     hotkey
 }
 
+/* folder of the timeline steps of every session, removed by clean_tmp */
+fn tmp_folder() -> PathBuf {
+    let mut folder = std::env::current_dir().unwrap();
+    folder.push(SAVE_FOLDER_NAME);
+    folder.push(TMP_FOLDER_NAME);
+    folder
+}
+
+/* true when path is the tmp folder or inside it, where nothing saved would survive the exit */
+fn in_tmp_folder(path: &Path) -> bool {
+    match (fs::canonicalize(path), fs::canonicalize(tmp_folder())) {
+        (Ok(path), Ok(tmp)) => path.starts_with(tmp),
+        _ => false,
+    }
+}
+
 fn set_default_json() {
     let mut settings = retrieve_data_from_json();
     if settings.default_location.is_empty() {
         let mut path = std::env::current_dir().unwrap();
-        path.push(TMP_FOLDER_NAME);
+        path.push(SAVE_FOLDER_NAME);
         settings.default_location = path.into_os_string().into_string().unwrap();
-        let json_data = serde_json::to_string(&settings);
-        match json_data {
-            Ok(json_data) => {
-                let _ = std::fs::write(SETTINGS_FILENAME, json_data);
-            }
-            Err(err) => {
-                eprintln!("{}", err);
-            }
-        }
+        write_settings(&settings);
     }
 }
 
-fn create_new_path(path: &PathBuf, index: u32) -> PathBuf {
-    let mut output = path.clone();
-    output.pop();
-    output.push(TMP_IMAGE_NAME.to_owned() + &index.to_string());
-    output.set_extension(TMP_IMAGE_EXTENSION);
-
-    output
-}
-
 fn clean_tmp() -> std::io::Result<()> {
-    let mut path = std::env::current_dir().unwrap();
-    path.push(TMP_FOLDER_NAME);
-    std::fs::remove_dir_all(path)?;
+    std::fs::remove_dir_all(tmp_folder())?;
     Ok(())
 }
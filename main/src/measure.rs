@@ -0,0 +1,125 @@
+use image::{Rgba, RgbaImage};
+
+/* segment drawn with the measurement tool, in image pixels; its bounding box
+gives the dimensions */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Measurement {
+    pub start_x: f64,
+    pub start_y: f64,
+    pub end_x: f64,
+    pub end_y: f64,
+}
+
+impl Measurement {
+    pub fn width(&self) -> f64 {
+        (self.end_x - self.start_x).abs()
+    }
+
+    pub fn height(&self) -> f64 {
+        (self.end_y - self.start_y).abs()
+    }
+
+    pub fn distance(&self) -> f64 {
+        self.width().hypot(self.height())
+    }
+
+    /* dimensions and distance in pixels and in logical points, a point being
+    scale_factor pixels of the captured screen */
+    pub fn describe(&self, scale_factor: f64) -> String {
+        let scale_factor = if scale_factor > 0.0 {
+            scale_factor
+        } else {
+            1.0
+        };
+        format!(
+            "{} × {} px, {:.1} px  |  {} × {} pt, {:.1} pt",
+            self.width().round(),
+            self.height().round(),
+            self.distance(),
+            (self.width() / scale_factor).round(),
+            (self.height() / scale_factor).round(),
+            self.distance() / scale_factor
+        )
+    }
+}
+
+/* moves a point to the nearest edge of the uniform-colour region it lies in, on each axis
+separately, if that edge is at most max_distance pixels away; edges are between pixels, so
+the result is a whole number. Channels differing by at most tolerance count as the same colour */
+pub fn snap_to_edges(
+    image: &RgbaImage,
+    x: f64,
+    y: f64,
+    max_distance: u32,
+    tolerance: u8,
+) -> (f64, f64) {
+    if x < 0.0 || y < 0.0 || x >= image.width() as f64 || y >= image.height() as f64 {
+        return (x, y);
+    }
+    let pixel_x = x as u32;
+    let pixel_y = y as u32;
+    let color = *image.get_pixel(pixel_x, pixel_y);
+    let same = |other: &Rgba<u8>| {
+        color
+            .0
+            .iter()
+            .zip(other.0.iter())
+            .all(|(a, b)| a.abs_diff(*b) <= tolerance)
+    };
+
+    let left = run_length(image, pixel_x, pixel_y, -1, 0, max_distance, &same)
+        .map(|length| (pixel_x - length) as f64);
+    let right = run_length(image, pixel_x, pixel_y, 1, 0, max_distance, &same)
+        .map(|length| (pixel_x + length + 1) as f64);
+    let top = run_length(image, pixel_x, pixel_y, 0, -1, max_distance, &same)
+        .map(|length| (pixel_y - length) as f64);
+    let bottom = run_length(image, pixel_x, pixel_y, 0, 1, max_distance, &same)
+        .map(|length| (pixel_y + length + 1) as f64);
+    (
+        nearest_edge(x, left, right, max_distance as f64),
+        nearest_edge(y, top, bottom, max_distance as f64),
+    )
+}
+
+/* pixels of the same colour following (x, y) in direction (dx, dy) before the region
+ends, None if it goes on for more than max_distance pixels */
+fn run_length(
+    image: &RgbaImage,
+    x: u32,
+    y: u32,
+    dx: i64,
+    dy: i64,
+    max_distance: u32,
+    same: &dyn Fn(&Rgba<u8>) -> bool,
+) -> Option<u32> {
+    let mut length = 0;
+    loop {
+        let next_x = x as i64 + dx * (length as i64 + 1);
+        let next_y = y as i64 + dy * (length as i64 + 1);
+        if next_x < 0
+            || next_y < 0
+            || next_x >= image.width() as i64
+            || next_y >= image.height() as i64
+            || !same(image.get_pixel(next_x as u32, next_y as u32))
+        {
+            return Some(length);
+        }
+        length += 1;
+        if length > max_distance {
+            return None;
+        }
+    }
+}
+
+/* closer of the two edges found, if within max_distance */
+fn nearest_edge(value: f64, low: Option<f64>, high: Option<f64>, max_distance: f64) -> f64 {
+    let to_low = low.map(|low| value - low).unwrap_or(f64::INFINITY);
+    let to_high = high.map(|high| high - value).unwrap_or(f64::INFINITY);
+    if to_low.min(to_high) > max_distance {
+        value
+    } else if to_low <= to_high {
+        value - to_low
+    } else {
+        value + to_high
+    }
+}
@@ -0,0 +1,151 @@
+use crate::timeline::Coordinates;
+use image::imageops::FilterType;
+use image::DynamicImage;
+use std::io::ErrorKind;
+use std::process::Command;
+
+const TESSERACT: &str = "tesseract";
+// screen text is small for Tesseract, the image is enlarged before recognising it
+const OCR_SCALE: u32 = 2;
+
+/* word found by Tesseract, with its box in pixels of the image */
+#[derive(Clone, Debug, PartialEq)]
+pub struct OcrWord {
+    pub text: String,
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub confidence: f32,
+    // block, paragraph and line the word belongs to
+    pub block: u32,
+    pub paragraph: u32,
+    pub line: u32,
+}
+
+/* words of the TSV output of Tesseract, in reading order; rows that are not words
+(pages, blocks, lines, empty text) are skipped */
+pub fn parse_tsv(tsv: &str) -> Vec<OcrWord> {
+    tsv.lines()
+        .filter_map(|row| {
+            let columns: Vec<&str> = row.split('\t').collect();
+            if columns.len() < 12 || columns[0] != "5" {
+                return None;
+            }
+            let number = |index: usize| columns[index].trim().parse::<u32>().ok();
+            let text = columns[11..].join("\t").trim().to_string();
+            if text.is_empty() {
+                return None;
+            }
+            Some(OcrWord {
+                text,
+                left: number(6)?,
+                top: number(7)?,
+                width: number(8)?,
+                height: number(9)?,
+                confidence: columns[10].trim().parse().ok()?,
+                block: number(2)?,
+                paragraph: number(3)?,
+                line: number(4)?,
+            })
+        })
+        .collect()
+}
+
+/* words joined in lines, with an empty line between paragraphs */
+pub fn words_to_text(words: &[OcrWord]) -> String {
+    let mut text = String::new();
+    let mut previous: Option<&OcrWord> = None;
+    for word in words {
+        if let Some(previous) = previous {
+            if (previous.block, previous.paragraph) != (word.block, word.paragraph) {
+                text.push_str("\n\n");
+            } else if previous.line != word.line {
+                text.push('\n');
+            } else {
+                text.push(' ');
+            }
+        }
+        text.push_str(&word.text);
+        previous = Some(word);
+    }
+    text
+}
+
+/* runs the tesseract executable on the image; language is a Tesseract language code, e.g.
+eng or ita+eng */
+pub fn recognize(image: &DynamicImage, language: &str) -> Result<Vec<OcrWord>, String> {
+    let mut path = std::env::temp_dir();
+    path.push(format!("screen-pds-ocr-{}.png", std::process::id()));
+    image
+        .resize(
+            image.width() * OCR_SCALE,
+            image.height() * OCR_SCALE,
+            FilterType::CatmullRom,
+        )
+        .save(&path)
+        .map_err(|error| error.to_string())?;
+    let output = Command::new(TESSERACT)
+        .arg(&path)
+        .arg("stdout")
+        .arg("-l")
+        .arg(language)
+        .arg("tsv")
+        .output();
+    let _ = std::fs::remove_file(&path);
+    let output = output.map_err(|error| match error.kind() {
+        ErrorKind::NotFound => "Tesseract is not installed, see the README".to_string(),
+        _ => error.to_string(),
+    })?;
+    if !output.status.success() {
+        return Err(String::from_utf8_lossy(&output.stderr).trim().to_string());
+    }
+    let mut words = parse_tsv(&String::from_utf8_lossy(&output.stdout));
+    for word in &mut words {
+        word.left /= OCR_SCALE;
+        word.top /= OCR_SCALE;
+        word.width = (word.width as f64 / OCR_SCALE as f64).ceil() as u32;
+        word.height = (word.height as f64 / OCR_SCALE as f64).ceil() as u32;
+    }
+    Ok(words)
+}
+
+/* recognises the part of the image inside the rectangle, or all of it; the boxes of the
+words are in pixels of the whole image */
+pub fn recognize_area(
+    image: &DynamicImage,
+    area: Option<&Coordinates>,
+    language: &str,
+) -> Result<Vec<OcrWord>, String> {
+    let (left, top, width, height) = area_pixels(image, area);
+    if width == 0 || height == 0 {
+        return Err("The area to read is empty".to_string());
+    }
+    let mut words = recognize(&image.crop_imm(left, top, width, height), language)?;
+    for word in &mut words {
+        word.left += left;
+        word.top += top;
+    }
+    Ok(words)
+}
+
+/* left, top, width and height in whole pixels of the part of the rectangle inside the
+image, or of the whole image */
+pub fn area_pixels(image: &DynamicImage, area: Option<&Coordinates>) -> (u32, u32, u32, u32) {
+    match area {
+        Some(coor) => {
+            let coor = coor.normalized();
+            let left = coor.start_x.clamp(0.0, image.width() as f64) as u32;
+            let top = coor.start_y.clamp(0.0, image.height() as f64) as u32;
+            let right = (coor.start_x + coor.offset_x).clamp(0.0, image.width() as f64) as u32;
+            let bottom = (coor.start_y + coor.offset_y).clamp(0.0, image.height() as f64) as u32;
+            (
+                left,
+                top,
+                right.saturating_sub(left),
+                bottom.saturating_sub(top),
+            )
+        }
+        None => (0, 0, image.width(), image.height()),
+    }
+}
@@ -0,0 +1,171 @@
+use crate::ocr::OcrWord;
+use image::imageops::{self, FilterType};
+use image::{DynamicImage, Rgba};
+use regex::Regex;
+use serde::{Deserialize, Serialize};
+
+// margin left around the redacted text, in pixels
+const REDACT_PADDING: u32 = 2;
+// blurred boxes are shrunk to this fraction of their size and stretched back, so that
+// nothing of the text survives
+const BLUR_FACTOR: u32 = 8;
+
+/* named regular expression looked for in the recognised text, kept in the settings */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct RedactPattern {
+    pub name: String,
+    pub regex: String,
+}
+
+pub fn default_redact_patterns() -> Vec<RedactPattern> {
+    [
+        ("Email", r"[A-Za-z0-9._%+-]+@[A-Za-z0-9.-]+\.[A-Za-z]{2,}"),
+        ("IP address", r"\b(?:\d{1,3}\.){3}\d{1,3}\b"),
+        (
+            "API key",
+            r"\b(?:sk|pk|rk)_(?:live|test)_[A-Za-z0-9]{10,}|\bgh[pousr]_[A-Za-z0-9]{20,}|\bAKIA[A-Z0-9]{16}\b|\b[A-Za-z0-9_-]{32,}",
+        ),
+        ("Card number", r"\b(?:\d[ -]?){12,18}\d\b"),
+    ]
+    .iter()
+    .map(|(name, regex)| RedactPattern {
+        name: name.to_string(),
+        regex: regex.to_string(),
+    })
+    .collect()
+}
+
+/* text matched by a pattern and the box covering its words, in pixels of the image;
+only enabled detections are redacted */
+#[derive(Clone, Debug, PartialEq)]
+pub struct Detection {
+    pub pattern: String,
+    pub text: String,
+    pub left: u32,
+    pub top: u32,
+    pub width: u32,
+    pub height: u32,
+    pub enabled: bool,
+}
+
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum RedactStyle {
+    Solid,
+    Blur,
+}
+
+/* matches of the patterns in every recognised line; a match spanning several words (a card
+number written in groups) gets a single box. The same box is reported once */
+pub fn find_sensitive(
+    words: &[OcrWord],
+    patterns: &[RedactPattern],
+) -> Result<Vec<Detection>, String> {
+    let regexes = patterns
+        .iter()
+        .map(|pattern| {
+            Regex::new(&pattern.regex)
+                .map(|regex| (pattern.name.as_str(), regex))
+                .map_err(|error| format!("Invalid pattern {}: {}", pattern.name, error))
+        })
+        .collect::<Result<Vec<_>, String>>()?;
+
+    let mut detections: Vec<Detection> = Vec::new();
+    let mut line_start = 0;
+    while line_start < words.len() {
+        let first = &words[line_start];
+        let line_length = words[line_start..]
+            .iter()
+            .take_while(|word| {
+                (word.block, word.paragraph, word.line)
+                    == (first.block, first.paragraph, first.line)
+            })
+            .count();
+        let line = &words[line_start..line_start + line_length];
+        line_start += line_length;
+        // the line as text, with the span of every word in it
+        let mut text = String::new();
+        let mut spans = Vec::new();
+        for word in line {
+            if !text.is_empty() {
+                text.push(' ');
+            }
+            spans.push((text.len(), text.len() + word.text.len()));
+            text.push_str(&word.text);
+        }
+        for (name, regex) in &regexes {
+            for found in regex.find_iter(&text) {
+                let covered: Vec<&OcrWord> = line
+                    .iter()
+                    .zip(&spans)
+                    .filter(|(_, (start, end))| *start < found.end() && found.start() < *end)
+                    .map(|(word, _)| word)
+                    .collect();
+                let left = covered.iter().map(|word| word.left).min().unwrap_or(0);
+                let top = covered.iter().map(|word| word.top).min().unwrap_or(0);
+                let right = covered
+                    .iter()
+                    .map(|word| word.left + word.width)
+                    .max()
+                    .unwrap_or(0);
+                let bottom = covered
+                    .iter()
+                    .map(|word| word.top + word.height)
+                    .max()
+                    .unwrap_or(0);
+                let detection = Detection {
+                    pattern: name.to_string(),
+                    text: found.as_str().to_string(),
+                    left,
+                    top,
+                    width: right - left,
+                    height: bottom - top,
+                    enabled: true,
+                };
+                let known = detections.iter().any(|other| {
+                    (other.left, other.top, other.width, other.height)
+                        == (left, top, detection.width, detection.height)
+                });
+                if !covered.is_empty() && !known {
+                    detections.push(detection);
+                }
+            }
+        }
+    }
+    Ok(detections)
+}
+
+/* image with the boxes of the enabled detections painted black or blurred beyond reading */
+pub fn redact(image: &DynamicImage, detections: &[Detection], style: RedactStyle) -> DynamicImage {
+    let mut redacted = image.to_rgba8();
+    for detection in detections.iter().filter(|detection| detection.enabled) {
+        let left = detection.left.saturating_sub(REDACT_PADDING);
+        let top = detection.top.saturating_sub(REDACT_PADDING);
+        let right = (detection.left + detection.width + REDACT_PADDING).min(redacted.width());
+        let bottom = (detection.top + detection.height + REDACT_PADDING).min(redacted.height());
+        if left >= right || top >= bottom {
+            continue;
+        }
+        let (width, height) = (right - left, bottom - top);
+        match style {
+            RedactStyle::Solid => {
+                for y in top..bottom {
+                    for x in left..right {
+                        redacted.put_pixel(x, y, Rgba([0, 0, 0, 255]));
+                    }
+                }
+            }
+            RedactStyle::Blur => {
+                let area = imageops::crop_imm(&redacted, left, top, width, height).to_image();
+                let small = imageops::resize(
+                    &area,
+                    (width / BLUR_FACTOR).max(1),
+                    (height / BLUR_FACTOR).max(1),
+                    FilterType::Triangle,
+                );
+                let blurred = imageops::resize(&small, width, height, FilterType::Gaussian);
+                imageops::replace(&mut redacted, &blurred, left as i64, top as i64);
+            }
+        }
+    }
+    DynamicImage::ImageRgba8(redacted)
+}
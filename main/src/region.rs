@@ -0,0 +1,53 @@
+use crate::capture::{Region, ScreenInfo};
+use crate::timeline::Coordinates;
+use image::DynamicImage;
+use serde::{Deserialize, Serialize};
+
+/* region of the desktop saved under a name, e.g. "dashboard" */
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct NamedRegion {
+    pub name: String,
+    pub region: Region,
+}
+
+/* cuts the rectangle drawn on a frozen capture of the screen out of the capture itself, at
+its full resolution; also returns the region of the desktop it shows, so that it can be
+captured again later. None when the rectangle does not overlap the capture */
+pub fn cut_region(
+    capture: &DynamicImage,
+    screen: &ScreenInfo,
+    coor: &Coordinates,
+) -> Option<(DynamicImage, Region)> {
+    let local_coor = coor.normalized();
+    let left = local_coor.start_x.max(0.0).floor();
+    let top = local_coor.start_y.max(0.0).floor();
+    let right = (local_coor.start_x + local_coor.offset_x)
+        .min(capture.width() as f64)
+        .ceil();
+    let bottom = (local_coor.start_y + local_coor.offset_y)
+        .min(capture.height() as f64)
+        .ceil();
+    if right <= left || bottom <= top {
+        return None;
+    }
+    let (x, y) = (left as u32, top as u32);
+    let (width, height) = ((right - left) as u32, (bottom - top) as u32);
+    let image = capture.crop_imm(x, y, width, height);
+    Some((image, desktop_region(screen, x, y, width, height)))
+}
+
+/* region of the desktop shown by a rectangle of pixels of a capture of the screen: captures
+have scale_factor pixels per desktop unit */
+pub fn desktop_region(screen: &ScreenInfo, x: u32, y: u32, width: u32, height: u32) -> Region {
+    let scale_factor = if screen.scale_factor > 0.0 {
+        screen.scale_factor as f64
+    } else {
+        1.0
+    };
+    Region {
+        x: screen.x + (x as f64 / scale_factor).round() as i32,
+        y: screen.y + (y as f64 / scale_factor).round() as i32,
+        width: (width as f64 / scale_factor).round() as u32,
+        height: (height as f64 / scale_factor).round() as u32,
+    }
+}
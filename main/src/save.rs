@@ -0,0 +1,75 @@
+use image::{DynamicImage, ImageFormat, Rgb, RgbImage, Rgba};
+use std::fs::File;
+use std::path::{Path, PathBuf};
+
+/* extension (with the dot) of the format chosen in the dropdown */
+pub fn extension_for(format_index: u32) -> Option<&'static str> {
+    match format_index {
+        0 => Some(".png"),
+        1 => Some(".jpg"),
+        2 => Some(".gif"),
+        _ => None,
+    }
+}
+
+/* the path itself when no file is there, otherwise the first free one among "name (1).ext",
+"name (2).ext" and so on */
+pub fn free_path(path: &Path) -> PathBuf {
+    let stem = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().to_string())
+        .unwrap_or_default();
+    let extension = path
+        .extension()
+        .map(|extension| format!(".{}", extension.to_string_lossy()))
+        .unwrap_or_default();
+    let mut free = path.to_path_buf();
+    let mut copy = 0;
+    while free.exists() {
+        copy += 1;
+        free.set_file_name(format!("{} ({}){}", stem, copy, extension));
+    }
+    free
+}
+
+/* the image over an opaque matte colour, for the formats without transparency */
+pub fn flatten(image: &DynamicImage, matte: Rgba<u8>) -> RgbImage {
+    let rgba = image.to_rgba8();
+    RgbImage::from_fn(rgba.width(), rgba.height(), |x, y| {
+        let pixel = rgba.get_pixel(x, y);
+        let alpha = pixel[3] as u32;
+        Rgb([0, 1, 2].map(|channel| {
+            ((pixel[channel] as u32 * alpha + matte[channel] as u32 * (255 - alpha) + 127) / 255)
+                as u8
+        }))
+    })
+}
+
+/* encodes the image in the format chosen in the dropdown; JPG and GIF have no transparency,
+their transparent pixels get the matte colour */
+pub fn write_image(
+    image: &DynamicImage,
+    format_index: u32,
+    path: &Path,
+    matte: Rgba<u8>,
+) -> Result<(), String> {
+    match format_index {
+        0 => image
+            .save_with_format(path, ImageFormat::Png)
+            .map_err(|error| error.to_string()),
+        1 => DynamicImage::ImageRgb8(flatten(image, matte))
+            .save_with_format(path, ImageFormat::Jpeg)
+            .map_err(|error| error.to_string()),
+        2 => {
+            let pixels = flatten(image, matte).into_raw();
+            let frame = gif::Frame::from_rgb(image.width() as u16, image.height() as u16, &pixels);
+            let mut file = File::create(path).map_err(|error| error.to_string())?;
+            let mut encoder = gif::Encoder::new(&mut file, frame.width, frame.height, &[])
+                .map_err(|error| error.to_string())?;
+            encoder
+                .write_frame(&frame)
+                .map_err(|error| error.to_string())
+        }
+        _ => Err("Unknown format".to_string()),
+    }
+}
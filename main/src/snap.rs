@@ -0,0 +1,175 @@
+use crate::capture::{Region, ScreenInfo};
+use crate::timeline::Coordinates;
+use image::RgbaImage;
+
+/* window bounds from CaptureBackend::list_windows converted to pixels of a capture of the
+screen and cut to it; windows outside of the screen are dropped, the order is kept */
+pub fn windows_in_capture(windows: &[(u32, Region)], screen: &ScreenInfo) -> Vec<Region> {
+    let scale_factor = if screen.scale_factor > 0.0 {
+        screen.scale_factor as f64
+    } else {
+        1.0
+    };
+    let capture_width = screen.width as f64 * scale_factor;
+    let capture_height = screen.height as f64 * scale_factor;
+    windows
+        .iter()
+        .filter_map(|(_, region)| {
+            let left = ((region.x - screen.x) as f64 * scale_factor).max(0.0);
+            let top = ((region.y - screen.y) as f64 * scale_factor).max(0.0);
+            let right = ((region.x - screen.x) as f64 + region.width as f64) * scale_factor;
+            let bottom = ((region.y - screen.y) as f64 + region.height as f64) * scale_factor;
+            let right = right.min(capture_width);
+            let bottom = bottom.min(capture_height);
+            if right <= left || bottom <= top {
+                return None;
+            }
+            Some(Region {
+                x: left.round() as i32,
+                y: top.round() as i32,
+                width: (right - left).round() as u32,
+                height: (bottom - top).round() as u32,
+            })
+        })
+        .collect()
+}
+
+/* innermost window under a point, the windows being ordered as by list_windows */
+pub fn window_at(windows: &[Region], x: f64, y: f64) -> Option<Region> {
+    windows
+        .iter()
+        .find(|region| region.contains(x.floor() as i32, y.floor() as i32))
+        .copied()
+}
+
+/* moves the corners of a dragged rectangle, in image pixels, onto the nearest window border
+within max_distance pixels or, if there is none, onto the strongest edge of the image within
+the same distance, i.e. the line between two rows or columns whose average difference in
+brightness along the rectangle side is at least edge_threshold (0-255) */
+pub fn snap_rectangle(
+    image: Option<&RgbaImage>,
+    windows: &[Region],
+    coor: &Coordinates,
+    max_distance: f64,
+    edge_threshold: f64,
+) -> Coordinates {
+    let end_x = coor.start_x + coor.offset_x;
+    let end_y = coor.start_y + coor.offset_y;
+    let (top, bottom) = (coor.start_y.min(end_y), coor.start_y.max(end_y));
+    let (left, right) = (coor.start_x.min(end_x), coor.start_x.max(end_x));
+
+    let vertical_borders: Vec<f64> = windows
+        .iter()
+        .flat_map(|region| [region.x as f64, region.x as f64 + region.width as f64])
+        .collect();
+    let horizontal_borders: Vec<f64> = windows
+        .iter()
+        .flat_map(|region| [region.y as f64, region.y as f64 + region.height as f64])
+        .collect();
+
+    let snap_x = |value: f64| {
+        nearest_border(value, &vertical_borders, max_distance).unwrap_or_else(|| match image {
+            Some(image) => strongest_edge(value, max_distance, edge_threshold, |column| {
+                column_strength(image, column, top, bottom)
+            }),
+            None => value,
+        })
+    };
+    let snap_y = |value: f64| {
+        nearest_border(value, &horizontal_borders, max_distance).unwrap_or_else(|| match image {
+            Some(image) => strongest_edge(value, max_distance, edge_threshold, |row| {
+                row_strength(image, row, left, right)
+            }),
+            None => value,
+        })
+    };
+
+    let start_x = snap_x(coor.start_x);
+    let start_y = snap_y(coor.start_y);
+    Coordinates {
+        start_x,
+        start_y,
+        offset_x: snap_x(end_x) - start_x,
+        offset_y: snap_y(end_y) - start_y,
+    }
+}
+
+fn nearest_border(value: f64, borders: &[f64], max_distance: f64) -> Option<f64> {
+    borders
+        .iter()
+        .filter(|border| (*border - value).abs() <= max_distance)
+        .min_by(|a, b| (*a - value).abs().total_cmp(&(*b - value).abs()))
+        .copied()
+}
+
+/* line between pixels, within max_distance of value, with the highest strength */
+fn strongest_edge<F>(value: f64, max_distance: f64, threshold: f64, strength: F) -> f64
+where
+    F: Fn(i64) -> f64,
+{
+    let first = (value - max_distance).ceil() as i64;
+    let last = (value + max_distance).floor() as i64;
+    // (line, strength), ties go to the line closer to value
+    let mut best: Option<(f64, f64)> = None;
+    for line in first..=last {
+        let line_strength = strength(line);
+        if line_strength < threshold {
+            continue;
+        }
+        let better = match best {
+            None => true,
+            Some((best_line, best_strength)) => {
+                line_strength > best_strength
+                    || (line_strength == best_strength
+                        && (line as f64 - value).abs() < (best_line - value).abs())
+            }
+        };
+        if better {
+            best = Some((line as f64, line_strength));
+        }
+    }
+    best.map(|(line, _)| line).unwrap_or(value)
+}
+
+/* average brightness difference between columns column - 1 and column from top to bottom */
+fn column_strength(image: &RgbaImage, column: i64, top: f64, bottom: f64) -> f64 {
+    if column <= 0 || column >= image.width() as i64 {
+        return 0.0;
+    }
+    let first = top.max(0.0) as u32;
+    let last = bottom.min(image.height() as f64) as u32;
+    if last <= first {
+        return 0.0;
+    }
+    let total: f64 = (first..last)
+        .map(|row| {
+            (brightness(image, column as u32 - 1, row) - brightness(image, column as u32, row))
+                .abs()
+        })
+        .sum();
+    total / (last - first) as f64
+}
+
+/* average brightness difference between rows row - 1 and row from left to right */
+fn row_strength(image: &RgbaImage, row: i64, left: f64, right: f64) -> f64 {
+    if row <= 0 || row >= image.height() as i64 {
+        return 0.0;
+    }
+    let first = left.max(0.0) as u32;
+    let last = right.min(image.width() as f64) as u32;
+    if last <= first {
+        return 0.0;
+    }
+    let total: f64 = (first..last)
+        .map(|column| {
+            (brightness(image, column, row as u32 - 1) - brightness(image, column, row as u32))
+                .abs()
+        })
+        .sum();
+    total / (last - first) as f64
+}
+
+fn brightness(image: &RgbaImage, x: u32, y: u32) -> f64 {
+    let pixel = image.get_pixel(x, y);
+    0.299 * pixel[0] as f64 + 0.587 * pixel[1] as f64 + 0.114 * pixel[2] as f64
+}
@@ -0,0 +1,207 @@
+use crate::capture::{CaptureBackend, Region};
+use image::RgbaImage;
+use std::collections::hash_map::DefaultHasher;
+use std::hash::{Hash, Hasher};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread;
+use std::time::{Duration, Instant};
+
+// share of the overlapping rows that must be the same for two frames to be stitched, the
+// rest can differ (a blinking caret, an animation)
+const MIN_MATCH: f64 = 0.9;
+// notches of the wheel between two frames when scrolling automatically
+const AUTO_SCROLL_CLICKS: u32 = 3;
+
+/* how the content moved between two frames of the same size: header and footer rows stayed
+in place (toolbars, fixed headers), the rows between them moved up by rows */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct Scroll {
+    pub header: u32,
+    pub footer: u32,
+    pub rows: u32,
+}
+
+/* finds how far the content of previous moved up in next; rows is 0 when the frames are the
+same, None when they do not overlap enough (scrolled too far) */
+pub fn find_scroll(previous: &RgbaImage, next: &RgbaImage) -> Option<Scroll> {
+    let previous_rows = row_hashes(previous);
+    let next_rows = row_hashes(next);
+    let height = previous_rows.len();
+    let header = previous_rows
+        .iter()
+        .zip(next_rows.iter())
+        .take_while(|(a, b)| a == b)
+        .count();
+    if header == height {
+        return Some(Scroll {
+            header: 0,
+            footer: 0,
+            rows: 0,
+        });
+    }
+    let footer = previous_rows[header..]
+        .iter()
+        .rev()
+        .zip(next_rows[header..].iter().rev())
+        .take_while(|(a, b)| a == b)
+        .count();
+    let band = height - header - footer;
+    // the frames must share at least a quarter of the moving band
+    let min_overlap = (band / 4).max(1);
+
+    let mut best: Option<(usize, f64)> = None;
+    for rows in 1..=band - min_overlap {
+        let overlap = band - rows;
+        let matches = (0..overlap)
+            .filter(|row| previous_rows[header + rows + row] == next_rows[header + row])
+            .count();
+        let share = matches as f64 / overlap as f64;
+        let better = match best {
+            Some((_, best_share)) => share > best_share,
+            None => true,
+        };
+        if share >= MIN_MATCH && better {
+            best = Some((rows, share));
+        }
+    }
+    best.map(|(rows, _)| Scroll {
+        header: header as u32,
+        footer: footer as u32,
+        rows: rows as u32,
+    })
+}
+
+fn row_hashes(image: &RgbaImage) -> Vec<u64> {
+    image
+        .rows()
+        .map(|row| {
+            let mut hasher = DefaultHasher::new();
+            for pixel in row {
+                pixel.0.hash(&mut hasher);
+            }
+            hasher.finish()
+        })
+        .collect()
+}
+
+/* tall image made of frames captured while scrolling; it always ends with the bottom of the
+last frame */
+pub struct Stitcher {
+    image: RgbaImage,
+    previous: RgbaImage,
+}
+
+impl Stitcher {
+    pub fn new(first: RgbaImage) -> Stitcher {
+        Stitcher {
+            image: first.clone(),
+            previous: first,
+        }
+    }
+
+    /* adds the rows of the frame that were not on the previous one and returns how many */
+    pub fn push(&mut self, frame: RgbaImage) -> Result<u32, String> {
+        if frame.dimensions() != self.previous.dimensions() {
+            return Err("The frames have different sizes".to_string());
+        }
+        let scroll = find_scroll(&self.previous, &frame)
+            .ok_or("The frames do not overlap, scroll more slowly".to_string())?;
+        if scroll.rows == 0 {
+            return Ok(0);
+        }
+        // the footer moves to the new bottom, after the rows that came into view
+        let kept = self.image.height() - scroll.footer;
+        let added = scroll.rows + scroll.footer;
+        let mut image = RgbaImage::new(frame.width(), kept + added);
+        image::imageops::replace(
+            &mut image,
+            &image::imageops::crop_imm(&self.image, 0, 0, frame.width(), kept).to_image(),
+            0,
+            0,
+        );
+        image::imageops::replace(
+            &mut image,
+            &image::imageops::crop_imm(&frame, 0, frame.height() - added, frame.width(), added)
+                .to_image(),
+            0,
+            kept as i64,
+        );
+        self.image = image;
+        self.previous = frame;
+        Ok(scroll.rows)
+    }
+
+    pub fn height(&self) -> u32 {
+        self.image.height()
+    }
+
+    pub fn finish(self) -> RgbaImage {
+        self.image
+    }
+}
+
+/* how often frames are taken and when a scrolling capture ends */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ScrollOptions {
+    pub interval: Duration,
+    // the capture ends when nothing scrolled for this long
+    pub idle: Duration,
+    pub max_height: u32,
+    // scroll with synthetic wheel events instead of waiting for the user
+    pub auto_scroll: bool,
+}
+
+impl Default for ScrollOptions {
+    fn default() -> Self {
+        ScrollOptions {
+            interval: Duration::from_millis(250),
+            idle: Duration::from_secs(2),
+            max_height: 30_000,
+            auto_scroll: false,
+        }
+    }
+}
+
+/* image of a scrolling capture; error tells why the frames stopped being stitched before
+the end, the image then holds the ones stitched so far */
+#[derive(Clone, Debug, PartialEq)]
+pub struct ScrollCapture {
+    pub image: RgbaImage,
+    pub error: Option<String>,
+}
+
+/* captures the region again and again while its content scrolls and stitches the frames;
+it ends when the content stops moving, when the image reaches max_height or when stop is
+set, and early when a frame cannot be stitched (it moved too far, its size changed) */
+pub fn scrolling_capture(
+    backend: &dyn CaptureBackend,
+    region: Region,
+    options: &ScrollOptions,
+    stop: &AtomicBool,
+) -> Result<ScrollCapture, String> {
+    let mut stitcher = Stitcher::new(backend.capture_region(region)?);
+    let mut last_scroll = Instant::now();
+    let mut error = None;
+    while stitcher.height() < options.max_height && !stop.load(Ordering::Relaxed) {
+        if options.auto_scroll {
+            backend.scroll_down(region, AUTO_SCROLL_CLICKS)?;
+        }
+        thread::sleep(options.interval);
+        match stitcher.push(backend.capture_region(region)?) {
+            Ok(0) => {
+                if last_scroll.elapsed() >= options.idle {
+                    break;
+                }
+            }
+            Ok(_) => last_scroll = Instant::now(),
+            Err(stitch_error) => {
+                error = Some(stitch_error);
+                break;
+            }
+        }
+    }
+    Ok(ScrollCapture {
+        image: stitcher.finish(),
+        error,
+    })
+}
@@ -0,0 +1,238 @@
+use crate::trim::trim_bounds;
+use image::{open, DynamicImage};
+use std::path::{Path, PathBuf};
+
+pub const TMP_IMAGE_NAME: &str = "tmp";
+pub const TMP_IMAGE_EXTENSION: &str = "png";
+
+#[derive(Clone)]
+pub struct Coordinates {
+    pub start_x: f64,
+    pub start_y: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+impl Default for Coordinates {
+    fn default() -> Self {
+        Coordinates {
+            start_x: f64::NAN,
+            start_y: f64::NAN,
+            offset_x: f64::NAN,
+            offset_y: f64::NAN,
+        }
+    }
+}
+
+impl Coordinates {
+    /* true when a drag has ended with a non empty rectangle */
+    pub fn is_complete(&self) -> bool {
+        !self.start_x.is_nan()
+            && !self.offset_x.is_nan()
+            && (self.offset_x != 0.0 || self.offset_y != 0.0)
+    }
+
+    /* same rectangle with positive offsets */
+    pub fn normalized(&self) -> Coordinates {
+        let mut local_coor = self.clone();
+        if local_coor.offset_x.is_sign_negative() {
+            local_coor.start_x += local_coor.offset_x;
+            local_coor.offset_x = -local_coor.offset_x;
+        }
+        if local_coor.offset_y.is_sign_negative() {
+            local_coor.start_y += local_coor.offset_y;
+            local_coor.offset_y = -local_coor.offset_y;
+        }
+        local_coor
+    }
+
+    pub fn reset(&mut self) {
+        *self = Coordinates::default();
+    }
+}
+
+/* sequence of edited images stored as tmp0.png, tmp1.png, ... next to each other */
+pub struct Timeline {
+    base_path: PathBuf,
+    current_index: u32,
+    last_index: u32,
+    // top left corner of every step in pixels of the first one
+    origins: Vec<(f64, f64)>,
+    // steps resized, rotated or flipped, or made from one that was
+    transformed: Vec<bool>,
+}
+
+impl Timeline {
+    /* base_path is the path of the first step, see create_new_path */
+    pub fn new(base_path: PathBuf) -> Self {
+        Timeline {
+            base_path,
+            current_index: 0,
+            last_index: 0,
+            origins: vec![(0.0, 0.0)],
+            transformed: vec![false],
+        }
+    }
+
+    /* forgets the previous steps: the image at the first path becomes the only one */
+    pub fn restart(&mut self) {
+        self.current_index = 0;
+        self.last_index = 0;
+        self.origins = vec![(0.0, 0.0)];
+        self.transformed = vec![false];
+    }
+
+    /* starts a new timeline whose first step is the given image */
+    pub fn start(&mut self, image: &DynamicImage) -> Result<PathBuf, String> {
+        let first_path = self.first_path();
+        image.save(&first_path).map_err(|error| error.to_string())?;
+        self.restart();
+        Ok(first_path)
+    }
+
+    pub fn first_path(&self) -> PathBuf {
+        create_new_path(&self.base_path, 0)
+    }
+
+    pub fn current_path(&self) -> PathBuf {
+        create_new_path(&self.base_path, self.current_index)
+    }
+
+    pub fn current_index(&self) -> u32 {
+        self.current_index
+    }
+
+    pub fn last_index(&self) -> u32 {
+        self.last_index
+    }
+
+    /* files of all the steps, redo history included */
+    pub fn step_paths(&self) -> Vec<PathBuf> {
+        (0..=self.last_index)
+            .map(|index| create_new_path(&self.base_path, index))
+            .collect()
+    }
+
+    /* where the current step lies in the first one, moved by the crops made so far */
+    pub fn current_origin(&self) -> (f64, f64) {
+        self.origins[self.current_index as usize]
+    }
+
+    /* true when the pixels of the current step no longer line up with the first one, see
+    push_transformed */
+    pub fn is_transformed(&self) -> bool {
+        self.transformed[self.current_index as usize]
+    }
+
+    pub fn current_image(&self) -> Result<DynamicImage, String> {
+        open(self.current_path()).map_err(|error| error.to_string())
+    }
+
+    /* stores a new step after the current one, dropping the redo history */
+    pub fn push(&mut self, image: &DynamicImage) -> Result<PathBuf, String> {
+        let new_path = create_new_path(&self.base_path, self.current_index + 1);
+        image.save(&new_path).map_err(|error| error.to_string())?;
+        let origin = self.current_origin();
+        let transformed = self.is_transformed();
+        self.current_index += 1;
+        self.last_index = self.current_index;
+        self.origins.truncate(self.current_index as usize);
+        self.origins.push(origin);
+        self.transformed.truncate(self.current_index as usize);
+        self.transformed.push(transformed);
+        Ok(new_path)
+    }
+
+    /* stores a resized, rotated or flipped image as a new step: from it on the origins do not
+    place the steps in the first one any more */
+    pub fn push_transformed(&mut self, image: &DynamicImage) -> Result<PathBuf, String> {
+        let new_path = self.push(image)?;
+        self.transformed[self.current_index as usize] = true;
+        Ok(new_path)
+    }
+
+    /* crops the current image; None when the rectangle starts outside of it */
+    pub fn crop(&mut self, coor: &Coordinates) -> Option<PathBuf> {
+        let local_coor = coor.normalized();
+        let mut img = match self.current_image() {
+            Ok(img) => img,
+            Err(error) => {
+                eprintln!("{}", error);
+                return None;
+            }
+        };
+        if (local_coor.start_x as u32) < img.width() && (local_coor.start_y as u32) < img.height() {
+            let cropped = img.crop(
+                local_coor.start_x as u32,
+                local_coor.start_y as u32,
+                local_coor.offset_x as u32,
+                local_coor.offset_y as u32,
+            );
+            let (origin_x, origin_y) = self.current_origin();
+            match self.push(&cropped) {
+                Ok(path) => {
+                    self.origins[self.current_index as usize] = (
+                        origin_x + (local_coor.start_x as u32) as f64,
+                        origin_y + (local_coor.start_y as u32) as f64,
+                    );
+                    Some(path)
+                }
+                Err(error) => {
+                    eprintln!("{}", error);
+                    None
+                }
+            }
+        } else {
+            None
+        }
+    }
+
+    /* crops away the uniform borders of the current image, see trim_bounds */
+    pub fn trim(&mut self, tolerance: u8) -> Option<PathBuf> {
+        let img = match self.current_image() {
+            Ok(img) => img,
+            Err(error) => {
+                eprintln!("{}", error);
+                return None;
+            }
+        };
+        let bounds = trim_bounds(&img.to_rgba8(), tolerance)?;
+        self.crop(&bounds)
+    }
+
+    pub fn undo(&mut self) -> Option<PathBuf> {
+        if self.current_index > 0 {
+            self.current_index -= 1;
+            Some(self.current_path())
+        } else {
+            None
+        }
+    }
+
+    pub fn redo(&mut self) -> Option<PathBuf> {
+        if self.current_index < self.last_index {
+            self.current_index += 1;
+            Some(self.current_path())
+        } else {
+            None
+        }
+    }
+}
+
+/* path of the first step of the timeline of an open capture: every capture has its own
+folder inside folder, so that the steps of captures edited side by side stay apart */
+pub fn session_path(folder: &Path, session: u32) -> PathBuf {
+    let mut path = folder.join(format!("session{}", session));
+    path.push(TMP_IMAGE_NAME.to_owned() + "0");
+    path.set_extension(TMP_IMAGE_EXTENSION);
+    path
+}
+
+pub fn create_new_path(path: &Path, index: u32) -> PathBuf {
+    let mut output = path.to_path_buf();
+    output.pop();
+    output.push(TMP_IMAGE_NAME.to_owned() + &index.to_string());
+    output.set_extension(TMP_IMAGE_EXTENSION);
+
+    output
+}
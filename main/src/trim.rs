@@ -0,0 +1,67 @@
+use crate::timeline::Coordinates;
+use image::RgbaImage;
+
+/* rectangle left once the rows and columns of a single colour are removed from the edges,
+channels differing by at most tolerance counting as the same colour; sides are trimmed in
+turn, so strips of different colours (a band on top of a sidebar) go away too.
+None when there is nothing to trim or the image is of a single colour */
+pub fn trim_bounds(image: &RgbaImage, tolerance: u8) -> Option<Coordinates> {
+    let (width, height) = image.dimensions();
+    if width == 0 || height == 0 {
+        return None;
+    }
+    // a blank image has no content to keep
+    if uniform(
+        image,
+        tolerance,
+        image.enumerate_pixels().map(|(x, y, _)| (x, y)),
+    ) {
+        return None;
+    }
+
+    let (mut left, mut top, mut right, mut bottom) = (0, 0, width, height);
+    loop {
+        let before = (left, top, right, bottom);
+        if bottom - top > 1 && uniform(image, tolerance, (left..right).map(|x| (x, top))) {
+            top += 1;
+        }
+        if bottom - top > 1 && uniform(image, tolerance, (left..right).map(|x| (x, bottom - 1))) {
+            bottom -= 1;
+        }
+        if right - left > 1 && uniform(image, tolerance, (top..bottom).map(|y| (left, y))) {
+            left += 1;
+        }
+        if right - left > 1 && uniform(image, tolerance, (top..bottom).map(|y| (right - 1, y))) {
+            right -= 1;
+        }
+        if (left, top, right, bottom) == before {
+            break;
+        }
+    }
+
+    if (left, top, right, bottom) == (0, 0, width, height) {
+        return None;
+    }
+    Some(Coordinates {
+        start_x: left as f64,
+        start_y: top as f64,
+        offset_x: (right - left) as f64,
+        offset_y: (bottom - top) as f64,
+    })
+}
+
+/* true when the pixels at the given positions all have the colour of the first one */
+fn uniform(image: &RgbaImage, tolerance: u8, mut pixels: impl Iterator<Item = (u32, u32)>) -> bool {
+    let color = match pixels.next() {
+        Some((x, y)) => image.get_pixel(x, y),
+        None => return false,
+    };
+    pixels.all(|(x, y)| {
+        image
+            .get_pixel(x, y)
+            .0
+            .iter()
+            .zip(color.0.iter())
+            .all(|(a, b)| a.abs_diff(*b) <= tolerance)
+    })
+}
@@ -0,0 +1,74 @@
+use crate::timeline::Coordinates;
+
+/* maps points of the editor picture widget to pixels of the shown image */
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct ViewTransform {
+    pub scale: f64,
+    pub offset_x: f64,
+    pub offset_y: f64,
+}
+
+impl ViewTransform {
+    /* same layout as ContentFit::ScaleDown: shrunk to fit, never enlarged, centered */
+    pub fn scale_down(
+        widget_width: f64,
+        widget_height: f64,
+        image_width: f64,
+        image_height: f64,
+    ) -> Self {
+        if widget_width <= 0.0 || widget_height <= 0.0 || image_width <= 0.0 || image_height <= 0.0
+        {
+            return ViewTransform::identity();
+        }
+        let scale = (widget_width / image_width)
+            .min(widget_height / image_height)
+            .min(1.0);
+        ViewTransform {
+            scale,
+            offset_x: (widget_width - image_width * scale) / 2.0,
+            offset_y: (widget_height - image_height * scale) / 2.0,
+        }
+    }
+
+    /* picture drawn at the given zoom from its top left corner */
+    pub fn zoom(scale: f64) -> Self {
+        ViewTransform {
+            scale,
+            offset_x: 0.0,
+            offset_y: 0.0,
+        }
+    }
+
+    pub fn identity() -> Self {
+        ViewTransform {
+            scale: 1.0,
+            offset_x: 0.0,
+            offset_y: 0.0,
+        }
+    }
+
+    pub fn to_image(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            (x - self.offset_x) / self.scale,
+            (y - self.offset_y) / self.scale,
+        )
+    }
+
+    pub fn to_widget(&self, x: f64, y: f64) -> (f64, f64) {
+        (
+            x * self.scale + self.offset_x,
+            y * self.scale + self.offset_y,
+        )
+    }
+
+    /* drag rectangle in widget points converted to image pixels */
+    pub fn to_image_coordinates(&self, coor: &Coordinates) -> Coordinates {
+        let (start_x, start_y) = self.to_image(coor.start_x, coor.start_y);
+        Coordinates {
+            start_x,
+            start_y,
+            offset_x: coor.offset_x / self.scale,
+            offset_y: coor.offset_y / self.scale,
+        }
+    }
+}
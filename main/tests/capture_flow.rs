@@ -0,0 +1,1406 @@
+use image::{open, RgbaImage};
+use screen_pds::barcode::parse_zbar_xml;
+use screen_pds::capture::{
+    capture_fullscreen, capture_region_to, pattern_pixel, CaptureBackend, FakeBackend, HidingWait,
+    Region, ScreenInfo, SelfHiding, HIDDEN_POLL,
+};
+use screen_pds::cli::{
+    parse_args, parse_region, run, Command, Failure, DIFFERENCES_EXIT, ERROR_EXIT,
+};
+use screen_pds::clipboard::{
+    escape_html, file_uri, html_image, markdown_image, ClipboardPayload, CopyFormat, CopyPolicy,
+    HTML_TARGET, MARKDOWN_TARGET, PNG_TARGET, TEXT_TARGET, URI_LIST_TARGET,
+};
+use screen_pds::collage::{collage, square_columns, CollageLayout, CollageOptions};
+use screen_pds::color::{self, ColorFormat};
+use screen_pds::constraint::CropConstraint;
+use screen_pds::cursor::{from_premultiplied_argb, CursorImage, CursorLayer};
+use screen_pds::diff::{
+    align, aligned_pair, heatmap, onion_skin, score, side_by_side, DiffOptions,
+};
+use screen_pds::effects::{round_corners, Effects, WHITE};
+use screen_pds::geometry::{
+    rotate, rotate_quarter, target_size, GeometryEdit, MAX_DIMENSION, RESIZE_FILTERS,
+};
+use screen_pds::loupe::Loupe;
+use screen_pds::measure::{snap_to_edges, Measurement};
+use screen_pds::ocr::{parse_tsv, words_to_text};
+use screen_pds::redact::{
+    default_redact_patterns, find_sensitive, redact, RedactPattern, RedactStyle,
+};
+use screen_pds::region::{cut_region, desktop_region, NamedRegion};
+use screen_pds::save::{flatten, free_path, write_image};
+use screen_pds::snap::{snap_rectangle, window_at, windows_in_capture};
+use screen_pds::stitch::{
+    find_scroll, scrolling_capture, Scroll, ScrollCapture, ScrollOptions, Stitcher,
+};
+use screen_pds::timeline::{session_path, Coordinates, Timeline};
+use screen_pds::trim::trim_bounds;
+use screen_pds::view::ViewTransform;
+use std::cell::Cell;
+use std::path::{Path, PathBuf};
+use std::rc::Rc;
+use std::sync::atomic::AtomicBool;
+use std::time::{Duration, Instant};
+
+/* empty folder for the timeline of a single test */
+fn test_folder(name: &str) -> PathBuf {
+    let mut path = std::env::temp_dir();
+    path.push("screen-pds-tests");
+    path.push(name);
+    let _ = std::fs::remove_dir_all(&path);
+    std::fs::create_dir_all(&path).unwrap();
+    path
+}
+
+fn start_timeline(backend: &dyn CaptureBackend, name: &str) -> Timeline {
+    let mut path = test_folder(name);
+    path.push("tmp0.png");
+    let mut timeline = Timeline::new(path);
+    capture_fullscreen(backend, &timeline.first_path()).unwrap();
+    timeline.restart();
+    timeline
+}
+
+fn drag(start_x: f64, start_y: f64, offset_x: f64, offset_y: f64) -> Coordinates {
+    Coordinates {
+        start_x,
+        start_y,
+        offset_x,
+        offset_y,
+    }
+}
+
+#[test]
+fn new_crop_undo_redo_save() {
+    let backend = FakeBackend::from_pattern(320, 200);
+    let mut timeline = start_timeline(&backend, "new_crop_undo_redo_save");
+    assert_eq!(backend.capture_count(), 1);
+    assert_eq!(timeline.current_image().unwrap().width(), 320);
+
+    let first = timeline.crop(&drag(10.0, 20.0, 100.0, 50.0)).unwrap();
+    let img = open(&first).unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (100, 50));
+    assert_eq!(*img.get_pixel(0, 0), pattern_pixel(10, 20));
+
+    // dragging up and left selects the same kind of rectangle
+    let second = timeline.crop(&drag(40.0, 30.0, -20.0, -10.0)).unwrap();
+    let img = open(&second).unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (20, 10));
+    assert_eq!(*img.get_pixel(0, 0), pattern_pixel(30, 40));
+    assert_eq!(timeline.current_index(), 2);
+
+    assert_eq!(timeline.undo(), Some(first.clone()));
+    assert_eq!(timeline.undo(), Some(timeline.first_path()));
+    assert_eq!(timeline.undo(), None);
+    assert_eq!(timeline.redo(), Some(first));
+    assert_eq!(timeline.redo(), Some(second.clone()));
+    assert_eq!(timeline.redo(), None);
+
+    let mut out = test_folder("new_crop_undo_redo_save_out");
+    out.push("capture.png");
+    write_image(&timeline.current_image().unwrap(), 0, &out, WHITE).unwrap();
+    let saved = open(&out).unwrap().to_rgba8();
+    assert_eq!(saved, open(&second).unwrap().to_rgba8());
+}
+
+#[test]
+fn crop_after_undo_drops_redo_history() {
+    let backend = FakeBackend::from_pattern(64, 64);
+    let mut timeline = start_timeline(&backend, "crop_after_undo_drops_redo_history");
+    timeline.crop(&drag(0.0, 0.0, 32.0, 32.0)).unwrap();
+    timeline.crop(&drag(0.0, 0.0, 16.0, 16.0)).unwrap();
+    timeline.undo().unwrap();
+    timeline.crop(&drag(8.0, 8.0, 8.0, 8.0)).unwrap();
+    assert_eq!(timeline.current_index(), 2);
+    assert_eq!(timeline.last_index(), 2);
+    assert_eq!(timeline.redo(), None);
+    let img = timeline.current_image().unwrap().to_rgba8();
+    assert_eq!(*img.get_pixel(0, 0), pattern_pixel(8, 8));
+}
+
+#[test]
+fn crop_outside_the_image_is_ignored() {
+    let backend = FakeBackend::from_pattern(50, 50);
+    let mut timeline = start_timeline(&backend, "crop_outside_the_image_is_ignored");
+    assert_eq!(timeline.crop(&drag(60.0, 10.0, 10.0, 10.0)), None);
+    assert_eq!(timeline.current_index(), 0);
+}
+
+#[test]
+fn open_existing_image_and_crop() {
+    let folder = test_folder("open_existing_image_and_crop");
+    let source = folder.join("existing.jpg");
+    image::DynamicImage::ImageRgb8(image::RgbImage::from_pixel(80, 60, image::Rgb([200, 0, 0])))
+        .save(&source)
+        .unwrap();
+
+    let mut timeline = Timeline::new(folder.join("tmp0.png"));
+    let first = timeline.start(&open(&source).unwrap()).unwrap();
+    assert_eq!(first, timeline.first_path());
+    assert_eq!(timeline.current_index(), 0);
+
+    timeline.crop(&drag(10.0, 10.0, 20.0, 30.0)).unwrap();
+    assert_eq!(timeline.current_image().unwrap().height(), 30);
+    assert_eq!(timeline.undo(), Some(first));
+}
+
+#[test]
+fn crop_coordinates_follow_zoom() {
+    let backend = FakeBackend::from_pattern(400, 300);
+    let mut timeline = start_timeline(&backend, "crop_coordinates_follow_zoom");
+
+    // fitted in a 200x200 picture: half size, centered vertically
+    let fit = ViewTransform::scale_down(200.0, 200.0, 400.0, 300.0);
+    assert_eq!(fit.scale, 0.5);
+    let coor = fit.to_image_coordinates(&drag(10.0, 50.0, 20.0, 10.0));
+    timeline.crop(&coor).unwrap();
+    let img = timeline.current_image().unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (40, 20));
+    assert_eq!(*img.get_pixel(0, 0), pattern_pixel(20, 50));
+
+    // zoomed in four times on the cropped image
+    let zoomed = ViewTransform::zoom(4.0);
+    timeline
+        .crop(&zoomed.to_image_coordinates(&drag(40.0, 40.0, 40.0, 20.0)))
+        .unwrap();
+    let img = timeline.current_image().unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (10, 5));
+    assert_eq!(*img.get_pixel(0, 0), pattern_pixel(30, 60));
+    assert_eq!(zoomed.to_widget(10.0, 10.0), (40.0, 40.0));
+}
+
+#[test]
+fn save_in_every_format() {
+    let backend = FakeBackend::from_pattern(40, 30);
+    let timeline = start_timeline(&backend, "save_in_every_format");
+    let folder = test_folder("save_in_every_format_out");
+    for (index, name) in ["capture.png", "capture.jpg", "capture.gif"]
+        .iter()
+        .enumerate()
+    {
+        let path = folder.join(name);
+        write_image(
+            &timeline.current_image().unwrap(),
+            index as u32,
+            &path,
+            WHITE,
+        )
+        .unwrap();
+        assert_eq!(open(&path).unwrap().width(), 40);
+    }
+    assert!(write_image(
+        &timeline.current_image().unwrap(),
+        7,
+        &folder.join("x"),
+        WHITE
+    )
+    .is_err());
+}
+
+#[test]
+fn fake_from_file_screens_regions_and_windows() {
+    let folder = test_folder("fake_from_file");
+    let source = folder.join("screen.png");
+    RgbaImage::from_fn(30, 20, pattern_pixel)
+        .save(&source)
+        .unwrap();
+
+    let mut backend = FakeBackend::from_file(&source).unwrap();
+    let second = backend.add_screen(RgbaImage::from_pixel(10, 10, image::Rgba([1, 2, 3, 255])));
+    backend.add_window(
+        42,
+        Region {
+            x: 5,
+            y: 4,
+            width: 6,
+            height: 3,
+        },
+    );
+
+    let screens = backend.list_screens().unwrap();
+    assert_eq!(screens.len(), 2);
+    assert_eq!(screens[1].x, 30);
+    assert_eq!(
+        *backend.capture_screen(second).unwrap().get_pixel(0, 0),
+        image::Rgba([1, 2, 3, 255])
+    );
+
+    let window = backend.capture_window(42).unwrap();
+    assert_eq!(window.dimensions(), (6, 3));
+    assert_eq!(*window.get_pixel(0, 0), pattern_pixel(5, 4));
+
+    let region = backend
+        .capture_region(Region {
+            x: 32,
+            y: 1,
+            width: 2,
+            height: 2,
+        })
+        .unwrap();
+    assert_eq!(*region.get_pixel(0, 0), image::Rgba([1, 2, 3, 255]));
+    assert!(backend.capture_window(7).is_err());
+}
+
+#[test]
+fn loupe_samples_around_the_hovered_pixel() {
+    let image = RgbaImage::from_fn(20, 10, pattern_pixel);
+    let loupe = Loupe::sample(&image, 1, 9, 2);
+    assert_eq!(loupe.side(), 5);
+    assert_eq!(loupe.center(), Some(pattern_pixel(1, 9)));
+    assert_eq!(loupe.cell(0, 0), None);
+    assert_eq!(loupe.cell(1, 0), Some(pattern_pixel(0, 7)));
+    assert_eq!(loupe.cell(4, 2), Some(pattern_pixel(3, 9)));
+    assert_eq!(loupe.cell(2, 3), None);
+
+    let pixel = image::Rgba([255, 16, 0, 255]);
+    assert_eq!(color::hex(pixel), "#FF1000");
+    assert_eq!(color::rgb(pixel), "rgb(255, 16, 0)");
+}
+
+#[test]
+fn picked_colour_notations_and_history() {
+    let pixel = image::Rgba([51, 102, 153, 255]);
+    assert_eq!(ColorFormat::Hex.format(pixel), "#336699");
+    assert_eq!(ColorFormat::Rgb.format(pixel), "rgb(51, 102, 153)");
+    assert_eq!(ColorFormat::Hsl.format(pixel), "hsl(210, 50%, 40%)");
+    assert_eq!(
+        color::hsl(image::Rgba([255, 0, 0, 255])),
+        "hsl(0, 100%, 50%)"
+    );
+    assert_eq!(
+        color::hsl(image::Rgba([128, 128, 128, 255])),
+        "hsl(0, 0%, 50%)"
+    );
+    assert_eq!(
+        ColorFormat::from_json(&ColorFormat::Hsl.to_json()),
+        ColorFormat::Hsl
+    );
+
+    assert_eq!(color::parse_hex("#336699"), Some(pixel));
+    assert_eq!(color::parse_hex("336699"), None);
+    assert_eq!(color::parse_hex("#33669"), None);
+
+    let mut history = Vec::new();
+    for value in [1, 2, 3, 1] {
+        color::push_recent(&mut history, image::Rgba([value, 0, 0, 255]), 3);
+    }
+    assert_eq!(history, vec!["#010000", "#030000", "#020000"]);
+    color::push_recent(&mut history, pixel, 3);
+    assert_eq!(history, vec!["#336699", "#010000", "#030000"]);
+}
+
+#[test]
+fn measure_and_snap_to_uniform_regions() {
+    let measurement = Measurement {
+        start_x: 10.0,
+        start_y: 10.0,
+        end_x: 40.0,
+        end_y: 50.0,
+    };
+    assert_eq!((measurement.width(), measurement.height()), (30.0, 40.0));
+    assert_eq!(measurement.distance(), 50.0);
+    assert_eq!(
+        measurement.describe(2.0),
+        "30 × 40 px, 50.0 px  |  15 × 20 pt, 25.0 pt"
+    );
+
+    // white 100x100 with a black button from (20, 30) to (59, 49)
+    let mut image = RgbaImage::from_pixel(100, 100, image::Rgba([255, 255, 255, 255]));
+    for y in 30..50 {
+        for x in 20..60 {
+            image.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+        }
+    }
+    assert_eq!(snap_to_edges(&image, 22.4, 47.6, 5, 0), (20.0, 50.0));
+    // left of the button the column goes on, only x snaps
+    assert_eq!(snap_to_edges(&image, 17.5, 35.0, 5, 0), (20.0, 35.0));
+    // the middle of the button is too far from every edge
+    assert_eq!(snap_to_edges(&image, 40.5, 40.5, 5, 0), (40.5, 40.5));
+    // image borders are edges too
+    assert_eq!(snap_to_edges(&image, 98.5, 1.5, 5, 0), (100.0, 0.0));
+}
+
+#[test]
+fn crop_constraints_shape_the_dragged_rectangle() {
+    assert_eq!(
+        CropConstraint::parse("16:9"),
+        Some(CropConstraint::Ratio(16.0, 9.0))
+    );
+    assert_eq!(
+        CropConstraint::parse(" 1280 x 720 "),
+        Some(CropConstraint::Size(1280, 720))
+    );
+    assert_eq!(CropConstraint::parse("0:1"), None);
+    assert_eq!(CropConstraint::parse("wide"), None);
+    assert_eq!(CropConstraint::Ratio(4.0, 3.0).to_text(), "4:3");
+    assert_eq!(CropConstraint::Size(1280, 720).to_text(), "1280x720");
+
+    // the largest 1:1 square inside a drag up and left
+    let square = CropConstraint::Ratio(1.0, 1.0).apply(&drag(50.0, 50.0, -30.0, -20.0), 100, 100);
+    assert_eq!(
+        (
+            square.start_x,
+            square.start_y,
+            square.offset_x,
+            square.offset_y
+        ),
+        (50.0, 50.0, -20.0, -20.0)
+    );
+
+    // a fixed size near the right border is moved back inside the image
+    let backend = FakeBackend::from_pattern(100, 80);
+    let mut timeline = start_timeline(&backend, "crop_constraints_shape_the_dragged_rectangle");
+    let fixed = CropConstraint::Size(40, 30).apply(&drag(90.0, 10.0, 5.0, 5.0), 100, 80);
+    timeline.crop(&fixed).unwrap();
+    let img = timeline.current_image().unwrap().to_rgba8();
+    assert_eq!(img.dimensions(), (40, 30));
+    assert_eq!(*img.get_pixel(0, 0), pattern_pixel(60, 10));
+}
+
+#[test]
+fn region_cut_from_frozen_capture_and_repeated() {
+    let mut backend = FakeBackend::from_pattern(200, 100);
+    backend.add_screen(RgbaImage::from_fn(300, 100, pattern_pixel));
+    let folder = test_folder("region_cut_from_frozen_capture_and_repeated");
+    let frozen = folder.join("frozen.png");
+    let screen = capture_fullscreen(&backend, &frozen).unwrap();
+    assert_eq!(screen.x, 0);
+
+    // dragged up and left, partly outside of the capture
+    let capture = open(&frozen).unwrap();
+    let (image, region) = cut_region(&capture, &screen, &drag(30.0, 20.0, -40.0, 10.0)).unwrap();
+    assert_eq!(image.width(), 30);
+    assert_eq!(image.height(), 10);
+    assert_eq!(*image.to_rgba8().get_pixel(0, 0), pattern_pixel(0, 20));
+    assert_eq!(
+        region,
+        Region {
+            x: 0,
+            y: 20,
+            width: 30,
+            height: 10
+        }
+    );
+    assert!(cut_region(&capture, &screen, &drag(250.0, 10.0, 10.0, 10.0)).is_none());
+
+    // repeating the region captures the same pixels again
+    let repeated = folder.join("repeated.png");
+    capture_region_to(&backend, region, &repeated).unwrap();
+    assert_eq!(open(&repeated).unwrap().to_rgba8(), image.to_rgba8());
+
+    let saved = NamedRegion {
+        name: "dashboard".to_string(),
+        region,
+    };
+    let json = serde_json::to_string(&saved).unwrap();
+    assert_eq!(serde_json::from_str::<NamedRegion>(&json).unwrap(), saved);
+
+    // a capture at twice the scale shows half as many desktop units
+    let hidpi = ScreenInfo {
+        scale_factor: 2.0,
+        ..screen
+    };
+    assert_eq!(
+        desktop_region(&hidpi, 40, 20, 100, 50),
+        Region {
+            x: 20,
+            y: 10,
+            width: 50,
+            height: 25
+        }
+    );
+}
+
+#[test]
+fn selection_snaps_to_windows_and_image_edges() {
+    let mut backend = FakeBackend::from_pattern(200, 100);
+    backend.add_screen(RgbaImage::from_fn(200, 100, pattern_pixel));
+    // a dialog on the first screen with a button inside, and a window on the second screen
+    backend.add_window(
+        1,
+        Region {
+            x: 10,
+            y: 10,
+            width: 100,
+            height: 60,
+        },
+    );
+    backend.add_window(
+        2,
+        Region {
+            x: 20,
+            y: 40,
+            width: 30,
+            height: 20,
+        },
+    );
+    backend.add_window(
+        3,
+        Region {
+            x: 190,
+            y: 0,
+            width: 50,
+            height: 50,
+        },
+    );
+    let screen = backend.list_screens().unwrap()[0];
+    let windows = windows_in_capture(&backend.list_windows().unwrap(), &screen);
+    // the window across both screens is cut to the first one
+    assert_eq!(
+        windows[0],
+        Region {
+            x: 190,
+            y: 0,
+            width: 10,
+            height: 50
+        }
+    );
+    assert_eq!(window_at(&windows, 25.5, 45.0), Some(windows[1]));
+    assert_eq!(window_at(&windows, 80.0, 20.0), Some(windows[2]));
+    assert_eq!(window_at(&windows, 150.0, 90.0), None);
+
+    let snapped = snap_rectangle(None, &windows, &drag(13.0, 8.0, 94.0, 59.0), 4.0, 40.0);
+    assert_eq!(
+        (
+            snapped.start_x,
+            snapped.start_y,
+            snapped.offset_x,
+            snapped.offset_y
+        ),
+        (10.0, 10.0, 100.0, 60.0)
+    );
+
+    // white image with a black button from (20, 30) to (59, 49)
+    let mut image = RgbaImage::from_pixel(100, 100, image::Rgba([255, 255, 255, 255]));
+    for y in 30..50 {
+        for x in 20..60 {
+            image.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+        }
+    }
+    // dragged from the bottom right corner, a few pixels off
+    let snapped = snap_rectangle(
+        Some(&image),
+        &[],
+        &drag(61.5, 51.2, -43.2, -22.6),
+        4.0,
+        40.0,
+    );
+    assert_eq!(
+        (
+            snapped.start_x,
+            snapped.start_y,
+            snapped.offset_x,
+            snapped.offset_y
+        ),
+        (60.0, 50.0, -40.0, -20.0)
+    );
+    // nothing strong enough nearby: the rectangle is kept
+    let kept = snap_rectangle(Some(&image), &[], &drag(70.5, 60.5, 10.0, 10.0), 4.0, 40.0);
+    assert_eq!((kept.start_x, kept.offset_x), (70.5, 10.0));
+}
+
+#[test]
+fn auto_trim_removes_uniform_borders_as_a_step() {
+    // content inside a grey strip on the left, a slightly noisy band on top and white elsewhere
+    let mut image = RgbaImage::from_pixel(80, 60, image::Rgba([255, 255, 255, 255]));
+    for y in 0..60 {
+        for x in 0..5 {
+            image.put_pixel(x, y, image::Rgba([128, 128, 128, 255]));
+        }
+    }
+    for y in 0..3 {
+        for x in 5..80 {
+            let shade = if (x + y) % 2 == 0 { 252 } else { 255 };
+            image.put_pixel(x, y, image::Rgba([shade, shade, 255, 255]));
+        }
+    }
+    for y in 20..40 {
+        for x in 30..50 {
+            image.put_pixel(x, y, pattern_pixel(x, y));
+        }
+    }
+    let mut path = test_folder("auto_trim_removes_uniform_borders_as_a_step");
+    path.push("tmp0.png");
+    let mut timeline = Timeline::new(path);
+    timeline
+        .start(&image::DynamicImage::ImageRgba8(image))
+        .unwrap();
+
+    // without tolerance the noisy band is content
+    let bounds = trim_bounds(&timeline.current_image().unwrap().to_rgba8(), 0).unwrap();
+    assert_eq!(
+        (
+            bounds.start_x,
+            bounds.start_y,
+            bounds.offset_x,
+            bounds.offset_y
+        ),
+        (5.0, 0.0, 75.0, 40.0)
+    );
+
+    timeline.trim(4).unwrap();
+    assert_eq!(timeline.current_index(), 1);
+    let trimmed = timeline.current_image().unwrap().to_rgba8();
+    assert_eq!(trimmed.dimensions(), (20, 20));
+    assert_eq!(*trimmed.get_pixel(0, 0), pattern_pixel(30, 20));
+    // nothing left to trim: no new step
+    assert!(timeline.trim(4).is_none());
+    assert_eq!(timeline.undo().unwrap(), timeline.first_path());
+}
+
+#[test]
+fn cursor_layer_follows_crops_and_composes() {
+    let mut backend = FakeBackend::from_pattern(200, 100);
+    backend.add_screen(RgbaImage::from_fn(200, 100, pattern_pixel));
+    // 2x2 arrow: opaque red hotspot, half transparent blue, two clear pixels
+    let pixels = [0xffff0000, 0x800000ff, 0, 0];
+    let image = from_premultiplied_argb(2, 2, &pixels);
+    assert_eq!(*image.get_pixel(1, 0), image::Rgba([0, 0, 255, 128]));
+    backend.set_cursor(CursorImage {
+        x: 250,
+        y: 40,
+        hot_x: 0,
+        hot_y: 0,
+        image,
+    });
+    let screens = backend.list_screens().unwrap();
+    let cursor = backend.cursor().unwrap();
+    assert!(CursorLayer::on_screen(cursor.clone(), &screens[0], false).is_none());
+    let mut layer = CursorLayer::on_screen(cursor, &screens[1], false).unwrap();
+    assert_eq!((layer.x, layer.y), (50.0, 40.0));
+
+    let mut path = test_folder("cursor_layer_follows_crops_and_composes");
+    path.push("tmp0.png");
+    let mut timeline = Timeline::new(path);
+    timeline
+        .start(&image::DynamicImage::ImageRgba8(
+            backend.capture_screen(1).unwrap(),
+        ))
+        .unwrap();
+    timeline.crop(&drag(40.5, 30.0, 30.0, 30.0)).unwrap();
+    assert_eq!(timeline.current_origin(), (40.0, 30.0));
+    let step = timeline.current_image().unwrap().to_rgba8();
+    let composed = layer.compose(&step, timeline.current_origin());
+    assert_eq!(*composed.get_pixel(10, 10), image::Rgba([255, 0, 0, 255]));
+    assert_eq!(composed.get_pixel(11, 10)[2], 128);
+    assert_eq!(*composed.get_pixel(10, 11), *step.get_pixel(10, 11));
+    assert!(layer.contains(timeline.current_origin(), 11.5, 10.5));
+
+    // moved and with the click ring, the base step is untouched
+    layer.x += 5.0;
+    layer.ring = true;
+    let composed = layer.compose(&step, timeline.current_origin());
+    assert_eq!(*composed.get_pixel(15, 10), image::Rgba([255, 0, 0, 255]));
+    assert_ne!(*composed.get_pixel(15, 28), *step.get_pixel(15, 28));
+    assert_eq!(*composed.get_pixel(10, 10), *step.get_pixel(10, 10));
+    assert_eq!(timeline.undo().unwrap(), timeline.first_path());
+    assert_eq!(timeline.current_origin(), (0.0, 0.0));
+}
+
+/* windows that need a few checks before they are reported hidden, or never are */
+struct FakeWindows {
+    visible: Rc<Cell<bool>>,
+    checks_left: Option<u32>,
+}
+
+impl SelfHiding for FakeWindows {
+    fn hide(&mut self) {
+        self.visible.set(false);
+    }
+
+    fn is_hidden(&mut self) -> bool {
+        match &mut self.checks_left {
+            Some(0) => true,
+            Some(checks) => {
+                *checks -= 1;
+                false
+            }
+            None => false,
+        }
+    }
+
+    fn restore(&mut self) {
+        self.visible.set(true);
+    }
+}
+
+#[test]
+fn capture_waits_for_own_windows_to_hide() {
+    let backend = FakeBackend::from_pattern(20, 10);
+    let path = test_folder("capture_waits_for_own_windows_to_hide").join("tmp0.png");
+    let visible = Rc::new(Cell::new(true));
+    // polled as the tick does until the windows are gone, then captured and restored
+    let poll = |wait: &mut HidingWait, windows: &mut FakeWindows| {
+        let start = Instant::now();
+        while !wait.is_ready(windows) {
+            assert!(start.elapsed() < Duration::from_secs(5));
+            std::thread::sleep(HIDDEN_POLL);
+        }
+    };
+
+    let mut windows = FakeWindows {
+        visible: Rc::clone(&visible),
+        checks_left: Some(3),
+    };
+    let mut wait = HidingWait::start(&mut windows, Duration::from_secs(5));
+    assert!(!visible.get());
+    poll(&mut wait, &mut windows);
+    assert_eq!(windows.checks_left, Some(0));
+    capture_fullscreen(&backend, &path).unwrap();
+    windows.restore();
+    assert!(visible.get());
+    assert_eq!(backend.capture_count(), 1);
+
+    // gone right away: ready a frame later, not before
+    let mut windows = FakeWindows {
+        visible: Rc::clone(&visible),
+        checks_left: Some(0),
+    };
+    let mut wait = HidingWait::start(&mut windows, Duration::from_secs(5));
+    assert!(!wait.is_ready(&mut windows));
+    std::thread::sleep(Duration::from_millis(60));
+    assert!(wait.is_ready(&mut windows));
+    windows.restore();
+
+    // never confirmed: the settle delay bounds the wait
+    let mut windows = FakeWindows {
+        visible: Rc::clone(&visible),
+        checks_left: None,
+    };
+    let start = Instant::now();
+    let mut wait = HidingWait::start(&mut windows, Duration::from_millis(100));
+    poll(&mut wait, &mut windows);
+    assert!(start.elapsed() >= Duration::from_millis(100));
+    windows.restore();
+    assert!(visible.get());
+}
+
+#[test]
+fn ocr_output_and_command_line() {
+    let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+1\t1\t0\t0\t0\t0\t0\t0\t400\t200\t-1\t
+4\t1\t1\t1\t1\t0\t20\t10\t200\t30\t-1\t
+5\t1\t1\t1\t1\t1\t20\t10\t90\t30\t96.5\tHello
+5\t1\t1\t1\t1\t2\t120\t10\t100\t30\t95.1\tworld!
+5\t1\t1\t1\t2\t1\t20\t50\t60\t30\t91.0\tSecond
+5\t1\t1\t1\t2\t2\t90\t50\t10\t30\t-1\t 
+5\t1\t2\t1\t1\t1\t20\t120\t80\t30\t88.0\tuser@example.com
+";
+    let words = parse_tsv(tsv);
+    assert_eq!(words.len(), 4);
+    assert_eq!(
+        (words[1].text.as_str(), words[1].left, words[1].width),
+        ("world!", 120, 100)
+    );
+    assert_eq!(
+        words_to_text(&words),
+        "Hello world!\nSecond\n\nuser@example.com"
+    );
+
+    let args = |line: &str| -> Vec<String> { line.split(' ').map(String::from).collect() };
+    assert_eq!(
+        parse_args(&args("ocr --region 10,20,300,40 --lang ita")).unwrap(),
+        Command::Ocr {
+            file: None,
+            region: Some(Region {
+                x: 10,
+                y: 20,
+                width: 300,
+                height: 40
+            }),
+            language: "ita".to_string()
+        }
+    );
+    assert!(parse_args(&args("ocr --region 10,20,0,40")).is_err());
+    assert!(parse_args(&args("ocr --file")).is_err());
+    assert!(parse_args(&args("scan")).is_err());
+    assert_eq!(parse_region("-1920, 0, 10, 10").unwrap().x, -1920);
+}
+
+#[test]
+fn sensitive_text_is_found_reviewed_and_redacted() {
+    let tsv = "level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext
+5\t1\t1\t1\t1\t1\t10\t10\t40\t12\t95\tMail:
+5\t1\t1\t1\t1\t2\t55\t10\t90\t12\t95\tjane.doe@example.org
+5\t1\t1\t1\t2\t1\t10\t30\t30\t12\t95\tCard
+5\t1\t1\t1\t2\t2\t45\t30\t30\t12\t95\t4111
+5\t1\t1\t1\t2\t3\t80\t30\t30\t12\t95\t1111
+5\t1\t1\t1\t2\t4\t115\t30\t30\t12\t95\t1111
+5\t1\t1\t1\t2\t5\t150\t30\t30\t12\t95\t1111
+5\t1\t1\t1\t3\t1\t10\t50\t60\t12\t95\tserver
+5\t1\t1\t1\t3\t2\t75\t50\t70\t12\t95\t192.168.1.20
+5\t1\t1\t1\t4\t1\t10\t70\t60\t12\t95\tversion
+5\t1\t1\t1\t4\t2\t75\t70\t20\t12\t95\t2.1
+";
+    let words = parse_tsv(tsv);
+    let mut detections = find_sensitive(&words, &default_redact_patterns()).unwrap();
+    let found: Vec<(&str, &str)> = detections
+        .iter()
+        .map(|detection| (detection.pattern.as_str(), detection.text.as_str()))
+        .collect();
+    assert_eq!(
+        found,
+        vec![
+            ("Email", "jane.doe@example.org"),
+            ("Card number", "4111 1111 1111 1111"),
+            ("IP address", "192.168.1.20"),
+        ]
+    );
+    // the card number spans its four words
+    assert_eq!(
+        (
+            detections[1].left,
+            detections[1].top,
+            detections[1].width,
+            detections[1].height
+        ),
+        (45, 30, 135, 12)
+    );
+    let invalid = RedactPattern {
+        name: "Broken".to_string(),
+        regex: "(".to_string(),
+    };
+    assert!(find_sensitive(&words, &[invalid]).is_err());
+
+    // the IP address is kept after the review
+    detections[2].enabled = false;
+    let image = image::DynamicImage::ImageRgba8(RgbaImage::from_pixel(
+        200,
+        100,
+        image::Rgba([255, 255, 255, 255]),
+    ));
+    let redacted = redact(&image, &detections, RedactStyle::Solid).to_rgba8();
+    assert_eq!(*redacted.get_pixel(60, 15), image::Rgba([0, 0, 0, 255]));
+    assert_eq!(*redacted.get_pixel(178, 40), image::Rgba([0, 0, 0, 255]));
+    assert_eq!(
+        *redacted.get_pixel(100, 55),
+        image::Rgba([255, 255, 255, 255])
+    );
+    assert_eq!(*redacted.get_pixel(5, 5), image::Rgba([255, 255, 255, 255]));
+
+    let mut path = test_folder("sensitive_text_is_found_reviewed_and_redacted");
+    path.push("tmp0.png");
+    let mut timeline = Timeline::new(path);
+    timeline.start(&image).unwrap();
+    timeline
+        .push(&redact(&image, &detections, RedactStyle::Blur))
+        .unwrap();
+    assert_eq!(timeline.undo().unwrap(), timeline.first_path());
+}
+
+#[test]
+fn barcodes_are_parsed_from_zbar_output() {
+    let xml = "<barcodes xmlns='http://zbar.sourceforge.net/2008/barcode'>
+<source href='/tmp/screen-pds-scan.png'>
+<index num='0'>
+<symbol type='QR-Code' quality='1' orientation='UP'><polygon points='+40,20 +40,120 +140,120 +140,20'/><data><![CDATA[https://example.com/?a=1&b=2]]></data></symbol>
+<symbol type='EAN-13' quality='12'><data format='base64' length='13'><![CDATA[OTc4MDIwMTM3OTYyNA==]]></data></symbol>
+<symbol type='CODE-128' quality='3'><data length='0'/></symbol>
+</index>
+</source>
+</barcodes>";
+    let codes = parse_zbar_xml(xml, 400, 300);
+    assert_eq!(codes.len(), 2);
+    assert_eq!(codes[0].describe(), "QR-Code: https://example.com/?a=1&b=2");
+    assert_eq!(
+        (codes[0].left, codes[0].top, codes[0].width, codes[0].height),
+        (40, 20, 101, 101)
+    );
+    // without a polygon the code is somewhere in the scanned image
+    assert_eq!(codes[1].payload, "9780201379624");
+    assert_eq!(
+        (codes[1].left, codes[1].top, codes[1].width, codes[1].height),
+        (0, 0, 400, 300)
+    );
+}
+
+#[test]
+fn captures_are_aligned_and_compared() {
+    let screen = RgbaImage::from_fn(80, 60, pattern_pixel);
+    let before = image::imageops::crop_imm(&screen, 0, 0, 70, 50).to_image();
+    // the same screen scrolled by a few pixels, with a new red square
+    let mut after = image::imageops::crop_imm(&screen, 3, 2, 70, 50).to_image();
+    for y in 20..25 {
+        for x in 30..35 {
+            after.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
+        }
+    }
+    let shift = align(&before, &after, 8);
+    assert_eq!(shift, (3, 2));
+
+    let (before_canvas, after_canvas) = aligned_pair(&before, &after, shift);
+    assert_eq!(before_canvas.dimensions(), (73, 52));
+    assert_eq!(
+        after_canvas.get_pixel(33, 22),
+        &image::Rgba([255, 0, 0, 255])
+    );
+    assert_eq!(
+        after_canvas.get_pixel(10, 10),
+        before_canvas.get_pixel(10, 10)
+    );
+    // only one of the images reaches the border strips
+    let overlap = 67 * 48;
+    let diff = score(&before_canvas, &after_canvas, &DiffOptions::default());
+    assert_eq!(diff.total_pixels, 73 * 52);
+    assert_eq!(diff.changed_pixels, 2 * (70 * 50 - overlap) + 25);
+    assert_eq!(
+        score(&before_canvas, &before_canvas, &DiffOptions::default()).percent(),
+        0.0
+    );
+
+    let map = heatmap(&before_canvas, &after_canvas, &DiffOptions::default());
+    assert_eq!(map.get_pixel(33, 22)[0], 255);
+    assert_eq!(map.get_pixel(33, 22)[2], 0);
+    let unchanged = map.get_pixel(10, 10);
+    assert!(unchanged[0] == unchanged[1] && unchanged[1] == unchanged[2]);
+
+    let onion = onion_skin(&before_canvas, &after_canvas, 0.5);
+    let expected = (before_canvas.get_pixel(33, 22)[1] as f64 / 2.0).round() as u8;
+    assert_eq!(onion.get_pixel(33, 22)[1], expected);
+    assert_eq!(side_by_side(&before, &after).dimensions(), (148, 50));
+}
+
+#[test]
+fn diff_command_checks_baselines() {
+    let folder = test_folder("diff_command");
+    let baseline = folder.join("baseline.png");
+    let current = folder.join("current.png");
+    let out = folder.join("diff.png");
+    let backend = FakeBackend::from_pattern(10, 10);
+
+    // a black bar on white whose edge moved by a pixel, and a new red square
+    let mut baseline_image = RgbaImage::from_pixel(100, 100, image::Rgba([255, 255, 255, 255]));
+    let mut current_image = baseline_image.clone();
+    for y in 0..100 {
+        for x in 0..40 {
+            baseline_image.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+        }
+        for x in 0..41 {
+            current_image.put_pixel(x, y, image::Rgba([0, 0, 0, 255]));
+        }
+        baseline_image.put_pixel(40, y, image::Rgba([128, 128, 128, 255]));
+        current_image.put_pixel(41, y, image::Rgba([128, 128, 128, 255]));
+    }
+    for y in 60..70 {
+        for x in 60..70 {
+            current_image.put_pixel(x, y, image::Rgba([255, 0, 0, 255]));
+        }
+    }
+    baseline_image.save(&baseline).unwrap();
+    current_image.save(&current).unwrap();
+
+    let command = |line: &str| {
+        let args: Vec<String> =
+            format!("diff {} {} {}", baseline.display(), current.display(), line)
+                .split_whitespace()
+                .map(String::from)
+                .collect();
+        parse_args(&args).unwrap()
+    };
+    match command("--threshold 0.5% --aa-tolerance 0 --ignore-region 60,60,10,10") {
+        Command::Diff {
+            threshold, options, ..
+        } => {
+            assert_eq!(threshold, 0.5);
+            assert_eq!(options.antialiasing, Some(0));
+            assert_eq!(options.ignore.len(), 1);
+        }
+        other => panic!("unexpected {:?}", other),
+    }
+    let args = |line: &str| -> Vec<String> { line.split(' ').map(String::from).collect() };
+    assert!(parse_args(&args("diff one.png")).is_err());
+    assert!(parse_args(&args("diff a.png b.png --threshold 120%")).is_err());
+
+    // the moved edge (200 px) and the square (100 px) are 3% of the image
+    let report = run(
+        &command(&format!("--threshold 2.9% --out {}", out.display())),
+        &backend,
+    );
+    let failure = report.unwrap_err();
+    assert!(failure.message().starts_with("3.00% changed"));
+    assert_eq!(failure.exit_code(), DIFFERENCES_EXIT);
+    assert_eq!(open(&out).unwrap().to_rgba8().get_pixel(65, 65)[1], 0);
+    assert!(run(&command("--threshold 3%"), &backend).is_ok());
+    // without the edge only the square is left, and it can be ignored
+    assert!(run(&command("--threshold 1% --aa-tolerance 0"), &backend).is_ok());
+    assert!(run(&command("--aa-tolerance 0"), &backend).is_err());
+    assert!(run(
+        &command("--aa-tolerance 0 --ignore-region 60,60,10,10"),
+        &backend
+    )
+    .is_ok());
+
+    assert!(run(&command("--update"), &backend).is_ok());
+    assert!(run(&command(""), &backend).is_ok());
+    std::fs::remove_file(&baseline).unwrap();
+    // a missing baseline is an error, not a difference
+    match run(&command(""), &backend) {
+        Err(failure @ Failure::Error(_)) => assert_eq!(failure.exit_code(), ERROR_EXIT),
+        other => panic!("unexpected {:?}", other),
+    }
+    assert!(run(&command("--update"), &backend)
+        .unwrap()
+        .contains("created"));
+    assert!(run(&command(""), &backend).is_ok());
+}
+
+#[test]
+fn scrolled_frames_are_stitched() {
+    let page = RgbaImage::from_fn(60, 400, pattern_pixel);
+    // a window with a fixed toolbar and status bar around 80 rows of the page
+    let frame = |offset: u32| {
+        RgbaImage::from_fn(60, 100, |x, y| match y {
+            0..=9 => image::Rgba([255, 0, 0, 255]),
+            90..=99 => image::Rgba([0, 0, 255, 255]),
+            _ => *page.get_pixel(x, y - 10 + offset),
+        })
+    };
+    assert_eq!(
+        find_scroll(&frame(0), &frame(30)),
+        Some(Scroll {
+            header: 10,
+            footer: 10,
+            rows: 30
+        })
+    );
+    assert_eq!(find_scroll(&frame(30), &frame(30)).unwrap().rows, 0);
+    assert_eq!(find_scroll(&frame(0), &frame(75)), None);
+
+    let mut stitcher = Stitcher::new(frame(0));
+    assert_eq!(stitcher.push(frame(30)).unwrap(), 30);
+    assert_eq!(stitcher.push(frame(30)).unwrap(), 0);
+    assert_eq!(stitcher.push(frame(75)).unwrap(), 45);
+    assert!(stitcher.push(frame(150)).is_err());
+    assert!(stitcher.push(RgbaImage::new(60, 50)).is_err());
+    let tall = stitcher.finish();
+    assert_eq!(tall.dimensions(), (60, 10 + 155 + 10));
+    assert_eq!(tall.get_pixel(5, 5), &image::Rgba([255, 0, 0, 255]));
+    assert_eq!(tall.get_pixel(5, 10 + 154), page.get_pixel(5, 154));
+    assert_eq!(tall.get_pixel(5, 170), &image::Rgba([0, 0, 255, 255]));
+
+    // scrolling by itself to the end of the page gives the whole page back
+    let backend = FakeBackend::from_page(page.clone(), 100, 10);
+    let options = ScrollOptions {
+        interval: Duration::ZERO,
+        idle: Duration::ZERO,
+        auto_scroll: true,
+        ..ScrollOptions::default()
+    };
+    let region = Region {
+        x: 0,
+        y: 0,
+        width: 60,
+        height: 100,
+    };
+    let running = AtomicBool::new(false);
+    assert_eq!(
+        scrolling_capture(&backend, region, &options, &running).unwrap(),
+        ScrollCapture {
+            image: page.clone(),
+            error: None
+        }
+    );
+
+    // scrolled too far between two frames: what was stitched comes back with the reason
+    let backend = FakeBackend::from_page(page.clone(), 100, 30);
+    let partial = scrolling_capture(&backend, region, &options, &running).unwrap();
+    assert_eq!(
+        partial.image,
+        image::imageops::crop_imm(&page, 0, 0, 60, 100).to_image()
+    );
+    assert!(partial.error.unwrap().contains("do not overlap"));
+
+    // stopped by the user: the frames taken so far, without an error
+    let backend = FakeBackend::from_page(page.clone(), 100, 10);
+    let stopped = scrolling_capture(&backend, region, &options, &AtomicBool::new(true)).unwrap();
+    assert_eq!(
+        stopped,
+        ScrollCapture {
+            image: image::imageops::crop_imm(&page, 0, 0, 60, 100).to_image(),
+            error: None
+        }
+    );
+}
+
+#[test]
+fn captures_are_laid_out_in_a_collage() {
+    let red = image::Rgba([255, 0, 0, 255]);
+    let white = image::Rgba([255, 255, 255, 255]);
+    let images = vec![
+        RgbaImage::from_pixel(40, 20, red),
+        RgbaImage::from_pixel(20, 30, red),
+        RgbaImage::from_pixel(30, 10, red),
+    ];
+    let mut options = CollageOptions {
+        layout: CollageLayout::Horizontal,
+        spacing: 5,
+        background: white,
+        label_height: 0,
+    };
+    let row = collage(&images, &options);
+    assert_eq!(
+        row.image.dimensions(),
+        (5 + 40 + 5 + 20 + 5 + 30 + 5, 5 + 30 + 5)
+    );
+    // the short images are centred in the row
+    assert_eq!(row.image.get_pixel(5, 10), &red);
+    assert_eq!(row.image.get_pixel(5, 9), &white);
+    assert_eq!(row.image.get_pixel(75, 15), &red);
+
+    options.layout = CollageLayout::Vertical;
+    assert_eq!(collage(&images, &options).image.dimensions(), (50, 80));
+
+    options.layout = CollageLayout::Grid(square_columns(3));
+    options.label_height = 12;
+    let grid = collage(&images, &options);
+    // 2 columns: 40 and 20 wide; 2 rows: 30 + 12 and 10 + 12 tall
+    assert_eq!(
+        grid.image.dimensions(),
+        (5 + 40 + 5 + 20 + 5, 5 + 42 + 5 + 22 + 5)
+    );
+    assert_eq!(grid.labels.len(), 3);
+    assert_eq!(grid.labels[0], (5, 5 + 5 + 20, 40, 12));
+    assert_eq!(grid.labels[2], (5, 52 + 10, 40, 12));
+    assert_eq!(grid.image.get_pixel(10, 52), &red);
+    assert_eq!(grid.image.get_pixel(50, 52), &white);
+}
+
+#[test]
+fn open_captures_keep_their_own_timelines() {
+    let folder = test_folder("open_captures_keep_their_own_timelines");
+    let first_session = session_path(&folder, 1);
+    let second_session = session_path(&folder, 2);
+    assert_ne!(first_session.parent(), second_session.parent());
+    let mut timelines = Vec::new();
+    for (path, width) in [(first_session, 64), (second_session, 48)] {
+        std::fs::create_dir_all(path.parent().unwrap()).unwrap();
+        let backend = FakeBackend::from_pattern(width, 40);
+        let mut timeline = Timeline::new(path);
+        capture_fullscreen(&backend, &timeline.first_path()).unwrap();
+        timeline.restart();
+        timelines.push(timeline);
+    }
+
+    // editing one capture leaves the other one and its history alone
+    timelines[0].crop(&drag(0.0, 0.0, 20.0, 10.0)).unwrap();
+    timelines[1].crop(&drag(4.0, 4.0, 30.0, 30.0)).unwrap();
+    timelines[1].crop(&drag(0.0, 0.0, 8.0, 8.0)).unwrap();
+    assert_eq!(timelines[0].current_image().unwrap().width(), 20);
+    assert_eq!(timelines[1].current_image().unwrap().width(), 8);
+    assert_eq!(timelines[0].undo(), Some(timelines[0].first_path()));
+    assert_eq!(timelines[0].current_image().unwrap().width(), 64);
+    assert_eq!(timelines[1].last_index(), 2);
+
+    // saving all of them in the same second does not overwrite the previous files
+    let out = test_folder("open_captures_keep_their_own_timelines_out");
+    let mut saved = Vec::new();
+    for timeline in &timelines {
+        let path = free_path(&out.join("capture.png"));
+        write_image(&timeline.current_image().unwrap(), 0, &path, WHITE).unwrap();
+        saved.push(path);
+    }
+    assert_eq!(
+        saved,
+        vec![out.join("capture.png"), out.join("capture (1).png")]
+    );
+    assert_eq!(
+        free_path(&out.join("capture.png")),
+        out.join("capture (2).png")
+    );
+}
+
+#[test]
+fn resize_rotate_and_flip_as_steps() {
+    let backend = FakeBackend::from_pattern(40, 20);
+    let mut timeline = start_timeline(&backend, "resize_rotate_and_flip_as_steps");
+    let original = timeline.current_image().unwrap().to_rgba8();
+
+    assert_eq!(target_size((40, 20), 50.0, 0.0, true, true), Ok((20, 10)));
+    assert_eq!(
+        target_size((40, 20), 100.0, 0.0, false, true),
+        Ok((100, 50))
+    );
+    assert_eq!(target_size((40, 20), 30.0, 7.0, false, false), Ok((30, 7)));
+    assert_eq!(
+        target_size((40, 20), 150.0, 50.0, true, false),
+        Ok((60, 10))
+    );
+    // too large for memory: refused before anything is allocated
+    assert!(target_size((40, 20), 100000.0, 0.0, true, true).is_err());
+    assert!(target_size((20, 40), MAX_DIMENSION as f64, 0.0, false, true).is_err());
+    let huge = GeometryEdit::Resize {
+        width: MAX_DIMENSION + 1,
+        height: 1,
+        filter: RESIZE_FILTERS[0].1,
+    };
+    assert!(huge.apply(&original).is_err());
+    // a slight turn of a full width image needs a wider canvas
+    let wide = RgbaImage::new(MAX_DIMENSION, 300);
+    let tilt = GeometryEdit::Rotate {
+        degrees: 2.0,
+        background: image::Rgba([0, 0, 0, 0]),
+    };
+    assert!(tilt.apply(&wide).is_err());
+
+    // nearest keeps every source pixel as a block
+    let resize = GeometryEdit::Resize {
+        width: 80,
+        height: 40,
+        filter: RESIZE_FILTERS[0].1,
+    };
+    assert!(!timeline.is_transformed());
+    let path = timeline
+        .push_transformed(&image::DynamicImage::ImageRgba8(
+            resize.apply(&original).unwrap(),
+        ))
+        .unwrap();
+    assert!(timeline.is_transformed());
+    let resized = open(&path).unwrap().to_rgba8();
+    assert_eq!(resized.dimensions(), (80, 40));
+    assert_eq!(resized.get_pixel(7, 3), original.get_pixel(3, 1));
+
+    let turned = GeometryEdit::RotateQuarter(1).apply(&original).unwrap();
+    assert_eq!(turned.dimensions(), (20, 40));
+    // clockwise: the bottom left corner goes to the top left
+    assert_eq!(turned.get_pixel(0, 0), original.get_pixel(0, 19));
+    assert_eq!(
+        GeometryEdit::RotateQuarter(4).apply(&original),
+        Ok(original.clone())
+    );
+
+    let flipped = GeometryEdit::FlipHorizontal.apply(&original).unwrap();
+    assert_eq!(flipped.get_pixel(0, 5), original.get_pixel(39, 5));
+    let flipped = GeometryEdit::FlipVertical.apply(&original).unwrap();
+    assert_eq!(flipped.get_pixel(3, 0), original.get_pixel(3, 19));
+
+    // right angles are exact, other angles grow the canvas and fill the corners
+    let background = image::Rgba([0, 0, 255, 255]);
+    assert_eq!(
+        rotate(&original, -90.0, background),
+        rotate_quarter(&original, 3)
+    );
+    let square = RgbaImage::from_pixel(20, 20, image::Rgba([255, 0, 0, 255]));
+    let tilted = GeometryEdit::Rotate {
+        degrees: 45.0,
+        background,
+    }
+    .apply(&square)
+    .unwrap();
+    assert_eq!(tilted.dimensions(), (29, 29));
+    assert_eq!(tilted.get_pixel(0, 0), &background);
+    assert_eq!(tilted.get_pixel(14, 14), &image::Rgba([255, 0, 0, 255]));
+
+    // steps made from a transformed one are transformed too, until it is undone
+    let crop = drag(0.0, 0.0, 10.0, 10.0);
+    timeline.crop(&crop).unwrap();
+    assert!(timeline.is_transformed());
+    timeline.undo();
+    assert_eq!(timeline.undo(), Some(timeline.first_path()));
+    assert!(!timeline.is_transformed());
+    assert_eq!(timeline.current_image().unwrap().width(), 40);
+    timeline.redo();
+    assert!(timeline.is_transformed());
+    timeline.undo();
+    timeline.crop(&crop).unwrap();
+    assert!(!timeline.is_transformed());
+}
+
+#[test]
+fn output_effects_decorate_the_capture() {
+    let red = image::Rgba([255, 0, 0, 255]);
+    let capture = RgbaImage::from_pixel(40, 30, red);
+
+    // only padding on a solid background
+    let mut effects = Effects {
+        enabled: true,
+        padding: 10,
+        background: "#FFFFFF".to_string(),
+        corner_radius: 0,
+        shadow_opacity: 0,
+        ..Effects::default()
+    };
+    let padded = effects.apply(&capture).unwrap();
+    assert_eq!(padded.dimensions(), (60, 50));
+    assert_eq!(padded.get_pixel(9, 9), &image::Rgba([255, 255, 255, 255]));
+    assert_eq!(padded.get_pixel(10, 10), &red);
+
+    // the border grows the image, the gradient goes from top to bottom
+    effects.border_width = 2;
+    effects.border_color = "#0000FF".to_string();
+    effects.gradient_end = "#000000".to_string();
+    let bordered = effects.apply(&capture).unwrap();
+    assert_eq!(bordered.dimensions(), (64, 54));
+    assert_eq!(bordered.get_pixel(10, 10), &image::Rgba([0, 0, 255, 255]));
+    assert_eq!(bordered.get_pixel(12, 12), &red);
+    assert_eq!(bordered.get_pixel(0, 0), &image::Rgba([255, 255, 255, 255]));
+    assert_eq!(bordered.get_pixel(0, 53), &image::Rgba([0, 0, 0, 255]));
+
+    // rounded corners and the shadow on a transparent background
+    let shadowed = Effects {
+        enabled: true,
+        padding: 0,
+        ..Effects::default()
+    }
+    .apply(&capture)
+    .unwrap();
+    // the shadow needs 16 pixels on the sides, 8 above and 24 below
+    assert_eq!(shadowed.dimensions(), (16 + 40 + 16, 8 + 30 + 24));
+    assert_eq!(shadowed.get_pixel(0, 0)[3], 0);
+    assert_eq!(shadowed.get_pixel(16 + 20, 8 + 15), &red);
+    // the rounded corner lets the shadow through, below the image there is only shadow
+    let corner = shadowed.get_pixel(16, 8);
+    assert!(corner[1] == 0 && corner[3] < 255);
+    let below = shadowed.get_pixel(16 + 20, 8 + 30 + 4);
+    assert!(below[0] == 0 && below[3] > 0 && below[3] < 255);
+
+    let mut square = RgbaImage::from_pixel(20, 20, red);
+    round_corners(&mut square, 6);
+    assert_eq!(square.get_pixel(0, 0)[3], 0);
+    assert_eq!(square.get_pixel(19, 19)[3], 0);
+    assert_eq!(square.get_pixel(6, 0)[3], 255);
+    assert_eq!(square.get_pixel(10, 10)[3], 255);
+
+    let preview = effects.scaled(0.5);
+    assert_eq!((preview.padding, preview.border_width), (5, 1));
+    effects.background = "white".to_string();
+    assert!(effects.apply(&capture).is_err());
+
+    // formats without transparency put the transparent padding and corners on the
+    // background, or on white when there is none
+    let decorated = image::DynamicImage::ImageRgba8(
+        Effects {
+            enabled: true,
+            ..Effects::default()
+        }
+        .apply(&capture)
+        .unwrap(),
+    );
+    assert_eq!(decorated.to_rgba8().get_pixel(0, 0)[3], 0);
+    let folder = test_folder("output_effects_flattened");
+    for (index, name) in [(1, "decorated.jpg"), (2, "decorated.gif")] {
+        let path = folder.join(name);
+        write_image(&decorated, index, &path, Effects::default().matte()).unwrap();
+        let corner = open(&path).unwrap().to_rgba8();
+        assert!(corner.get_pixel(0, 0).0.iter().all(|&value| value > 245));
+    }
+    let blue = Effects {
+        enabled: true,
+        background: "#0000FF".to_string(),
+        ..Effects::default()
+    };
+    assert_eq!(blue.matte(), image::Rgba([0, 0, 255, 255]));
+    assert_eq!(
+        Effects {
+            enabled: false,
+            ..blue.clone()
+        }
+        .matte(),
+        WHITE
+    );
+    let half = RgbaImage::from_pixel(1, 1, image::Rgba([255, 0, 0, 128]));
+    assert_eq!(
+        flatten(&image::DynamicImage::ImageRgba8(half), blue.matte()).get_pixel(0, 0),
+        &image::Rgb([128, 0, 127])
+    );
+}
+
+#[test]
+fn clipboard_flavours_of_a_capture() {
+    assert_eq!(
+        file_uri(Path::new("/home/me/my shots/ùa#1.png")),
+        "file:///home/me/my%20shots/%C3%B9a%231.png"
+    );
+    assert_eq!(
+        file_uri(Path::new("C:\\Users\\me\\a b.png")),
+        "file:///C:/Users/me/a%20b.png"
+    );
+    assert_eq!(
+        markdown_image(Path::new("/tmp/shots/a.png"), "capture"),
+        "![capture](/tmp/shots/a.png)"
+    );
+    assert_eq!(
+        markdown_image(Path::new("/tmp/my shots/a (1).png"), "cap [1]"),
+        "![cap \\[1\\]](</tmp/my shots/a (1).png>)"
+    );
+    assert_eq!(
+        escape_html("a \"<b>\" & c"),
+        "a &quot;&lt;b&gt;&quot; &amp; c"
+    );
+    assert_eq!(
+        html_image(&[1, 2, 3], 4, 5, "<x>"),
+        "<img src=\"data:image/png;base64,AQID\" width=\"4\" height=\"5\" alt=\"&lt;x&gt;\">"
+    );
+
+    for policy in [CopyPolicy::EveryStep, CopyPolicy::OnSave, CopyPolicy::Never] {
+        assert_eq!(CopyPolicy::from_json(&policy.to_json()), policy);
+        assert_eq!(CopyPolicy::from_index(policy.to_index()), policy);
+    }
+    assert_eq!(CopyPolicy::from_json("unknown"), CopyPolicy::EveryStep);
+    assert_eq!(CopyPolicy::from_index(9), CopyPolicy::EveryStep);
+    let formats: Vec<CopyFormat> = (0..5).map(CopyFormat::from_index).collect();
+    assert_eq!(
+        formats,
+        vec![
+            CopyFormat::Png,
+            CopyFormat::Html,
+            CopyFormat::FileUri,
+            CopyFormat::Markdown,
+            CopyFormat::Png
+        ]
+    );
+    let needs: Vec<bool> = formats
+        .iter()
+        .map(|format| format.needs_saved_file())
+        .collect();
+    assert_eq!(needs, vec![false, false, true, true, false]);
+
+    // every flavour goes on the clipboard, the chosen one first, and plain text carries it
+    let image = image::DynamicImage::ImageRgba8(RgbaImage::new(3, 2));
+    let saved = Path::new("/tmp/shots/capture 1.png");
+    let payload = ClipboardPayload::new(&image, Some(saved)).unwrap();
+    assert_eq!(open_png(&payload.png).dimensions(), (3, 2));
+    assert_eq!(
+        payload.plain_text(CopyFormat::FileUri).unwrap(),
+        "file:///tmp/shots/capture%201.png"
+    );
+    assert_eq!(
+        payload.plain_text(CopyFormat::Markdown).unwrap(),
+        "![capture 1](</tmp/shots/capture 1.png>)"
+    );
+    assert!(payload
+        .plain_text(CopyFormat::Html)
+        .unwrap()
+        .contains("alt=\"capture 1\""));
+    assert_eq!(payload.plain_text(CopyFormat::Png), None);
+    let targets = payload.into_targets(CopyFormat::FileUri);
+    let types: Vec<&str> = targets.iter().map(|(target, _)| *target).collect();
+    assert_eq!(
+        types,
+        vec![
+            URI_LIST_TARGET,
+            PNG_TARGET,
+            HTML_TARGET,
+            MARKDOWN_TARGET,
+            TEXT_TARGET
+        ]
+    );
+    assert_eq!(
+        targets[0].1,
+        b"file:///tmp/shots/capture%201.png\r\n".to_vec()
+    );
+
+    // without a saved file there is nothing to point at
+    let unsaved = ClipboardPayload::new(&image, None).unwrap();
+    assert_eq!(unsaved.plain_text(CopyFormat::Markdown), None);
+    let types: Vec<&str> = unsaved
+        .into_targets(CopyFormat::Png)
+        .into_iter()
+        .map(|(target, _)| target)
+        .collect();
+    assert_eq!(types, vec![PNG_TARGET, HTML_TARGET]);
+}
+
+fn open_png(bytes: &[u8]) -> RgbaImage {
+    image::load_from_memory(bytes).unwrap().to_rgba8()
+}